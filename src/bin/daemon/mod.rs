@@ -0,0 +1,128 @@
+//! Command line and connection setup shared by the daemons.
+//!
+//! Each daemon takes `<serial-port | --tcp <addr>> [--baud <rate>]` plus
+//! its own options.
+
+use std::future::Future;
+use std::process::ExitCode;
+
+use meshcore::transport::Transport;
+use meshcore::transport::serial::{DEFAULT_BAUD_RATE, SerialConfig};
+use meshcore::{Result, SerialTransport, StreamTransport};
+use tokio::net::TcpStream;
+
+/// Where the radio is connected.
+pub enum Device {
+    Serial { port: String, baud_rate: u32 },
+    Tcp(String),
+}
+
+impl Device {
+    /// Opens the transport to the radio.
+    pub async fn transport(self) -> Result<Box<dyn Transport>> {
+        Ok(match self {
+            Self::Serial { port, baud_rate } => Box::new(SerialTransport::new(
+                SerialConfig::new(port).baud_rate(baud_rate),
+            )),
+            Self::Tcp(addr) => Box::new(StreamTransport::new(TcpStream::connect(addr).await?)),
+        })
+    }
+}
+
+/// Why the command line was not accepted.
+pub enum ArgsError {
+    /// `-h` or `--help` was given.
+    Help,
+    /// The arguments are invalid.
+    Invalid(String),
+}
+
+impl From<&str> for ArgsError {
+    fn from(message: &str) -> Self {
+        Self::Invalid(message.to_string())
+    }
+}
+
+impl From<String> for ArgsError {
+    fn from(message: String) -> Self {
+        Self::Invalid(message)
+    }
+}
+
+/// Parses the device arguments from the command line.
+///
+/// Other options are passed to `option` with the remaining arguments; it
+/// returns `Ok(false)` for options it does not know.
+pub fn parse_args(
+    mut option: impl FnMut(
+        &str,
+        &mut dyn Iterator<Item = String>,
+    ) -> std::result::Result<bool, ArgsError>,
+) -> std::result::Result<Device, ArgsError> {
+    let mut args = std::env::args().skip(1);
+    let mut port = None;
+    let mut tcp = None;
+    let mut baud_rate = DEFAULT_BAUD_RATE;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--baud" => {
+                baud_rate = args
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .ok_or("--baud needs a number")?;
+            }
+            "--tcp" if port.is_none() && tcp.is_none() => {
+                tcp = Some(args.next().ok_or("--tcp needs an address")?);
+            }
+            "-h" | "--help" => return Err(ArgsError::Help),
+            _ if port.is_none() && tcp.is_none() && !arg.starts_with('-') => port = Some(arg),
+            _ if option(&arg, &mut args)? => {}
+            _ => return Err(format!("unexpected argument: {arg}").into()),
+        }
+    }
+
+    match (port, tcp) {
+        (Some(port), _) => Ok(Device::Serial { port, baud_rate }),
+        (_, Some(addr)) => Ok(Device::Tcp(addr)),
+        _ => Err("missing serial port or --tcp address".into()),
+    }
+}
+
+/// Runs a daemon: sets up logging, parses the command line and maps the
+/// outcome to the exit code.
+pub async fn main<A, F>(
+    usage: &str,
+    parse: impl FnOnce() -> std::result::Result<A, ArgsError>,
+    run: impl FnOnce(A) -> F,
+) -> ExitCode
+where
+    F: Future<Output = Result<()>>,
+{
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()),
+        )
+        .init();
+
+    let args = match parse() {
+        Ok(args) => args,
+        Err(ArgsError::Help) => {
+            println!("usage: {usage}");
+            return ExitCode::SUCCESS;
+        }
+        Err(ArgsError::Invalid(message)) => {
+            eprintln!("error: {message}");
+            eprintln!("usage: {usage}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
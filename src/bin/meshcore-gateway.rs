@@ -0,0 +1,54 @@
+//! HTTP/JSON gateway daemon exposing one `MeshCore` radio.
+//!
+//! Usage: `meshcore-gateway <serial-port | --tcp <addr>> [--listen <addr>] [--baud <rate>]`
+//!
+//! See [`meshcore::gateway`] for the endpoints.
+
+#[path = "daemon/mod.rs"]
+mod daemon;
+
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use meshcore::gateway::Gateway;
+use meshcore::{MeshCore, Result};
+use tokio::net::TcpListener;
+
+use crate::daemon::{ArgsError, Device};
+
+/// Default address to accept HTTP requests on.
+const DEFAULT_LISTEN: &str = "127.0.0.1:8080";
+
+const USAGE: &str =
+    "meshcore-gateway <serial-port | --tcp <addr>> [--listen <addr>] [--baud <rate>]";
+
+struct Args {
+    device: Device,
+    listen: String,
+}
+
+fn parse_args() -> std::result::Result<Args, ArgsError> {
+    let mut listen = DEFAULT_LISTEN.to_string();
+    let device = daemon::parse_args(|arg, args| {
+        match arg {
+            "--listen" => listen = args.next().ok_or("--listen needs an address")?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    Ok(Args { device, listen })
+}
+
+async fn run(args: Args) -> Result<()> {
+    let mut client = MeshCore::new(args.device.transport().await?);
+    let info = client.connect().await?;
+    tracing::info!("connected to {}, listening on {}", info.name, args.listen);
+
+    let gateway = Gateway::new(Arc::new(client));
+    gateway.serve(TcpListener::bind(&args.listen).await?).await
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    daemon::main(USAGE, parse_args, run).await
+}
@@ -0,0 +1,99 @@
+//! MQTT bridge daemon for one `MeshCore` radio.
+//!
+//! Usage: `meshcore-mqtt <serial-port | --tcp <addr>> [--broker <host:port>] [--prefix <prefix>]
+//! [--stats <secs>] [--baud <rate>]`
+//!
+//! Broker credentials are read from `MQTT_USERNAME` and `MQTT_PASSWORD`.
+//! See [`meshcore::mqtt`] for the topics.
+
+#[path = "daemon/mod.rs"]
+mod daemon;
+
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
+
+use meshcore::mqtt::{DEFAULT_PREFIX, MqttBridge};
+use meshcore::{MeshCore, Result};
+use rumqttc::MqttOptions;
+
+use crate::daemon::{ArgsError, Device};
+
+/// Default MQTT broker.
+const DEFAULT_BROKER: &str = "localhost:1883";
+
+const USAGE: &str = "meshcore-mqtt <serial-port | --tcp <addr>> [--broker <host:port>] \
+                     [--prefix <prefix>] [--stats <secs>] [--baud <rate>]";
+
+struct Args {
+    device: Device,
+    broker_host: String,
+    broker_port: u16,
+    prefix: String,
+    stats_secs: u64,
+}
+
+fn parse_args() -> std::result::Result<Args, ArgsError> {
+    let mut broker = DEFAULT_BROKER.to_string();
+    let mut prefix = DEFAULT_PREFIX.to_string();
+    let mut stats_secs = 60;
+    let device = daemon::parse_args(|arg, args| {
+        match arg {
+            "--broker" => broker = args.next().ok_or("--broker needs an address")?,
+            "--prefix" => prefix = args.next().ok_or("--prefix needs a topic prefix")?,
+            "--stats" => {
+                stats_secs = args
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .ok_or("--stats needs a number of seconds (0 disables)")?;
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+
+    let (broker_host, broker_port) = match broker.rsplit_once(':') {
+        Some((host, port)) => (
+            host.to_string(),
+            port.parse()
+                .map_err(|_| format!("invalid broker port: {port}"))?,
+        ),
+        None => (broker, 1883),
+    };
+
+    Ok(Args {
+        device,
+        broker_host,
+        broker_port,
+        prefix,
+        stats_secs,
+    })
+}
+
+async fn run(args: Args) -> Result<()> {
+    let mut client = MeshCore::new(args.device.transport().await?);
+    let info = client.connect().await?;
+    tracing::info!("connected to {}", info.name);
+
+    let mut options = MqttOptions::new(
+        format!("meshcore-{}", &info.public_key.to_hex()[..12]),
+        args.broker_host,
+        args.broker_port,
+    );
+    options.set_keep_alive(Duration::from_secs(30));
+    if let Ok(username) = std::env::var("MQTT_USERNAME") {
+        options.set_credentials(username, std::env::var("MQTT_PASSWORD").unwrap_or_default());
+    }
+
+    let stats = (args.stats_secs > 0).then(|| Duration::from_secs(args.stats_secs));
+    MqttBridge::new(Arc::new(client), options)
+        .prefix(args.prefix)
+        .stats_interval(stats)
+        .run()
+        .await
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    daemon::main(USAGE, parse_args, run).await
+}
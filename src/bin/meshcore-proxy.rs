@@ -0,0 +1,46 @@
+//! Proxy daemon sharing one `MeshCore` radio between multiple TCP clients.
+//!
+//! Usage: `meshcore-proxy <serial-port | --tcp <addr>> [--listen <addr>] [--baud <rate>]`
+
+#[path = "daemon/mod.rs"]
+mod daemon;
+
+use std::process::ExitCode;
+
+use meshcore::Result;
+use meshcore::proxy::Proxy;
+use tokio::net::TcpListener;
+
+use crate::daemon::{ArgsError, Device};
+
+/// Default address to accept clients on.
+const DEFAULT_LISTEN: &str = "127.0.0.1:5000";
+
+const USAGE: &str = "meshcore-proxy <serial-port | --tcp <addr>> [--listen <addr>] [--baud <rate>]";
+
+struct Args {
+    device: Device,
+    listen: String,
+}
+
+fn parse_args() -> std::result::Result<Args, ArgsError> {
+    let mut listen = DEFAULT_LISTEN.to_string();
+    let device = daemon::parse_args(|arg, args| {
+        match arg {
+            "--listen" => listen = args.next().ok_or("--listen needs an address")?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    Ok(Args { device, listen })
+}
+
+async fn run(args: Args) -> Result<()> {
+    let proxy = Proxy::new(args.device.transport().await?).start().await?;
+    proxy.serve(TcpListener::bind(&args.listen).await?).await
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    daemon::main(USAGE, parse_args, run).await
+}
@@ -0,0 +1,335 @@
+//! Command-line client for `MeshCore` devices.
+//!
+//! Usage: `meshcore [--port <path> | --tcp <addr>] [--json] <command>`
+//!
+//! Without `--port` or `--tcp`, the first device found on a USB serial
+//! port is used. `meshcore chat` starts an interactive shell and, with
+//! the `tui` feature, `meshcore tui` a full-screen terminal interface.
+
+mod output;
+mod repl;
+#[cfg(feature = "tui")]
+mod tui;
+
+use std::process::ExitCode;
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
+use meshcore::transport::Transport;
+use meshcore::transport::discovery::{DEFAULT_PROBE_TIMEOUT, discover_devices_with};
+use meshcore::transport::serial::{DEFAULT_BAUD_RATE, SerialConfig};
+use meshcore::{Error, Event, MeshCore, RadioConfig, Result, SerialTransport, StreamTransport};
+use tokio::net::TcpStream;
+
+use crate::output::Output;
+
+/// Client over any transport selected on the command line.
+type Client = MeshCore<Box<dyn Transport>>;
+
+#[derive(Parser)]
+#[command(
+    name = "meshcore",
+    version,
+    about = "Command-line client for MeshCore devices"
+)]
+struct Args {
+    /// Serial port of the device (auto-detected if neither --port nor --tcp is given)
+    #[arg(short, long, conflicts_with = "tcp")]
+    port: Option<String>,
+
+    /// Address of a TCP device or meshcore-proxy (host:port)
+    #[arg(short, long)]
+    tcp: Option<String>,
+
+    /// Serial baud rate
+    #[arg(short, long, default_value_t = DEFAULT_BAUD_RATE)]
+    baud: u32,
+
+    /// Print JSON instead of text
+    #[arg(short, long)]
+    json: bool,
+
+    /// Seconds to wait for remote responses
+    #[arg(long, default_value_t = 30)]
+    timeout: u64,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Show device information
+    Infos,
+    /// List contacts
+    Contacts,
+    /// Send a private message to a contact (name, name prefix or public key)
+    Msg {
+        contact: String,
+        #[arg(required = true, num_args = 1..)]
+        text: Vec<String>,
+    },
+    /// Send a message to a channel
+    Chan {
+        index: u8,
+        #[arg(required = true, num_args = 1..)]
+        text: Vec<String>,
+    },
+    /// Print waiting messages
+    Recv {
+        /// Wait for a message if none is waiting
+        #[arg(short, long)]
+        wait: bool,
+        /// Keep printing messages as they arrive
+        #[arg(short, long, conflicts_with = "wait")]
+        follow: bool,
+    },
+    /// Change device settings
+    Set {
+        #[command(subcommand)]
+        setting: Setting,
+    },
+    /// Trace a path through repeaters (hex hashes, e.g. "a1,b2,c3")
+    Trace { path: String },
+    /// Show core, radio and packet statistics
+    Stats,
+    /// Show telemetry of the device or of a contact
+    Telemetry { contact: Option<String> },
+    /// Print the device's private key
+    ExportKey,
+    /// Interactive chat shell
+    #[command(alias = "repl")]
+    Chat,
+    /// Full-screen terminal interface
+    #[cfg(feature = "tui")]
+    Tui,
+}
+
+#[derive(Subcommand)]
+enum Setting {
+    /// Set radio parameters
+    Radio {
+        /// Frequency in MHz
+        frequency: f64,
+        /// Bandwidth in kHz
+        bandwidth: f64,
+        /// Spreading factor (6-12)
+        spreading_factor: u8,
+        /// Coding rate (5-8)
+        coding_rate: u8,
+    },
+}
+
+/// Opens the transport selected on the command line and connects.
+async fn connect(args: &Args) -> Result<Client> {
+    let transport: Box<dyn Transport> = if let Some(addr) = &args.tcp {
+        Box::new(StreamTransport::new(TcpStream::connect(addr).await?))
+    } else {
+        let config = if let Some(port) = &args.port {
+            SerialConfig::new(port.clone()).baud_rate(args.baud)
+        } else {
+            // Connect with the settings the device answered with
+            let probe = |port| SerialConfig::new(port).baud_rate(args.baud);
+            discover_devices_with(probe, DEFAULT_PROBE_TIMEOUT)
+                .await?
+                .into_iter()
+                .next()
+                .ok_or(Error::NoDeviceFound)?
+                .config
+        };
+        Box::new(SerialTransport::new(config))
+    };
+
+    let mut client = MeshCore::new(transport);
+    client.connect().await?;
+    Ok(client)
+}
+
+/// Returns the current Unix time.
+fn now() -> u32 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| u32::try_from(d.as_secs()).unwrap_or(u32::MAX))
+}
+
+/// Parses repeater hashes given as hex, optionally separated by commas.
+fn parse_path(path: &str) -> Result<Vec<u8>> {
+    hex::decode(path.replace(',', "")).map_err(|e| Error::Protocol {
+        message: format!("invalid path {path:?}: {e}"),
+    })
+}
+
+/// Prints waiting messages, optionally waiting for more.
+async fn recv(client: &Client, out: &Output, wait: bool, follow: bool) -> Result<()> {
+    // Sender names are resolved from the contact cache
+    client.get_contacts().await?;
+
+    // Subscribe before fetching so a message announced in between is not
+    // missed. Fetched messages are printed from the subscription.
+    let mut subscription = client.subscribe();
+    let fetched = client.fetch_messages().await?.len();
+    // Without --follow, print the waiting messages or, with --wait, the
+    // first one to arrive
+    let mut remaining = fetched.max(usize::from(wait));
+    if !follow && remaining == 0 {
+        return Ok(());
+    }
+
+    loop {
+        match subscription.recv().await {
+            Some(Event::MessagesWaiting) => {
+                client.fetch_messages().await?;
+            }
+            Some(event @ (Event::ContactMessage(_) | Event::ChannelMessage(_))) => {
+                out.message(client, &event).await;
+                remaining = remaining.saturating_sub(1);
+                if !follow && remaining == 0 {
+                    return Ok(());
+                }
+            }
+            Some(_) => {}
+            None => return Err(Error::ChannelClosed),
+        }
+    }
+}
+
+async fn telemetry(
+    client: &Client,
+    out: &Output,
+    contact: Option<&str>,
+    timeout: Duration,
+) -> Result<()> {
+    let Some(contact) = contact else {
+        out.telemetry(&client.get_self_telemetry().await?);
+        return Ok(());
+    };
+
+    let key = client.resolve_contact(contact).await?;
+    let mut subscription = client.subscribe();
+    client.request_remote_telemetry(&key).await?;
+    let wait = async {
+        while let Some(event) = subscription.recv().await {
+            match event {
+                Event::TelemetryResponse {
+                    pubkey_prefix,
+                    telemetry,
+                } if pubkey_prefix == key.prefix() => return Ok(*telemetry),
+                _ => {}
+            }
+        }
+        Err(Error::ChannelClosed)
+    };
+    let telemetry = tokio::time::timeout(timeout, wait)
+        .await
+        .map_err(|_| Error::Timeout {
+            timeout_ms: u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX),
+        })??;
+    out.telemetry(&telemetry);
+    Ok(())
+}
+
+async fn run(args: Args) -> Result<()> {
+    let out = Output::new(args.json);
+    let timeout = Duration::from_secs(args.timeout);
+    let mut client = connect(&args).await?;
+
+    match args.command {
+        Command::Infos => {
+            let info = client.self_info().await.ok_or(Error::NotConnected)?;
+            let device = client.get_device_info().await?;
+            let battery = client.get_battery().await?;
+            out.infos(&info, &device, &battery);
+        }
+        Command::Contacts => {
+            let mut contacts: Vec<_> = client.get_contacts().await?.into_values().collect();
+            contacts.sort_by(|a, b| a.name.cmp(&b.name));
+            out.contacts(&contacts);
+        }
+        Command::Msg { contact, text } => {
+            let key = client.resolve_contact(&contact).await?;
+            client.send_message(&key, &text.join(" ")).await?;
+            out.sent(&key.to_hex());
+        }
+        Command::Chan { index, text } => {
+            client.send_channel_message(index, &text.join(" ")).await?;
+            out.sent(&format!("channel {index}"));
+        }
+        Command::Recv { wait, follow } => {
+            if wait {
+                tokio::time::timeout(timeout, recv(&client, &out, wait, follow))
+                    .await
+                    .map_err(|_| Error::Timeout {
+                        timeout_ms: args.timeout * 1000,
+                    })??;
+            } else {
+                recv(&client, &out, wait, follow).await?;
+            }
+        }
+        Command::Set {
+            setting:
+                Setting::Radio {
+                    frequency,
+                    bandwidth,
+                    spreading_factor,
+                    coding_rate,
+                },
+        } => {
+            let radio = RadioConfig {
+                frequency_mhz: frequency,
+                bandwidth_khz: bandwidth,
+                spreading_factor,
+                coding_rate,
+            };
+            client.set_radio(&radio).await?;
+            out.radio(&radio);
+        }
+        Command::Trace { path } => {
+            let path = parse_path(&path)?;
+            out.trace(&client.trace(&path).await?);
+        }
+        Command::Stats => {
+            let core = client.get_core_stats().await?;
+            let radio = client.get_radio_stats().await?;
+            let packets = client.get_packet_stats().await?;
+            out.stats(&core, &radio, &packets);
+        }
+        Command::Telemetry { contact } => {
+            telemetry(&client, &out, contact.as_deref(), timeout).await?;
+        }
+        Command::ExportKey => {
+            out.private_key(&client.export_private_key().await?);
+        }
+        Command::Chat => repl::run(&client).await?,
+        #[cfg(feature = "tui")]
+        Command::Tui => tui::run(&client).await?,
+    }
+
+    client.disconnect().await
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = Args::parse();
+    // Log output would corrupt the full-screen interface
+    #[cfg(feature = "tui")]
+    let logging = !matches!(args.command, Command::Tui);
+    #[cfg(not(feature = "tui"))]
+    let logging = true;
+    if logging {
+        tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .with_env_filter(
+                tracing_subscriber::EnvFilter::try_from_default_env()
+                    .unwrap_or_else(|_| "warn".into()),
+            )
+            .init();
+    }
+
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! Text and JSON output of command results.
+
+use meshcore::transport::Transport;
+use meshcore::{
+    BatteryStatus, Contact, ContactType, CoreStats, DeviceInfo, Event, MeshCore, PacketStats,
+    RadioConfig, RadioStats, SelfInfo, SignalQuality, Telemetry, TraceResult,
+};
+use serde::Serialize;
+use serde_json::json;
+
+/// Formats a Unix timestamp as a UTC time of day.
+pub fn time_of_day(timestamp: u32) -> String {
+    let secs = timestamp % 86_400;
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Formats the signal quality suffix of a message.
+pub fn signal_suffix(signal: Option<&SignalQuality>) -> String {
+    signal.map_or_else(String::new, |s| format!(" (SNR {:.1} dB)", s.snr))
+}
+
+/// Formats an outbound path length.
+pub fn path_label(path_len: i8) -> String {
+    if path_len < 0 {
+        "flood".to_string()
+    } else {
+        format!("{path_len} hops")
+    }
+}
+
+const fn type_label(device_type: ContactType) -> &'static str {
+    match device_type {
+        ContactType::Node => "node",
+        ContactType::Repeater => "repeater",
+        ContactType::Room => "room",
+        ContactType::Unknown => "unknown",
+    }
+}
+
+/// Prints command results as text or JSON.
+pub struct Output {
+    json: bool,
+}
+
+impl Output {
+    pub const fn new(json: bool) -> Self {
+        Self { json }
+    }
+
+    fn print_json(value: &impl Serialize) {
+        match serde_json::to_string_pretty(value) {
+            Ok(json) => println!("{json}"),
+            Err(e) => eprintln!("error: {e}"),
+        }
+    }
+
+    pub fn infos(&self, info: &SelfInfo, device: &DeviceInfo, battery: &BatteryStatus) {
+        if self.json {
+            Self::print_json(&json!({
+                "self_info": info,
+                "device_info": device,
+                "battery": battery,
+            }));
+            return;
+        }
+
+        println!("name:       {}", info.name);
+        println!("public key: {}", info.public_key);
+        if let Some(version) = &device.version {
+            println!("firmware:   {version} (v{})", device.firmware_version);
+        } else {
+            println!("firmware:   v{}", device.firmware_version);
+        }
+        if let Some(model) = &device.model {
+            println!("model:      {model}");
+        }
+        println!(
+            "radio:      {} MHz, BW {} kHz, SF{}, CR{}",
+            info.radio.frequency_mhz,
+            info.radio.bandwidth_khz,
+            info.radio.spreading_factor,
+            info.radio.coding_rate
+        );
+        println!(
+            "tx power:   {} dBm (max {})",
+            info.tx_power, info.max_tx_power
+        );
+        if let (Some(lat), Some(lon)) = (info.latitude, info.longitude) {
+            println!("position:   {lat:.6}, {lon:.6}");
+        }
+        println!("battery:    {} mV", battery.millivolts);
+    }
+
+    pub fn contacts(&self, contacts: &[Contact]) {
+        if self.json {
+            Self::print_json(&contacts);
+            return;
+        }
+
+        for contact in contacts {
+            println!(
+                "{:<32} {:<8} {:<8} {}",
+                contact.name,
+                type_label(contact.device_type),
+                path_label(contact.out_path_len),
+                &contact.public_key.to_hex()[..12]
+            );
+        }
+    }
+
+    pub fn sent(&self, destination: &str) {
+        if self.json {
+            Self::print_json(&json!({ "sent": true, "destination": destination }));
+        } else {
+            println!("sent to {destination}");
+        }
+    }
+
+    /// Prints a received message, resolving the sender from the contacts.
+    pub async fn message<T: Transport + 'static>(&self, client: &MeshCore<T>, event: &Event) {
+        match event {
+            Event::ContactMessage(message) => {
+                let sender = client
+                    .find_contact_by_prefix(&message.sender_prefix)
+                    .await
+                    .map_or_else(|| hex::encode(message.sender_prefix), |c| c.name);
+                if self.json {
+                    Self::print_json(&json!({ "sender": sender, "message": message }));
+                } else {
+                    println!(
+                        "[{}] {}: {}{}",
+                        time_of_day(message.timestamp),
+                        sender,
+                        message.text,
+                        signal_suffix(message.signal.as_ref())
+                    );
+                }
+            }
+            Event::ChannelMessage(message) => {
+                if self.json {
+                    Self::print_json(
+                        &json!({ "channel": message.channel_index, "message": message }),
+                    );
+                } else {
+                    println!(
+                        "[{}] #{} {}{}",
+                        time_of_day(message.timestamp),
+                        message.channel_index,
+                        message.text,
+                        signal_suffix(message.signal.as_ref())
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn radio(&self, radio: &RadioConfig) {
+        if self.json {
+            Self::print_json(radio);
+        } else {
+            println!(
+                "radio set to {} MHz, BW {} kHz, SF{}, CR{}",
+                radio.frequency_mhz, radio.bandwidth_khz, radio.spreading_factor, radio.coding_rate
+            );
+        }
+    }
+
+    pub fn trace(&self, trace: &TraceResult) {
+        if self.json {
+            Self::print_json(trace);
+            return;
+        }
+
+        for (hash, snr) in trace.path.iter().zip(&trace.snrs) {
+            println!("{hash:02x}  {snr:+.2} dB");
+        }
+        if let Some(snr) = trace.final_snr() {
+            println!("--  {snr:+.2} dB");
+        }
+    }
+
+    pub fn stats(&self, core: &CoreStats, radio: &RadioStats, packets: &PacketStats) {
+        if self.json {
+            Self::print_json(&json!({ "core": core, "radio": radio, "packets": packets }));
+            return;
+        }
+
+        println!("uptime:      {} s", core.uptime_secs);
+        println!("battery:     {} mV", core.battery_mv);
+        println!("queue:       {}", core.queue_len);
+        println!("errors:      {}", core.errors);
+        println!("noise floor: {} dBm", radio.noise_floor);
+        println!("last rssi:   {} dBm", radio.rssi);
+        println!("last snr:    {:.2} dB", radio.snr);
+        println!("tx airtime:  {} s", radio.tx_airtime_secs);
+        println!("rx airtime:  {} s", radio.rx_airtime_secs);
+        println!(
+            "packets:     {} received ({} flood, {} direct)",
+            packets.received, packets.flood_rx, packets.direct_rx
+        );
+        println!(
+            "             {} sent ({} flood, {} direct)",
+            packets.sent, packets.flood_tx, packets.direct_tx
+        );
+    }
+
+    pub fn telemetry(&self, telemetry: &Telemetry) {
+        if self.json {
+            Self::print_json(telemetry);
+            return;
+        }
+
+        for reading in &telemetry.readings {
+            println!("ch{:<3} {:?}", reading.channel, reading.value);
+        }
+    }
+
+    pub fn private_key(&self, key: &[u8; 64]) {
+        if self.json {
+            Self::print_json(&json!({ "private_key": hex::encode(key) }));
+        } else {
+            println!("{}", hex::encode(key));
+        }
+    }
+}
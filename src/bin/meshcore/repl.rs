@@ -0,0 +1,693 @@
+//! Interactive chat shell.
+//!
+//! Plain lines are sent to the current target, selected with
+//! `to <contact>` or `to #<channel>`. Lines starting with `/` run device
+//! commands. Incoming messages are printed above the prompt.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use meshcore::{Channel, Contact, Error, Event, PublicKey, RadioConfig, Result};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::{Context, Editor, ExternalPrinter, Helper, Highlighter, Hinter, Validator};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::output::{Output, signal_suffix, time_of_day};
+use crate::{Client, now, parse_path};
+
+/// Slash commands with their usage.
+const COMMANDS: &[(&str, &str)] = &[
+    ("help", ""),
+    ("quit", ""),
+    ("contacts", ""),
+    ("refresh", ""),
+    ("channels", ""),
+    ("infos", ""),
+    ("stats", ""),
+    ("time", ""),
+    ("sync", ""),
+    ("advert", "[flood]"),
+    ("name", "<name>"),
+    ("coords", "<lat> <lon>"),
+    ("tx-power", "<dbm>"),
+    ("radio", "<freq> <bw> <sf> <cr>"),
+    ("reset-path", "<contact>"),
+    ("remove", "<contact>"),
+    ("share", "<contact>"),
+    ("login", "<contact> <password>"),
+    ("logout", "<contact>"),
+    ("status", "<contact>"),
+    ("telemetry", "[contact]"),
+    ("cmd", "<contact> <command>"),
+    ("trace", "<path>"),
+    ("discover", ""),
+    ("reboot", ""),
+];
+
+/// Slash commands whose first argument is a contact.
+const CONTACT_COMMANDS: &[&str] = &[
+    "reset-path",
+    "remove",
+    "share",
+    "login",
+    "logout",
+    "status",
+    "telemetry",
+    "cmd",
+];
+
+/// Names offered by tab completion.
+#[derive(Default)]
+struct Names {
+    contacts: Vec<String>,
+    channels: Vec<String>,
+}
+
+/// Returns the completion start and candidates for a line prefix.
+fn complete_line(line: &str, names: &Names) -> (usize, Vec<String>) {
+    let matching = |start: usize, candidates: &mut dyn Iterator<Item = String>| {
+        let word = line[start..].to_lowercase();
+        let mut matches: Vec<_> = candidates
+            .filter(|c| c.to_lowercase().starts_with(&word))
+            .collect();
+        matches.sort();
+        (start, matches)
+    };
+
+    if let Some(command) = line.strip_prefix('/') {
+        return match command.split_once(' ') {
+            None => matching(1, &mut COMMANDS.iter().map(|(name, _)| (*name).to_string())),
+            // Names may contain spaces, so the whole argument is matched
+            Some((command, arg)) if CONTACT_COMMANDS.contains(&command) => {
+                matching(line.len() - arg.len(), &mut names.contacts.iter().cloned())
+            }
+            Some(_) => (0, Vec::new()),
+        };
+    }
+
+    if line.starts_with("to ") {
+        let channels = names.channels.iter().map(|c| format!("#{c}"));
+        return matching(3, &mut names.contacts.iter().cloned().chain(channels));
+    }
+    (0, Vec::new())
+}
+
+/// Splits the contact argument off the front of `rest`.
+///
+/// Contact names may contain spaces, so the longest known name `rest`
+/// starts with wins; otherwise the contact is the first word.
+fn split_contact<'a>(rest: &'a str, contacts: &[String]) -> (&'a str, &'a str) {
+    let longest = contacts
+        .iter()
+        .filter(|name| {
+            !name.is_empty()
+                && rest
+                    .get(..name.len())
+                    .is_some_and(|head| head.eq_ignore_ascii_case(name))
+                && rest[name.len()..]
+                    .chars()
+                    .next()
+                    .is_none_or(char::is_whitespace)
+        })
+        .map(String::len)
+        .max();
+    let end = longest.unwrap_or_else(|| rest.find(char::is_whitespace).unwrap_or(rest.len()));
+    let (contact, rest) = rest.split_at(end);
+    (contact, rest.trim_start())
+}
+
+#[derive(Helper, Hinter, Highlighter, Validator)]
+struct ReplHelper {
+    names: Arc<RwLock<Names>>,
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let names = self
+            .names
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        Ok(complete_line(&line[..pos], &names))
+    }
+}
+
+/// Where plain lines are sent.
+enum Target {
+    None,
+    Contact { key: PublicKey, name: String },
+    Channel { index: u8, name: String },
+}
+
+impl Target {
+    fn prompt(&self) -> String {
+        match self {
+            Self::None => "> ".to_string(),
+            Self::Contact { name, .. } => format!("{name}> "),
+            Self::Channel { name, .. } => format!("#{name}> "),
+        }
+    }
+}
+
+/// Printer used when stdin is not a terminal.
+struct StdoutPrinter;
+
+impl ExternalPrinter for StdoutPrinter {
+    fn print(&mut self, msg: String) -> rustyline::Result<()> {
+        println!("{msg}");
+        Ok(())
+    }
+}
+
+/// A line read by the editor thread, with a channel for the next prompt.
+struct Line {
+    text: String,
+    prompt: oneshot::Sender<String>,
+}
+
+/// Runs the line editor on a blocking thread.
+///
+/// Each line waits for the next prompt before reading again, so command
+/// output is not interleaved with the prompt.
+fn spawn_editor(
+    mut editor: Editor<ReplHelper, rustyline::history::DefaultHistory>,
+    prompt: String,
+) -> mpsc::Receiver<Line> {
+    let (tx, rx) = mpsc::channel(1);
+    std::thread::spawn(move || {
+        let mut prompt = prompt;
+        loop {
+            match editor.readline(&prompt) {
+                Ok(text) => {
+                    if !text.trim().is_empty() {
+                        let _ = editor.add_history_entry(text.as_str());
+                    }
+                    let (prompt_tx, prompt_rx) = oneshot::channel();
+                    let line = Line {
+                        text,
+                        prompt: prompt_tx,
+                    };
+                    if tx.blocking_send(line).is_err() {
+                        break;
+                    }
+                    match prompt_rx.blocking_recv() {
+                        Ok(next) => prompt = next,
+                        Err(_) => break,
+                    }
+                }
+                Err(ReadlineError::Interrupted) => {}
+                Err(_) => break,
+            }
+        }
+    });
+    rx
+}
+
+struct Repl<'a> {
+    client: &'a Client,
+    out: Output,
+    printer: Box<dyn ExternalPrinter + Send>,
+    names: Arc<RwLock<Names>>,
+    target: Target,
+    channels: Vec<Channel>,
+    // Expected ACK codes of sent messages and their recipients
+    pending: HashMap<u32, String>,
+}
+
+impl Repl<'_> {
+    /// Prints a line above the prompt.
+    fn print(&mut self, text: &str) {
+        if self.printer.print(text.to_string()).is_err() {
+            println!("{text}");
+        }
+    }
+
+    fn set_names(&self, contacts: Vec<String>) {
+        let mut names = self
+            .names
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        names.contacts = contacts;
+        names.channels = self.channels.iter().map(|c| c.name.clone()).collect();
+    }
+
+    async fn refresh_contacts(&self) -> Result<Vec<Contact>> {
+        let mut contacts: Vec<_> = self.client.get_contacts().await?.into_values().collect();
+        contacts.sort_by(|a, b| a.name.cmp(&b.name));
+        self.set_names(contacts.iter().map(|c| c.name.clone()).collect());
+        Ok(contacts)
+    }
+
+    async fn load_channels(&mut self) -> Result<()> {
+        self.channels = self.client.get_channels().await?;
+        Ok(())
+    }
+
+    async fn contact(&self, name: &str) -> Result<PublicKey> {
+        self.client.resolve_contact(name).await
+    }
+
+    async fn sender_name(&self, prefix: &[u8]) -> String {
+        self.client
+            .find_contact_by_prefix(prefix)
+            .await
+            .map_or_else(|| hex::encode(prefix), |c| c.name)
+    }
+
+    fn channel_name(&self, index: u8) -> String {
+        self.channels
+            .iter()
+            .find(|c| c.index == index)
+            .map_or_else(|| index.to_string(), |c| c.name.clone())
+    }
+
+    async fn select_target(&mut self, target: &str) -> Result<()> {
+        if let Some(channel) = target.strip_prefix('#') {
+            let found = channel.parse::<u8>().map_or_else(
+                |_| {
+                    self.channels
+                        .iter()
+                        .find(|c| {
+                            c.name
+                                .trim_start_matches('#')
+                                .eq_ignore_ascii_case(channel.trim_start_matches('#'))
+                        })
+                        .map(|c| (c.index, c.name.clone()))
+                },
+                |index| Some((index, self.channel_name(index))),
+            );
+            let (index, name) = found.ok_or_else(|| Error::Protocol {
+                message: format!("unknown channel: {channel}"),
+            })?;
+            self.target = Target::Channel { index, name };
+            return Ok(());
+        }
+
+        let key = self.contact(target).await?;
+        let name = self
+            .client
+            .get_contact(&key)
+            .await
+            .map_or_else(|| key.to_hex()[..12].to_string(), |c| c.name);
+        self.target = Target::Contact { key, name };
+        Ok(())
+    }
+
+    async fn send_text(&mut self, text: &str) -> Result<()> {
+        match &self.target {
+            Target::None => Err(Error::Protocol {
+                message: "no target; use `to <contact>` or `to #<channel>`".into(),
+            }),
+            Target::Contact { key, name } => {
+                let name = name.clone();
+                let event = self
+                    .client
+                    .commands()
+                    .send_message(key, text, 0, now())
+                    .await?;
+                if let Event::MessageSent { expected_ack, .. } = event {
+                    self.pending.insert(expected_ack, name);
+                }
+                Ok(())
+            }
+            Target::Channel { index, .. } => self.client.send_channel_message(*index, text).await,
+        }
+    }
+
+    /// Runs a slash command. Returns false to quit.
+    #[allow(clippy::too_many_lines)]
+    async fn command(&mut self, line: &str) -> Result<bool> {
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let rest = rest.trim();
+        let args: Vec<&str> = rest.split_whitespace().collect();
+        let (contact, contact_rest) = {
+            let names = self
+                .names
+                .read()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            split_contact(rest, &names.contacts)
+        };
+        let commands = self.client.commands();
+
+        match command {
+            "help" => {
+                println!("to <contact> | to #<channel>   select where text is sent");
+                for (name, usage) in COMMANDS {
+                    println!("/{name} {usage}");
+                }
+            }
+            "quit" => return Ok(false),
+            "contacts" => self.out.contacts(&self.refresh_contacts().await?),
+            "refresh" => {
+                self.load_channels().await?;
+                let count = self.refresh_contacts().await?.len();
+                println!("{count} contacts, {} channels", self.channels.len());
+            }
+            "channels" => {
+                for channel in &self.channels {
+                    println!("#{:<3} {}", channel.index, channel.name);
+                }
+            }
+            "infos" => {
+                let info = self.client.self_info().await.ok_or(Error::NotConnected)?;
+                let device = self.client.get_device_info().await?;
+                let battery = self.client.get_battery().await?;
+                self.out.infos(&info, &device, &battery);
+            }
+            "stats" => {
+                let core = self.client.get_core_stats().await?;
+                let radio = self.client.get_radio_stats().await?;
+                let packets = self.client.get_packet_stats().await?;
+                self.out.stats(&core, &radio, &packets);
+            }
+            "time" => {
+                let sample = self.client.measure_clock().await?;
+                println!(
+                    "device time {} UTC ({:+.1}s off)",
+                    time_of_day(sample.device_time),
+                    sample.drift
+                );
+            }
+            "sync" => {
+                let sample = self.client.sync_clock().await?;
+                println!("clock synced ({:+.1}s off)", sample.drift);
+            }
+            "advert" => commands.send_advert(rest == "flood").await?,
+            "name" if !rest.is_empty() => commands.set_name(rest).await?,
+            "coords" if args.len() == 2 => {
+                let (lat, lon) = (parse_arg(args[0])?, parse_arg(args[1])?);
+                commands.set_coords(lat, lon).await?;
+            }
+            "tx-power" if args.len() == 1 => commands.set_tx_power(parse_arg(args[0])?).await?,
+            "radio" if args.len() == 4 => {
+                let radio = RadioConfig {
+                    frequency_mhz: parse_arg(args[0])?,
+                    bandwidth_khz: parse_arg(args[1])?,
+                    spreading_factor: parse_arg(args[2])?,
+                    coding_rate: parse_arg(args[3])?,
+                };
+                self.client.set_radio(&radio).await?;
+                self.out.radio(&radio);
+            }
+            "reset-path" if !contact.is_empty() && contact_rest.is_empty() => {
+                commands.reset_path(&self.contact(contact).await?).await?;
+            }
+            "remove" if !contact.is_empty() && contact_rest.is_empty() => {
+                commands
+                    .remove_contact(&self.contact(contact).await?)
+                    .await?;
+                self.refresh_contacts().await?;
+            }
+            "share" if !contact.is_empty() && contact_rest.is_empty() => {
+                commands
+                    .share_contact(&self.contact(contact).await?)
+                    .await?;
+            }
+            "login" if !contact.is_empty() && !contact_rest.is_empty() => {
+                // The result arrives as LoginSuccess/LoginFailed
+                commands
+                    .send_login(&self.contact(contact).await?, contact_rest)
+                    .await?;
+            }
+            "logout" if !contact.is_empty() && contact_rest.is_empty() => {
+                commands.send_logout(&self.contact(contact).await?).await?;
+            }
+            "status" if !contact.is_empty() && contact_rest.is_empty() => {
+                commands
+                    .send_status_request(&self.contact(contact).await?)
+                    .await?;
+            }
+            "telemetry" if contact_rest.is_empty() => {
+                if contact.is_empty() {
+                    commands.get_self_telemetry().await?;
+                } else {
+                    commands
+                        .send_telemetry_request(&self.contact(contact).await?)
+                        .await?;
+                }
+            }
+            "cmd" if !contact.is_empty() && !contact_rest.is_empty() => {
+                let key = self.contact(contact).await?;
+                commands.send_command(&key, contact_rest, now()).await?;
+            }
+            "trace" if args.len() == 1 => self
+                .out
+                .trace(&self.client.trace(&parse_path(args[0])?).await?),
+            "discover" => {
+                let nodes = self
+                    .client
+                    .discover_nodes(0xFF, true, std::time::Duration::from_secs(5))
+                    .await?;
+                for node in nodes {
+                    println!(
+                        "{:<16} {:?} SNR {:+.1} dB",
+                        node.key.to_hex(),
+                        node.node_type,
+                        node.snr
+                    );
+                }
+            }
+            "reboot" => {
+                commands.reboot().await?;
+                return Ok(false);
+            }
+            _ => match COMMANDS.iter().find(|(name, _)| *name == command) {
+                Some((name, usage)) => println!("usage: /{name} {usage}"),
+                None => println!("unknown command /{command}; try /help"),
+            },
+        }
+        Ok(true)
+    }
+
+    /// Handles an input line. Returns false to quit.
+    async fn input(&mut self, line: &str) -> bool {
+        let line = line.trim();
+        let result = if let Some(command) = line.strip_prefix('/') {
+            self.command(command).await
+        } else if let Some(target) = line.strip_prefix("to ") {
+            self.select_target(target.trim()).await.map(|()| true)
+        } else if line.is_empty() {
+            Ok(true)
+        } else {
+            self.send_text(line).await.map(|()| true)
+        };
+
+        result.unwrap_or_else(|e| {
+            println!("error: {e}");
+            true
+        })
+    }
+
+    /// Prints an incoming event. Returns false if the connection is gone.
+    async fn event(&mut self, event: Event) -> Result<bool> {
+        match event {
+            Event::MessagesWaiting => {
+                // Fetched messages are printed when their events arrive
+                self.client.fetch_messages().await?;
+            }
+            Event::ContactMessage(message) => {
+                let sender = self.sender_name(&message.sender_prefix).await;
+                self.print(&format!(
+                    "[{}] {}: {}{}",
+                    time_of_day(message.timestamp),
+                    sender,
+                    message.text,
+                    signal_suffix(message.signal.as_ref())
+                ));
+            }
+            Event::ChannelMessage(message) => {
+                let channel = self.channel_name(message.channel_index);
+                self.print(&format!(
+                    "[{}] #{}: {}{}",
+                    time_of_day(message.timestamp),
+                    channel,
+                    message.text,
+                    signal_suffix(message.signal.as_ref())
+                ));
+            }
+            Event::Ack(ack) => {
+                if let Some(name) = self.pending.remove(&ack.code) {
+                    self.print(&format!("(delivered to {name})"));
+                }
+            }
+            Event::NewContactAdvert(contact) => {
+                self.print(&format!("(new contact {})", contact.name));
+                let mut names = self
+                    .names
+                    .write()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner);
+                if !names.contacts.contains(&contact.name) {
+                    names.contacts.push(contact.name.clone());
+                }
+            }
+            Event::LoginSuccess(login) => {
+                let sender = self.sender_name(&login.pubkey_prefix).await;
+                let role = if login.is_admin() { "admin" } else { "guest" };
+                self.print(&format!("(logged in to {sender} as {role})"));
+            }
+            Event::LoginFailed { pubkey_prefix } => {
+                let sender = self.sender_name(&pubkey_prefix).await;
+                self.print(&format!("(login to {sender} failed)"));
+            }
+            Event::StatusResponse(status) => {
+                let sender = self.sender_name(&status.pubkey_prefix).await;
+                self.print(&format!(
+                    "(status {}: {} mV, up {} s, noise {} dBm, {} rx / {} tx)",
+                    sender,
+                    status.battery_mv,
+                    status.uptime_secs,
+                    status.noise_floor,
+                    status.packets_received,
+                    status.packets_sent
+                ));
+            }
+            Event::TelemetryResponse { telemetry, .. } => {
+                for reading in &telemetry.readings {
+                    self.print(&format!(
+                        "(telemetry ch{} {:?})",
+                        reading.channel, reading.value
+                    ));
+                }
+            }
+            Event::Disconnected => {
+                self.print("(disconnected)");
+                return Ok(false);
+            }
+            _ => {}
+        }
+        Ok(true)
+    }
+}
+
+fn parse_arg<T: std::str::FromStr>(arg: &str) -> Result<T> {
+    arg.parse().map_err(|_| Error::Protocol {
+        message: format!("invalid argument: {arg}"),
+    })
+}
+
+/// Runs the interactive shell until `/quit` or end of input.
+pub async fn run(client: &Client) -> Result<()> {
+    let names = Arc::new(RwLock::new(Names::default()));
+    let mut editor = Editor::new().map_err(|e| Error::Protocol {
+        message: format!("cannot open terminal: {e}"),
+    })?;
+    editor.set_helper(Some(ReplHelper {
+        names: Arc::clone(&names),
+    }));
+    // Without a terminal (e.g. piped input) lines are printed directly
+    let printer: Box<dyn ExternalPrinter + Send> = match editor.create_external_printer() {
+        Ok(printer) => Box::new(printer),
+        Err(_) => Box::new(StdoutPrinter),
+    };
+
+    let mut repl = Repl {
+        client,
+        out: Output::new(false),
+        printer,
+        names,
+        target: Target::None,
+        channels: Vec::new(),
+        pending: HashMap::new(),
+    };
+    let mut events = client.subscribe();
+    repl.load_channels().await?;
+    let count = repl.refresh_contacts().await?.len();
+    println!(
+        "{count} contacts, {} channels; `to <contact>` selects a recipient, /help lists commands",
+        repl.channels.len()
+    );
+    client.fetch_messages().await?;
+
+    let mut lines = spawn_editor(editor, repl.target.prompt());
+    loop {
+        tokio::select! {
+            line = lines.recv() => {
+                let Some(line) = line else { break };
+                if !repl.input(&line.text).await {
+                    break;
+                }
+                let _ = line.prompt.send(repl.target.prompt());
+            }
+            event = events.recv() => {
+                let Some(event) = event else { break };
+                match repl.event(event).await {
+                    Ok(true) => {}
+                    Ok(false) => break,
+                    Err(e) => repl.print(&format!("error: {e}")),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names() -> Names {
+        Names {
+            contacts: vec!["Alice".into(), "alfred".into(), "Bob Repeater".into()],
+            channels: vec!["Public".into()],
+        }
+    }
+
+    #[test]
+    fn test_complete_slash_commands() {
+        let (start, candidates) = complete_line("/re", &names());
+        assert_eq!(start, 1);
+        assert_eq!(
+            candidates,
+            vec!["reboot", "refresh", "remove", "reset-path"]
+        );
+    }
+
+    #[test]
+    fn test_complete_targets() {
+        let (start, candidates) = complete_line("to al", &names());
+        assert_eq!(start, 3);
+        assert_eq!(candidates, vec!["Alice", "alfred"]);
+
+        let (_, candidates) = complete_line("to #p", &names());
+        assert_eq!(candidates, vec!["#Public"]);
+
+        let (start, candidates) = complete_line("/login b", &names());
+        assert_eq!(start, 7);
+        assert_eq!(candidates, vec!["Bob Repeater"]);
+
+        let (start, candidates) = complete_line("/status bob r", &names());
+        assert_eq!(start, 8);
+        assert_eq!(candidates, vec!["Bob Repeater"]);
+
+        // Only the first argument is a contact
+        assert!(complete_line("/login bob pa", &names()).1.is_empty());
+        assert!(complete_line("hello al", &names()).1.is_empty());
+    }
+
+    #[test]
+    fn test_split_contact() {
+        let contacts = names().contacts;
+        assert_eq!(
+            split_contact("Bob Repeater secret", &contacts),
+            ("Bob Repeater", "secret")
+        );
+        assert_eq!(
+            split_contact("bob repeater", &contacts),
+            ("bob repeater", "")
+        );
+        assert_eq!(
+            split_contact("ali get clock", &contacts),
+            ("ali", "get clock")
+        );
+        assert_eq!(split_contact("Alice", &contacts), ("Alice", ""));
+        assert_eq!(split_contact("Bob", &contacts), ("Bob", ""));
+        assert_eq!(split_contact("", &contacts), ("", ""));
+    }
+}
@@ -0,0 +1,681 @@
+//! Terminal user interface.
+//!
+//! Shows the contact and channel list, the selected conversation, live
+//! device statistics and decoded packets received over the air. All data
+//! comes from the client's event stream, except for statistics which are
+//! polled periodically.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use crossterm::event::{Event as TermEvent, EventStream, KeyCode, KeyEvent, KeyModifiers};
+use futures::StreamExt;
+use meshcore::{
+    Channel, Contact, CoreStats, Error, Event, PacketStats, PublicKey, RadioStats, Result,
+    RxLogEntry,
+};
+use ratatui::DefaultTerminal;
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+
+use crate::output::{path_label, time_of_day};
+use crate::{Client, now};
+
+/// Number of received packets kept in the RX log.
+const RX_LOG_LEN: usize = 500;
+
+/// Number of lines kept per conversation.
+const CHAT_LEN: usize = 1000;
+
+/// Interval between statistics polls.
+const STATS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A conversation shown in the side list.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Conversation {
+    Channel(u8),
+    Contact(PublicKey),
+    /// Sender not in the contact list, by public key prefix.
+    Unknown([u8; 6]),
+}
+
+/// A line in a conversation.
+#[derive(Debug, Clone, PartialEq)]
+struct ChatLine {
+    timestamp: u32,
+    /// Sender name, `None` for own messages.
+    sender: Option<String>,
+    text: String,
+    /// Expected ACK code of an own direct message.
+    ack: Option<u32>,
+    /// Own direct messages are marked once the ACK arrives.
+    delivered: bool,
+}
+
+/// What the event loop should do after a key press.
+#[derive(Debug, PartialEq)]
+enum Action {
+    Send(Conversation, String),
+    Quit,
+}
+
+/// Formats the age of a timestamp, e.g. `5m` or `3d`.
+fn format_age(timestamp: u32, now: u32) -> String {
+    if timestamp == 0 {
+        return "-".to_string();
+    }
+    let secs = now.saturating_sub(timestamp);
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86_400 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86_400),
+    }
+}
+
+/// Formats a received packet for the RX log.
+fn format_rx(timestamp: u32, entry: &RxLogEntry) -> String {
+    let path = if entry.path.is_empty() {
+        String::new()
+    } else {
+        format!(" via {}", hex::encode(&entry.path))
+    };
+    format!(
+        "{} {:>4} dBm {:+5.1} dB {:?}/{:?}{} ({} bytes)",
+        time_of_day(timestamp),
+        entry.rssi,
+        entry.snr,
+        entry.route_type,
+        entry.payload_type,
+        path,
+        entry.payload.len()
+    )
+}
+
+/// State of the interface, updated from events and key presses.
+#[derive(Default)]
+struct App {
+    contacts: Vec<Contact>,
+    channels: Vec<Channel>,
+    // Prefixes of senders not in the contact list, in order of arrival
+    unknown: Vec<[u8; 6]>,
+    selected: usize,
+    chats: HashMap<Conversation, VecDeque<ChatLine>>,
+    unread: HashMap<Conversation, usize>,
+    stats: Option<(CoreStats, RadioStats, PacketStats)>,
+    rx_log: VecDeque<String>,
+    input: String,
+    // Expected ACK codes of sent messages
+    pending: HashMap<u32, Conversation>,
+    status: String,
+}
+
+impl App {
+    fn set_contacts(&mut self, contacts: impl IntoIterator<Item = Contact>) {
+        let selected = self.selected_conversation();
+        self.contacts = contacts.into_iter().collect();
+        self.contacts.sort_by(|a, b| a.name.cmp(&b.name));
+        // Keep the selection on the same conversation
+        if let Some(index) = selected.and_then(|s| self.conversations().position(|c| c == s)) {
+            self.selected = index;
+        }
+    }
+
+    fn conversations(&self) -> impl Iterator<Item = Conversation> + '_ {
+        let channels = self.channels.iter().map(|c| Conversation::Channel(c.index));
+        let contacts = self
+            .contacts
+            .iter()
+            .map(|c| Conversation::Contact(c.public_key.clone()));
+        let unknown = self.unknown.iter().copied().map(Conversation::Unknown);
+        channels.chain(contacts).chain(unknown)
+    }
+
+    fn selected_conversation(&self) -> Option<Conversation> {
+        self.conversations().nth(self.selected)
+    }
+
+    fn select(&mut self, offset: isize) {
+        let count = self.conversations().count();
+        if count == 0 {
+            return;
+        }
+        self.selected = self.selected.saturating_add_signed(offset).min(count - 1);
+        if let Some(conversation) = self.selected_conversation() {
+            self.unread.remove(&conversation);
+        }
+    }
+
+    fn title(&self, conversation: &Conversation) -> String {
+        match conversation {
+            Conversation::Channel(index) => self
+                .channels
+                .iter()
+                .find(|c| c.index == *index)
+                .map_or_else(|| format!("#{index}"), |c| format!("#{}", c.name)),
+            Conversation::Contact(key) => self
+                .contacts
+                .iter()
+                .find(|c| &c.public_key == key)
+                .map_or_else(|| key.to_hex()[..12].to_string(), |c| c.name.clone()),
+            Conversation::Unknown(prefix) => format!("? {}", hex::encode(prefix)),
+        }
+    }
+
+    fn push_line(&mut self, conversation: Conversation, line: ChatLine) {
+        if line.sender.is_some() && self.selected_conversation().as_ref() != Some(&conversation) {
+            *self.unread.entry(conversation.clone()).or_default() += 1;
+        }
+        let lines = self.chats.entry(conversation).or_default();
+        if lines.len() == CHAT_LEN {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    /// Records an own message, tracking its ACK if one is expected.
+    fn sent(&mut self, conversation: Conversation, text: String, expected_ack: Option<u32>) {
+        if let Some(code) = expected_ack {
+            self.pending.insert(code, conversation.clone());
+        }
+        self.push_line(
+            conversation,
+            ChatLine {
+                timestamp: now(),
+                sender: None,
+                text,
+                ack: expected_ack,
+                delivered: false,
+            },
+        );
+    }
+
+    /// Marks the own message with the ACK code as delivered.
+    fn delivered(&mut self, code: u32) {
+        let Some(conversation) = self.pending.remove(&code) else {
+            return;
+        };
+        if let Some(line) = self
+            .chats
+            .get_mut(&conversation)
+            .and_then(|lines| lines.iter_mut().find(|l| l.ack == Some(code)))
+        {
+            line.delivered = true;
+        }
+    }
+
+    /// Applies a client event. Returns true if the contacts should be reloaded.
+    fn event(&mut self, event: &Event, now: u32) -> bool {
+        match event {
+            Event::ContactMessage(message) => {
+                let contact = self
+                    .contacts
+                    .iter()
+                    .find(|c| c.public_key.as_bytes().starts_with(&message.sender_prefix));
+                let (conversation, sender) = if let Some(c) = contact {
+                    (Conversation::Contact(c.public_key.clone()), c.name.clone())
+                } else {
+                    // Unknown senders get their own conversation at the end of the list
+                    let sender = hex::encode(message.sender_prefix);
+                    self.status = format!("message from unknown sender {sender}");
+                    if !self.unknown.contains(&message.sender_prefix) {
+                        self.unknown.push(message.sender_prefix);
+                    }
+                    (Conversation::Unknown(message.sender_prefix), sender)
+                };
+                self.push_line(
+                    conversation,
+                    ChatLine {
+                        timestamp: message.timestamp,
+                        sender: Some(sender),
+                        text: message.text.clone(),
+                        ack: None,
+                        delivered: false,
+                    },
+                );
+            }
+            Event::ChannelMessage(message) => {
+                // Channel messages carry the sender in the text ("name: text")
+                let (sender, text) = message
+                    .text
+                    .split_once(": ")
+                    .unwrap_or(("?", message.text.as_str()));
+                self.push_line(
+                    Conversation::Channel(message.channel_index),
+                    ChatLine {
+                        timestamp: message.timestamp,
+                        sender: Some(sender.to_string()),
+                        text: text.to_string(),
+                        ack: None,
+                        delivered: false,
+                    },
+                );
+            }
+            Event::Ack(ack) => self.delivered(ack.code),
+            Event::Advertisement(key) => {
+                if let Some(contact) = self.contacts.iter_mut().find(|c| &c.public_key == key) {
+                    contact.last_advert = now;
+                }
+            }
+            Event::NewContactAdvert(contact) => {
+                let mut contacts = std::mem::take(&mut self.contacts);
+                contacts.retain(|c| c.public_key != contact.public_key);
+                contacts.push(contact.as_ref().clone());
+                self.set_contacts(contacts);
+            }
+            Event::PathUpdate(_) => return true,
+            Event::RxLog(entry) => {
+                if self.rx_log.len() == RX_LOG_LEN {
+                    self.rx_log.pop_front();
+                }
+                self.rx_log.push_back(format_rx(now, entry));
+            }
+            Event::Disconnected => self.status = "disconnected".to_string(),
+            _ => {}
+        }
+        false
+    }
+
+    fn key(&mut self, key: KeyEvent) -> Option<Action> {
+        match key.code {
+            KeyCode::Esc => return Some(Action::Quit),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Some(Action::Quit);
+            }
+            KeyCode::Up => self.select(-1),
+            KeyCode::Down => self.select(1),
+            KeyCode::PageUp => self.select(-10),
+            KeyCode::PageDown => self.select(10),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Char(c) => self.input.push(c),
+            KeyCode::Enter if !self.input.trim().is_empty() => {
+                let conversation = self.selected_conversation()?;
+                let text = std::mem::take(&mut self.input);
+                return Some(Action::Send(conversation, text.trim().to_string()));
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+fn draw(frame: &mut Frame, app: &App) {
+    let [side, main] =
+        Layout::horizontal([Constraint::Length(36), Constraint::Min(20)]).areas(frame.area());
+    let [list, stats] = Layout::vertical([Constraint::Min(5), Constraint::Length(8)]).areas(side);
+    let [chat, input, rx_log] = Layout::vertical([
+        Constraint::Percentage(60),
+        Constraint::Length(3),
+        Constraint::Min(5),
+    ])
+    .areas(main);
+
+    draw_list(frame, app, list);
+    draw_stats(frame, app, stats);
+    draw_chat(frame, app, chat);
+
+    let title = if app.status.is_empty() {
+        "Message (Enter send, Esc quit)".to_string()
+    } else {
+        app.status.clone()
+    };
+    frame.render_widget(
+        Paragraph::new(app.input.as_str()).block(Block::bordered().title(title)),
+        input,
+    );
+    let cursor = u16::try_from(app.input.chars().count()).unwrap_or(u16::MAX);
+    frame.set_cursor_position((
+        input.x.saturating_add(1).saturating_add(cursor),
+        input.y + 1,
+    ));
+
+    let height = usize::from(rx_log.height.saturating_sub(2));
+    let lines: Vec<Line> = app
+        .rx_log
+        .iter()
+        .skip(app.rx_log.len().saturating_sub(height))
+        .map(|l| Line::raw(l.as_str()))
+        .collect();
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title("RX log")),
+        rx_log,
+    );
+}
+
+fn draw_list(frame: &mut Frame, app: &App, area: Rect) {
+    let now = now();
+    let items: Vec<ListItem> = app
+        .conversations()
+        .map(|conversation| {
+            let unread = app
+                .unread
+                .get(&conversation)
+                .map_or_else(String::new, |n| format!(" ({n})"));
+            let details = match &conversation {
+                Conversation::Channel(_) => String::new(),
+                Conversation::Unknown(_) => "unknown".to_string(),
+                Conversation::Contact(key) => app
+                    .contacts
+                    .iter()
+                    .find(|c| &c.public_key == key)
+                    .map_or_else(String::new, |c| {
+                        format!(
+                            "{:>4} {}",
+                            format_age(c.last_advert, now),
+                            path_label(c.out_path_len)
+                        )
+                    }),
+            };
+            let name = format!("{}{unread}", app.title(&conversation));
+            ListItem::new(format!("{name:<20.20} {details}"))
+        })
+        .collect();
+
+    let mut state = ListState::default().with_selected(Some(app.selected));
+    frame.render_stateful_widget(
+        List::new(items)
+            .block(Block::bordered().title("Contacts"))
+            .highlight_style(Style::new().reversed()),
+        area,
+        &mut state,
+    );
+}
+
+fn draw_stats(frame: &mut Frame, app: &App, area: Rect) {
+    let lines = app.stats.as_ref().map_or_else(
+        || vec![Line::raw("waiting for statistics")],
+        |(core, radio, packets)| {
+            vec![
+                Line::raw(format!(
+                    "up {}s  {} mV  queue {}",
+                    core.uptime_secs, core.battery_mv, core.queue_len
+                )),
+                Line::raw(format!("noise {} dBm", radio.noise_floor)),
+                Line::raw(format!("last {} dBm {:+.1} dB", radio.rssi, radio.snr)),
+                Line::raw(format!(
+                    "airtime tx {}s rx {}s",
+                    radio.tx_airtime_secs, radio.rx_airtime_secs
+                )),
+                Line::raw(format!(
+                    "rx {} ({} flood)  tx {}",
+                    packets.received, packets.flood_rx, packets.sent
+                )),
+            ]
+        },
+    );
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title("Stats")),
+        area,
+    );
+}
+
+fn draw_chat(frame: &mut Frame, app: &App, area: Rect) {
+    let conversation = app.selected_conversation();
+    let title = conversation
+        .as_ref()
+        .map_or_else(|| "No conversation".to_string(), |c| app.title(c));
+    let height = usize::from(area.height.saturating_sub(2));
+    let lines: Vec<Line> = conversation
+        .and_then(|c| app.chats.get(&c))
+        .map(|lines| {
+            lines
+                .iter()
+                .skip(lines.len().saturating_sub(height))
+                .map(|line| {
+                    let sender = line.sender.as_deref().unwrap_or("me");
+                    let mark = if line.delivered { " ✓" } else { "" };
+                    Line::raw(format!(
+                        "[{}] {sender}: {}{mark}",
+                        time_of_day(line.timestamp),
+                        line.text
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title(title)),
+        area,
+    );
+}
+
+/// Sends a message to a conversation.
+async fn send(client: &Client, app: &mut App, conversation: Conversation, text: String) {
+    let result = match &conversation {
+        Conversation::Channel(index) => client
+            .send_channel_message(*index, &text)
+            .await
+            .map(|()| None),
+        Conversation::Contact(key) => client
+            .commands()
+            .send_message(key, &text, 0, now())
+            .await
+            .map(|event| match event {
+                Event::MessageSent { expected_ack, .. } => Some(expected_ack),
+                _ => None,
+            }),
+        // Only the key prefix is known, which is not enough to send
+        Conversation::Unknown(prefix) => Err(Error::Protocol {
+            message: format!("{} is not a contact", hex::encode(prefix)),
+        }),
+    };
+    match result {
+        Ok(expected_ack) => {
+            app.status.clear();
+            app.sent(conversation, text, expected_ack);
+        }
+        Err(e) => app.status = format!("error: {e}"),
+    }
+}
+
+async fn poll_stats(client: &Client) -> Result<(CoreStats, RadioStats, PacketStats)> {
+    Ok((
+        client.get_core_stats().await?,
+        client.get_radio_stats().await?,
+        client.get_packet_stats().await?,
+    ))
+}
+
+async fn event_loop(terminal: &mut DefaultTerminal, client: &Client, app: &mut App) -> Result<()> {
+    let mut keys = EventStream::new();
+    let mut events = client.subscribe();
+    let mut stats = tokio::time::interval(STATS_INTERVAL);
+    client.fetch_messages().await?;
+
+    loop {
+        terminal.draw(|frame| draw(frame, app))?;
+        tokio::select! {
+            key = keys.next() => match key {
+                Some(Ok(TermEvent::Key(key))) => match app.key(key) {
+                    Some(Action::Quit) => return Ok(()),
+                    Some(Action::Send(conversation, text)) => {
+                        send(client, app, conversation, text).await;
+                    }
+                    None => {}
+                },
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.into()),
+                None => return Ok(()),
+            },
+            event = events.recv() => {
+                let Some(event) = event else { return Ok(()) };
+                if matches!(event, Event::MessagesWaiting) {
+                    // Fetched messages arrive as events
+                    if let Err(e) = client.fetch_messages().await {
+                        app.status = format!("error: {e}");
+                    }
+                } else if app.event(&event, now()) {
+                    app.set_contacts(client.contacts().await.into_values());
+                }
+            }
+            _ = stats.tick() => match poll_stats(client).await {
+                Ok(polled) => app.stats = Some(polled),
+                Err(e) => app.status = format!("error: {e}"),
+            },
+        }
+    }
+}
+
+/// Runs the terminal interface until Esc or Ctrl-C.
+pub async fn run(client: &Client) -> Result<()> {
+    let mut app = App {
+        channels: client.get_channels().await?,
+        ..App::default()
+    };
+    app.set_contacts(client.get_contacts().await?.into_values());
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, client, &mut app).await;
+    ratatui::restore();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use meshcore::{Acknowledgment, ChannelMessage, ContactMessage, TextType};
+
+    use super::*;
+
+    fn contact(name: &str, key: u8) -> Contact {
+        Contact {
+            public_key: PublicKey::from_bytes(&[key; 32]),
+            device_type: meshcore::ContactType::Node,
+            flags: meshcore::ContactFlags::default(),
+            out_path_len: -1,
+            out_path: bytes::Bytes::new(),
+            name: name.to_string(),
+            last_advert: 0,
+            latitude: None,
+            longitude: None,
+            last_modified: 0,
+        }
+    }
+
+    fn app() -> App {
+        let mut app = App {
+            channels: vec![Channel {
+                index: 0,
+                name: "Public".into(),
+                secret: [0; 16],
+            }],
+            ..App::default()
+        };
+        app.set_contacts([contact("bob", 2), contact("alice", 1)]);
+        app
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(0, 1000), "-");
+        assert_eq!(format_age(990, 1000), "10s");
+        assert_eq!(format_age(1000 - 300, 1000), "5m");
+        assert_eq!(format_age(10_000 - 7200, 10_000), "2h");
+        assert_eq!(format_age(1_000_000 - 3 * 86_400, 1_000_000), "3d");
+    }
+
+    #[test]
+    fn test_messages_and_unread() {
+        let mut app = app();
+        // Channels come first, then contacts by name
+        assert_eq!(app.selected_conversation(), Some(Conversation::Channel(0)));
+
+        let message = ContactMessage {
+            sender_prefix: [2; 6],
+            path_len: 1,
+            text_type: TextType::Plain,
+            timestamp: 100,
+            signature: None,
+            text: "hi".into(),
+            signal: None,
+        };
+        app.event(&Event::ContactMessage(Box::new(message)), 100);
+        let bob = Conversation::Contact(PublicKey::from_bytes(&[2; 32]));
+        assert_eq!(app.chats[&bob][0].sender.as_deref(), Some("bob"));
+        assert_eq!(app.unread[&bob], 1);
+
+        let message = ChannelMessage {
+            channel_index: 0,
+            path_len: -1,
+            text_type: TextType::Plain,
+            timestamp: 100,
+            text: "carol: hello all".into(),
+            signal: None,
+        };
+        app.event(&Event::ChannelMessage(Box::new(message)), 100);
+        let line = &app.chats[&Conversation::Channel(0)][0];
+        assert_eq!(line.sender.as_deref(), Some("carol"));
+        assert_eq!(line.text, "hello all");
+        // The selected conversation is not marked unread
+        assert!(!app.unread.contains_key(&Conversation::Channel(0)));
+
+        app.key(key(KeyCode::Down));
+        app.key(key(KeyCode::Down));
+        assert_eq!(app.selected_conversation(), Some(bob.clone()));
+        assert!(app.unread.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_sender() {
+        let mut app = app();
+        let message = ContactMessage {
+            sender_prefix: [9; 6],
+            path_len: 1,
+            text_type: TextType::Plain,
+            timestamp: 100,
+            signature: None,
+            text: "who am i".into(),
+            signal: None,
+        };
+        app.event(&Event::ContactMessage(Box::new(message.clone())), 100);
+        app.event(&Event::ContactMessage(Box::new(message)), 100);
+        assert!(app.status.contains("090909090909"));
+
+        // Listed once, after the contacts, and selectable
+        let unknown = Conversation::Unknown([9; 6]);
+        assert_eq!(app.conversations().last(), Some(unknown.clone()));
+        assert_eq!(app.conversations().count(), 4);
+        app.key(key(KeyCode::PageDown));
+        assert_eq!(app.selected_conversation(), Some(unknown.clone()));
+        assert_eq!(app.title(&unknown), "? 090909090909");
+        assert_eq!(app.chats[&unknown].len(), 2);
+        assert!(app.unread.is_empty());
+    }
+
+    #[test]
+    fn test_send_and_ack() {
+        let mut app = app();
+        app.key(key(KeyCode::Down));
+        for c in "yo ".chars() {
+            app.key(key(KeyCode::Char(c)));
+        }
+        let alice = Conversation::Contact(PublicKey::from_bytes(&[1; 32]));
+        assert_eq!(
+            app.key(key(KeyCode::Enter)),
+            Some(Action::Send(alice.clone(), "yo".into()))
+        );
+        assert!(app.input.is_empty());
+
+        app.sent(alice.clone(), "yo".into(), Some(42));
+        app.sent(alice.clone(), "again".into(), Some(43));
+        app.event(&Event::Ack(Acknowledgment { code: 7 }), 0);
+        assert!(!app.chats[&alice][0].delivered);
+        // ACKs may arrive out of order
+        app.event(&Event::Ack(Acknowledgment { code: 43 }), 0);
+        assert!(!app.chats[&alice][0].delivered);
+        assert!(app.chats[&alice][1].delivered);
+        app.event(&Event::Ack(Acknowledgment { code: 42 }), 0);
+        assert!(app.chats[&alice][0].delivered);
+
+        let quit = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(app.key(quit), Some(Action::Quit));
+    }
+}
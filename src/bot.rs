@@ -0,0 +1,568 @@
+//! Bot framework.
+//!
+//! A [`Bot`] runs the event loop every mesh bot needs: it fetches waiting
+//! messages, matches direct and channel messages against registered
+//! commands and sends the handler's reply back to where the request came
+//! from, split to fit the radio's message size:
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use meshcore::MeshCore;
+//! use meshcore::bot::Bot;
+//!
+//! # async fn example() -> meshcore::Result<()> {
+//! let mut client = MeshCore::serial("/dev/ttyUSB0");
+//! client.connect().await?;
+//!
+//! Bot::new(Arc::new(client))
+//!     .prefix("!")
+//!     .command("ping", |request| async move {
+//!         Some(format!("pong {} ({} hops)", request.sender, request.path_len))
+//!     })
+//!     .command("echo", |request| async move { Some(request.args) })
+//!     .run()
+//!     .await
+//! # }
+//! ```
+//!
+//! Messages sent by the bot's own radio are ignored, and each sender can
+//! trigger at most one command per [`Bot::rate_limit`] interval.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::FutureExt;
+use futures::future::BoxFuture;
+use tokio::time::Instant;
+
+use crate::client::MeshCore;
+use crate::error::{Error, Result};
+use crate::event::Event;
+use crate::transport::Transport;
+use crate::types::{ChannelMessage, Contact, ContactMessage, SignalQuality, TextType};
+
+/// Maximum message text length accepted by the firmware, in bytes.
+pub const MAX_TEXT_LEN: usize = 160;
+
+/// Default minimum interval between commands from one sender.
+pub const DEFAULT_RATE_LIMIT: Duration = Duration::from_secs(5);
+
+/// Where a request was received.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// Private message from the contact with this public key prefix.
+    Direct { sender_prefix: [u8; 6] },
+    /// Message on a channel.
+    Channel { index: u8 },
+}
+
+/// A message that matched a command.
+#[derive(Debug, Clone)]
+pub struct Request {
+    /// Where the message was received (and where the reply goes).
+    pub source: Source,
+    /// Sender name (contact name, or the name prefixed to a channel message).
+    pub sender: String,
+    /// Sending contact, for private messages.
+    pub contact: Option<Contact>,
+    /// Matched command, lowercase and without the prefix.
+    pub command: String,
+    /// Text after the command, trimmed.
+    pub args: String,
+    /// Sender's timestamp (Unix seconds).
+    pub timestamp: u32,
+    /// Path length (-1 if received via flood with unknown path).
+    pub path_len: i8,
+    /// Signal quality (only in v3 format).
+    pub signal: Option<SignalQuality>,
+}
+
+type Handler = Arc<dyn Fn(Request) -> BoxFuture<'static, Option<String>> + Send + Sync>;
+
+/// Matches message text against registered commands.
+#[derive(Default)]
+struct Router {
+    prefix: String,
+    commands: HashMap<String, Handler>,
+    fallback: Option<Handler>,
+}
+
+impl Router {
+    /// Returns the handler, command and arguments for a message text.
+    fn route(&self, text: &str) -> Option<(Handler, String, String)> {
+        let text = text.trim().strip_prefix(self.prefix.as_str())?;
+        let (command, args) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let command = command.to_lowercase();
+        let handler = self
+            .commands
+            .get(&command)
+            .or(self.fallback.as_ref())?
+            .clone();
+        Some((handler, command, args.trim().to_string()))
+    }
+}
+
+/// Per-sender rate limiter.
+struct RateLimiter {
+    interval: Option<Duration>,
+    last: HashMap<String, Instant>,
+}
+
+impl RateLimiter {
+    /// Records a request from `sender`. Returns false if it came too soon.
+    fn allow(&mut self, sender: String) -> bool {
+        let Some(interval) = self.interval else {
+            return true;
+        };
+        let now = Instant::now();
+        self.last
+            .retain(|_, last| now.duration_since(*last) < interval);
+        if self.last.contains_key(&sender) {
+            return false;
+        }
+        self.last.insert(sender, now);
+        true
+    }
+}
+
+/// Splits a message into parts of at most `max_len` bytes.
+///
+/// Parts are broken at whitespace where possible; words longer than
+/// `max_len` are broken at a character boundary.
+#[must_use]
+pub fn split_message(text: &str, max_len: usize) -> Vec<String> {
+    // Any character fits
+    let max_len = max_len.max(4);
+    let mut parts = Vec::new();
+    let mut rest = text.trim();
+    while rest.len() > max_len {
+        let mut end = max_len;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let cut = if rest[end..].starts_with(char::is_whitespace) {
+            end
+        } else {
+            rest[..end]
+                .rfind(char::is_whitespace)
+                .filter(|&i| i > 0)
+                .unwrap_or(end)
+        };
+        parts.push(rest[..cut].trim_end().to_string());
+        rest = rest[cut..].trim_start();
+    }
+    if !rest.is_empty() {
+        parts.push(rest.to_string());
+    }
+    parts
+}
+
+/// A bot answering commands sent to a client.
+pub struct Bot<T> {
+    client: Arc<MeshCore<T>>,
+    router: Router,
+    max_length: usize,
+    rate_limit: Option<Duration>,
+    direct: bool,
+    channels: Option<Vec<u8>>,
+}
+
+impl<T: Transport + 'static> Bot<T> {
+    /// Creates a bot for a connected client.
+    #[must_use]
+    pub fn new(client: Arc<MeshCore<T>>) -> Self {
+        Self {
+            client,
+            router: Router::default(),
+            max_length: MAX_TEXT_LEN,
+            rate_limit: Some(DEFAULT_RATE_LIMIT),
+            direct: true,
+            channels: None,
+        }
+    }
+
+    /// Sets the prefix commands must start with, e.g. `"!"` (default none).
+    #[must_use]
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.router.prefix = prefix.into();
+        self
+    }
+
+    /// Registers a command.
+    ///
+    /// The command matches the first word of a message after the prefix,
+    /// case-insensitively. The handler returns the reply text, or `None`
+    /// to stay silent.
+    #[must_use]
+    pub fn command<F, Fut>(mut self, name: &str, handler: F) -> Self
+    where
+        F: Fn(Request) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Option<String>> + Send + 'static,
+    {
+        self.router
+            .commands
+            .insert(name.to_lowercase(), Arc::new(move |r| handler(r).boxed()));
+        self
+    }
+
+    /// Registers a handler for prefixed messages matching no command.
+    ///
+    /// Without a prefix, the fallback sees every message.
+    #[must_use]
+    pub fn fallback<F, Fut>(mut self, handler: F) -> Self
+    where
+        F: Fn(Request) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Option<String>> + Send + 'static,
+    {
+        self.router.fallback = Some(Arc::new(move |r| handler(r).boxed()));
+        self
+    }
+
+    /// Sets the maximum length of one reply message in bytes (default
+    /// [`MAX_TEXT_LEN`]). Longer replies are split.
+    #[must_use]
+    pub const fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Sets the minimum interval between commands from one sender, or
+    /// disables rate limiting with `None` (default [`DEFAULT_RATE_LIMIT`]).
+    ///
+    /// Commands arriving sooner are ignored.
+    #[must_use]
+    pub const fn rate_limit(mut self, interval: Option<Duration>) -> Self {
+        self.rate_limit = interval;
+        self
+    }
+
+    /// Sets whether private messages are answered (default true).
+    #[must_use]
+    pub const fn direct(mut self, direct: bool) -> Self {
+        self.direct = direct;
+        self
+    }
+
+    /// Restricts the bot to the given channels (default all channels).
+    #[must_use]
+    pub fn channels(mut self, channels: &[u8]) -> Self {
+        self.channels = Some(channels.to_vec());
+        self
+    }
+
+    /// Runs the bot until the client disconnects.
+    ///
+    /// Handlers run concurrently; replies that fail to send are logged.
+    pub async fn run(self) -> Result<()> {
+        let self_info = self.client.self_info().await.ok_or(Error::NotConnected)?;
+        let runner = Arc::new(Runner {
+            client: self.client,
+            router: self.router,
+            max_length: self.max_length,
+            direct: self.direct,
+            channels: self.channels,
+            own_prefix: self_info.public_key.prefix(),
+            own_name: self_info.name,
+        });
+        let mut limiter = RateLimiter {
+            interval: self.rate_limit,
+            last: HashMap::new(),
+        };
+
+        let mut events = runner.client.subscribe();
+        runner.client.fetch_messages().await?;
+
+        loop {
+            let request = match events.recv().await {
+                Some(Event::MessagesWaiting) => {
+                    if let Err(e) = runner.client.fetch_messages().await {
+                        tracing::warn!("bot: failed to fetch messages: {}", e);
+                    }
+                    continue;
+                }
+                Some(Event::ContactMessage(message)) => runner.direct_request(&message).await,
+                Some(Event::ChannelMessage(message)) => runner.channel_request(&message),
+                Some(Event::Disconnected) | None => return Ok(()),
+                Some(_) => continue,
+            };
+            let Some((handler, request)) = request else {
+                continue;
+            };
+
+            let sender = match request.source {
+                Source::Direct { sender_prefix } => hex::encode(sender_prefix),
+                Source::Channel { index } => format!("{index}/{}", request.sender),
+            };
+            if !limiter.allow(sender) {
+                tracing::debug!(
+                    "bot: rate limited {} from {}",
+                    request.command,
+                    request.sender
+                );
+                continue;
+            }
+
+            let runner = Arc::clone(&runner);
+            tokio::spawn(async move { runner.handle(&handler, request).await });
+        }
+    }
+}
+
+/// Running bot state shared with handler tasks.
+struct Runner<T> {
+    client: Arc<MeshCore<T>>,
+    router: Router,
+    max_length: usize,
+    direct: bool,
+    channels: Option<Vec<u8>>,
+    own_prefix: [u8; 6],
+    own_name: String,
+}
+
+impl<T: Transport + 'static> Runner<T> {
+    /// Matches a private message, resolving the sending contact.
+    async fn direct_request(&self, message: &ContactMessage) -> Option<(Handler, Request)> {
+        // Command replies and signed room posts are not addressed to the bot
+        if !self.direct
+            || message.text_type != TextType::Plain
+            || message.sender_prefix == self.own_prefix
+        {
+            return None;
+        }
+        let (handler, command, args) = self.router.route(&message.text)?;
+
+        let prefix = message.sender_prefix;
+        let mut contact = self.client.find_contact_by_prefix(&prefix).await;
+        if contact.is_none() {
+            // The cache may be empty or stale
+            if let Err(e) = self.client.get_contacts().await {
+                tracing::warn!("bot: failed to fetch contacts: {}", e);
+            }
+            contact = self.client.find_contact_by_prefix(&prefix).await;
+        }
+        let Some(contact) = contact else {
+            tracing::warn!(
+                "bot: ignoring {} from unknown contact {}",
+                command,
+                hex::encode(prefix)
+            );
+            return None;
+        };
+
+        let request = Request {
+            source: Source::Direct {
+                sender_prefix: prefix,
+            },
+            sender: contact.name.clone(),
+            contact: Some(contact),
+            command,
+            args,
+            timestamp: message.timestamp,
+            path_len: message.path_len,
+            signal: message.signal,
+        };
+        Some((handler, request))
+    }
+
+    /// Matches a channel message of the form `<sender>: <text>`.
+    fn channel_request(&self, message: &ChannelMessage) -> Option<(Handler, Request)> {
+        if self
+            .channels
+            .as_ref()
+            .is_some_and(|c| !c.contains(&message.channel_index))
+        {
+            return None;
+        }
+        let (sender, text) = message
+            .text
+            .split_once(": ")
+            .unwrap_or(("", message.text.as_str()));
+        if sender == self.own_name {
+            return None;
+        }
+        let (handler, command, args) = self.router.route(text)?;
+
+        let request = Request {
+            source: Source::Channel {
+                index: message.channel_index,
+            },
+            sender: sender.to_string(),
+            contact: None,
+            command,
+            args,
+            timestamp: message.timestamp,
+            path_len: message.path_len,
+            signal: message.signal,
+        };
+        Some((handler, request))
+    }
+
+    /// Runs a handler and sends its reply.
+    async fn handle(&self, handler: &Handler, request: Request) {
+        let command = request.command.clone();
+        let source = request.source;
+        let destination = request.contact.as_ref().map(|c| c.public_key.clone());
+        let Some(reply) = handler(request).await else {
+            return;
+        };
+
+        let result = match (source, destination) {
+            (Source::Channel { index }, _) => {
+                // The firmware prepends "<name>: " to channel messages
+                let max_length = self.max_length.saturating_sub(self.own_name.len() + 2);
+                self.send_parts(&reply, max_length, |part| async move {
+                    self.client.send_channel_message(index, &part).await
+                })
+                .await
+            }
+            (Source::Direct { .. }, Some(key)) => {
+                let key = &key;
+                self.send_parts(&reply, self.max_length, |part| async move {
+                    self.client.send_message(key, &part).await
+                })
+                .await
+            }
+            (Source::Direct { .. }, None) => Ok(()),
+        };
+        if let Err(e) = result {
+            tracing::warn!("bot: failed to reply to {}: {}", command, e);
+        }
+    }
+
+    async fn send_parts<F, Fut>(&self, reply: &str, max_length: usize, send: F) -> Result<()>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        for part in split_message(reply, max_length) {
+            send(part).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::protocol::{CommandOpcode, PacketType};
+    use crate::testing;
+
+    fn router() -> Router {
+        let mut router = Router {
+            prefix: "!".into(),
+            ..Router::default()
+        };
+        router
+            .commands
+            .insert("ping".into(), Arc::new(|_| async { None }.boxed()));
+        router
+    }
+
+    #[test]
+    fn test_route() {
+        let router = router();
+        let (_, command, args) = router.route("  !PING  a b ").unwrap();
+        assert_eq!((command.as_str(), args.as_str()), ("ping", "a b"));
+        assert!(router.route("ping").is_none());
+        assert!(router.route("!pong").is_none());
+
+        let mut router = router;
+        router.fallback = Some(Arc::new(|_| async { None }.boxed()));
+        let (_, command, _) = router.route("!pong").unwrap();
+        assert_eq!(command, "pong");
+    }
+
+    #[test]
+    fn test_split_message() {
+        assert_eq!(split_message("short", 10), vec!["short"]);
+        assert_eq!(
+            split_message("the quick brown fox", 10),
+            vec!["the quick", "brown fox"]
+        );
+        assert_eq!(split_message("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+        // Multi-byte characters are not cut
+        assert_eq!(split_message("ääää", 5), vec!["ää", "ää"]);
+        assert!(split_message("  ", 10).is_empty());
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let mut limiter = RateLimiter {
+            interval: Some(Duration::from_secs(60)),
+            last: HashMap::new(),
+        };
+        assert!(limiter.allow("a".into()));
+        assert!(!limiter.allow("a".into()));
+        assert!(limiter.allow("b".into()));
+    }
+
+    fn channel_message(text: &str) -> Bytes {
+        let mut frame = vec![PacketType::ChannelMsgRecv as u8, 0, 1, 0];
+        frame.extend_from_slice(&1_700_000_000u32.to_le_bytes());
+        frame.extend_from_slice(text.as_bytes());
+        Bytes::from(frame)
+    }
+
+    #[tokio::test]
+    async fn test_bot_answers_channel_commands() {
+        let (sent_tx, mut sent_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+        let (client, push_tx) = testing::connect(testing::self_info(0xB0, "bot"), move |request| {
+            let response = match CommandOpcode::from_byte(request[0]) {
+                Some(CommandOpcode::SendChannelMsg) => {
+                    let text = String::from_utf8_lossy(&request[7..]).into_owned();
+                    sent_tx.send(text).unwrap();
+                    vec![PacketType::Ok as u8]
+                }
+                Some(CommandOpcode::GetMessage) => vec![PacketType::NoMoreMsgs as u8],
+                _ => vec![PacketType::Ok as u8],
+            };
+            vec![response]
+        })
+        .await;
+
+        let bot = Bot::new(Arc::new(client))
+            .prefix("!")
+            .max_length(15)
+            .command(
+                "ping",
+                |r| async move { Some(format!("pong {}", r.sender)) },
+            )
+            .command("echo", |r| async move { Some(r.args) });
+        tokio::spawn(bot.run());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        for text in [
+            "alice: !ping",
+            "alice: !ping",
+            "bot: !ping",
+            "bob: hello",
+            "bob: !echo one two three",
+        ] {
+            push_tx.send(channel_message(text)).unwrap();
+        }
+
+        let mut sent = Vec::new();
+        while sent.len() < 3 {
+            let text = tokio::time::timeout(Duration::from_secs(1), sent_rx.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            sent.push(text);
+        }
+        sent.sort();
+        // Replies fit in 15 - len("bot: ") bytes
+        assert_eq!(sent, vec!["one two", "pong alice", "three"]);
+
+        // Rate limited and own messages are ignored
+        assert!(
+            tokio::time::timeout(Duration::from_millis(100), sent_rx.recv())
+                .await
+                .is_err()
+        );
+    }
+}
@@ -4,35 +4,49 @@
 //! transport, event handling, and commands into a unified interface.
 
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
 use std::time::Duration;
 
 use bytes::Bytes;
+use futures::StreamExt;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::sync::{Mutex, RwLock, mpsc};
 use tokio::task::JoinHandle;
 
-use crate::commands::CommandHandler;
+use crate::clock::{
+    self, ClockConfig, ClockMonitor, ClockSample, DEFAULT_CLOCK_SAMPLES, DEFAULT_DRIFT_THRESHOLD,
+};
+use crate::commands::{CommandHandler, SIGN_CHUNK_SIZE};
 use crate::error::{Error, Result};
 use crate::event::{Event, EventDispatcher, StatsData, Subscription};
+use crate::health::{HealthConfig, HealthMonitor};
 use crate::protocol::{
-    PacketType, StatsType, parse_battery, parse_channel, parse_channel_message, parse_contact,
-    parse_contact_message, parse_core_stats, parse_device_info, parse_device_status,
-    parse_packet_stats, parse_radio_stats, parse_self_info,
+    BinaryReqType, ControlDataType, ErrorCode, PacketType, StatsType, parse_battery, parse_channel,
+    parse_channel_message, parse_contact, parse_contact_message, parse_core_stats,
+    parse_device_info, parse_device_status, parse_discover_response, parse_login_success,
+    parse_packet_stats, parse_radio_stats, parse_rx_log, parse_self_info, parse_trace_data,
+};
+use crate::transport::discovery::{DEFAULT_PROBE_TIMEOUT, discover_devices_with};
+use crate::transport::{
+    FrameStream, SerialTransport, StreamTransport, Transport, serial::SerialConfig,
 };
-use crate::transport::{SerialTransport, Transport, serial::SerialConfig};
 use crate::types::{
-    Acknowledgment, BatteryStatus, Channel, Contact, CoreStats, DeviceInfo, PacketStats, PublicKey,
-    RadioStats, SelfInfo, Telemetry,
+    Acknowledgment, BatteryStatus, Channel, Contact, ContactCard, CoreStats, CustomVars,
+    DeviceInfo, DiscoverResponse, LoginResponse, PacketStats, PublicKey, RadioConfig, RadioStats,
+    SelfInfo, Signature, Telemetry, TextType, TraceResult,
 };
 
 /// Gets the current Unix timestamp as a u32.
-fn current_timestamp() -> u32 {
+pub(crate) fn current_timestamp() -> u32 {
     std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
-        .map(|d| u32::try_from(d.as_secs()).unwrap_or(u32::MAX))
-        .unwrap_or(0)
+        .map_or(0, |d| u32::try_from(d.as_secs()).unwrap_or(u32::MAX))
 }
 
+/// Number of attempts of [`MeshCore::remote_command`].
+pub const REMOTE_COMMAND_ATTEMPTS: u32 = 3;
+
 /// Client for communicating with a `MeshCore` device.
 pub struct MeshCore<T> {
     transport: Arc<Mutex<T>>,
@@ -42,10 +56,117 @@ pub struct MeshCore<T> {
     // Internal state
     self_info: Arc<RwLock<Option<SelfInfo>>>,
     contacts: Arc<RwLock<HashMap<PublicKey, Contact>>>,
+    /// Last timestamp used for a remote command.
+    command_timestamp: AtomicU32,
+    /// Serialises remote commands per destination prefix.
+    remote_locks: StdMutex<HashMap<[u8; 6], Arc<Mutex<()>>>>,
 
     // Background tasks
-    read_task: Option<JoinHandle<()>>,
+    read_loop: Arc<ReadLoop>,
     process_task: Option<JoinHandle<()>>,
+    health_task: Option<JoinHandle<()>>,
+    clock_task: Option<JoinHandle<()>>,
+}
+
+/// Background task forwarding frames from the transport.
+///
+/// Shared with the health monitor so that the transport can be reopened
+/// without access to the client.
+#[derive(Default)]
+pub(crate) struct ReadLoop {
+    task: StdMutex<Option<JoinHandle<()>>>,
+    frame_tx: StdMutex<Option<mpsc::Sender<Bytes>>>,
+}
+
+impl ReadLoop {
+    /// Spawns the read task, replacing any previous one.
+    fn start(&self, mut frames: FrameStream, frame_tx: mpsc::Sender<Bytes>) {
+        *lock(&self.frame_tx) = Some(frame_tx.clone());
+        let task = tokio::spawn(async move {
+            while let Some(result) = frames.next().await {
+                match result {
+                    Ok(frame) => {
+                        tracing::trace!("decoded frame: {} bytes", frame.len());
+                        if frame_tx.send(frame).await.is_err() {
+                            tracing::debug!("frame receiver dropped");
+                            return;
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("read loop error: {}", e);
+                        return;
+                    }
+                }
+            }
+            tracing::debug!("transport closed");
+        });
+        if let Some(old) = lock(&self.task).replace(task) {
+            old.abort();
+        }
+    }
+
+    /// Aborts the read task.
+    fn stop(&self) -> Option<JoinHandle<()>> {
+        let task = lock(&self.task).take()?;
+        task.abort();
+        Some(task)
+    }
+
+    /// Closes and reopens the transport, restarting the read task.
+    async fn reopen<T: Transport>(&self, transport: &Mutex<T>) -> Result<()> {
+        // Wait for the old task to finish so it releases the reader
+        if let Some(task) = self.stop() {
+            let _ = task.await;
+        }
+        let frame_tx = lock(&self.frame_tx).clone().ok_or(Error::NotConnected)?;
+
+        let frames = {
+            let mut transport = transport.lock().await;
+            transport.disconnect().await?;
+            transport.connect().await?;
+            transport.take_frame_stream()
+        };
+        if let Some(frames) = frames {
+            self.start(frames, frame_tx);
+        }
+        Ok(())
+    }
+}
+
+/// Locks a mutex, ignoring poisoning.
+fn lock<T>(mutex: &StdMutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Reconnects a client from a background task.
+pub(crate) struct Reconnector<T> {
+    transport: Arc<Mutex<T>>,
+    read_loop: Arc<ReadLoop>,
+    self_info: Arc<RwLock<Option<SelfInfo>>>,
+    dispatcher: EventDispatcher,
+}
+
+impl<T: Transport> Reconnector<T> {
+    /// Reopens the transport and repeats the `AppStart` handshake.
+    pub(crate) async fn reconnect(&self, commands: &CommandHandler<T>) -> Result<SelfInfo> {
+        tracing::info!("reconnecting");
+        self.read_loop.reopen(&self.transport).await?;
+
+        // Allow time for any stale data to be received and discarded
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let Event::SelfInfo(info) = commands.app_start().await? else {
+            return Err(Error::Protocol {
+                message: "unexpected response to AppStart".into(),
+            });
+        };
+        *self.self_info.write().await = Some((*info).clone());
+
+        self.dispatcher.dispatch(Event::Connected);
+        Ok(*info)
+    }
 }
 
 impl MeshCore<SerialTransport> {
@@ -70,11 +191,59 @@ impl MeshCore<SerialTransport> {
         let transport = SerialTransport::new(config);
         Self::new(transport)
     }
+
+    /// Creates a new client for the first `MeshCore` device found on any
+    /// USB serial port.
+    ///
+    /// See [`discover_devices`](crate::transport::discover_devices).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NoDeviceFound`] if no device answers.
+    pub async fn auto() -> Result<Self> {
+        Self::auto_with(SerialConfig::new, DEFAULT_PROBE_TIMEOUT).await
+    }
+
+    /// Creates a new client for the first `MeshCore` device found with
+    /// custom serial settings.
+    ///
+    /// The client connects with the settings the device answered with.
+    /// See [`discover_devices_with`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NoDeviceFound`] if no device answers.
+    pub async fn auto_with(
+        config: impl Fn(String) -> SerialConfig,
+        timeout: Duration,
+    ) -> Result<Self> {
+        let devices = discover_devices_with(config, timeout).await?;
+        let device = devices.into_iter().next().ok_or(Error::NoDeviceFound)?;
+        tracing::info!("using {} on {}", device.self_info.name, device.port);
+        Ok(Self::with_serial_config(device.config))
+    }
+}
+
+impl<S> MeshCore<StreamTransport<S>>
+where
+    S: AsyncRead + AsyncWrite + Send + Sync + 'static,
+{
+    /// Creates a new client over an async byte stream.
+    ///
+    /// See [`StreamTransport`] for suitable streams.
+    #[must_use]
+    pub fn stream(stream: S) -> Self {
+        Self::new(StreamTransport::new(stream))
+    }
 }
 
 impl<T: Transport + 'static> MeshCore<T> {
     /// Creates a new client with the given transport.
-    fn new(transport: T) -> Self {
+    ///
+    /// The client is not yet connected. Received frames are read from
+    /// [`Transport::take_frame_stream`] once [`Self::connect`] succeeds.
+    #[must_use]
+    pub fn new(transport: T) -> Self {
         let (dispatcher, _event_rx) = EventDispatcher::new(256);
         let transport = Arc::new(Mutex::new(transport));
 
@@ -86,8 +255,12 @@ impl<T: Transport + 'static> MeshCore<T> {
             commands,
             self_info: Arc::new(RwLock::new(None)),
             contacts: Arc::new(RwLock::new(HashMap::new())),
-            read_task: None,
+            command_timestamp: AtomicU32::new(0),
+            remote_locks: StdMutex::new(HashMap::new()),
+            read_loop: Arc::new(ReadLoop::default()),
             process_task: None,
+            health_task: None,
+            clock_task: None,
         }
     }
 
@@ -124,8 +297,6 @@ impl<T: Transport + 'static> MeshCore<T> {
             let mut self_info = self.self_info.write().await;
             *self_info = Some(*info);
             cloned
-        } else if let Event::Error { message } = event {
-            return Err(Error::Protocol { message });
         } else {
             return Err(Error::Protocol {
                 message: "unexpected response to AppStart".into(),
@@ -142,30 +313,15 @@ impl<T: Transport + 'static> MeshCore<T> {
     async fn start_read_loop(&mut self) -> Result<()> {
         let (frame_tx, mut frame_rx) = mpsc::channel::<Bytes>(256);
 
-        // Take reader and decoder from transport (only works for SerialTransport)
-        let reader_and_decoder = {
+        // Take the frame stream from the transport
+        let frames = {
             let mut transport = self.transport.lock().await;
-            if let Some(serial) =
-                ((&mut *transport) as &mut dyn std::any::Any).downcast_mut::<SerialTransport>()
-            {
-                let reader = serial.take_reader();
-                let decoder = std::mem::take(serial.decoder_mut());
-                reader.map(|r| (r, decoder))
-            } else {
-                None
-            }
+            transport.take_frame_stream()
         };
 
-        // Spawn read task with the reader (doesn't hold transport lock)
-        if let Some((reader, decoder)) = reader_and_decoder {
-            let read_task = tokio::spawn(async move {
-                if let Err(e) =
-                    SerialTransport::run_read_loop_with_reader(reader, decoder, frame_tx).await
-                {
-                    tracing::error!("read loop error: {}", e);
-                }
-            });
-            self.read_task = Some(read_task);
+        // Spawn read task with the stream (doesn't hold transport lock)
+        if let Some(frames) = frames {
+            self.read_loop.start(frames, frame_tx);
         }
 
         // Spawn frame processing task
@@ -183,12 +339,76 @@ impl<T: Transport + 'static> MeshCore<T> {
         Ok(())
     }
 
+    /// Reopens the transport and repeats the `AppStart` handshake.
+    ///
+    /// Dispatches [`Event::Connected`] on success.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the client was never connected or reconnecting
+    /// fails.
+    pub async fn reconnect(&self) -> Result<SelfInfo> {
+        self.reconnector().reconnect(&self.commands).await
+    }
+
+    fn reconnector(&self) -> Reconnector<T> {
+        Reconnector {
+            transport: Arc::clone(&self.transport),
+            read_loop: Arc::clone(&self.read_loop),
+            self_info: Arc::clone(&self.self_info),
+            dispatcher: self.dispatcher.clone(),
+        }
+    }
+
+    /// Starts the connection health monitor.
+    ///
+    /// Replaces a running monitor. See [`HealthConfig`] for details.
+    pub fn start_health_monitor(&mut self, config: HealthConfig) {
+        self.stop_health_monitor();
+
+        let mut commands =
+            CommandHandler::new(Arc::clone(&self.transport), self.dispatcher.clone());
+        commands.set_timeout(config.timeout);
+        let monitor = HealthMonitor::new(
+            config,
+            commands,
+            self.reconnector(),
+            self.dispatcher.clone(),
+        );
+        self.health_task = Some(tokio::spawn(monitor.run()));
+    }
+
+    /// Stops the connection health monitor.
+    pub fn stop_health_monitor(&mut self) {
+        if let Some(task) = self.health_task.take() {
+            task.abort();
+        }
+    }
+
+    /// Starts the device clock monitor.
+    ///
+    /// Replaces a running monitor. See [`ClockConfig`] for details.
+    pub fn start_clock_monitor(&mut self, config: ClockConfig) {
+        self.stop_clock_monitor();
+
+        let commands = CommandHandler::new(Arc::clone(&self.transport), self.dispatcher.clone());
+        let monitor = ClockMonitor::new(config, commands, self.dispatcher.clone());
+        self.clock_task = Some(tokio::spawn(monitor.run()));
+    }
+
+    /// Stops the device clock monitor.
+    pub fn stop_clock_monitor(&mut self) {
+        if let Some(task) = self.clock_task.take() {
+            task.abort();
+        }
+    }
+
     /// Disconnects from the device.
     pub async fn disconnect(&mut self) -> Result<()> {
         // Stop background tasks
-        if let Some(task) = self.read_task.take() {
-            task.abort();
-        }
+        self.stop_health_monitor();
+        self.stop_clock_monitor();
+        self.read_loop.stop();
         if let Some(task) = self.process_task.take() {
             task.abort();
         }
@@ -240,8 +460,6 @@ impl<T: Transport + 'static> MeshCore<T> {
         let event = self.commands.get_battery().await?;
         if let Event::Battery(status) = event {
             Ok(status)
-        } else if let Event::Error { message } = event {
-            Err(Error::Protocol { message })
         } else {
             Err(Error::Protocol {
                 message: "unexpected response".into(),
@@ -254,8 +472,6 @@ impl<T: Transport + 'static> MeshCore<T> {
         let event = self.commands.device_query().await?;
         if let Event::DeviceInfo(info) = event {
             Ok(*info)
-        } else if let Event::Error { message } = event {
-            Err(Error::Protocol { message })
         } else {
             Err(Error::Protocol {
                 message: "unexpected response".into(),
@@ -268,8 +484,6 @@ impl<T: Transport + 'static> MeshCore<T> {
         let event = self.commands.get_time().await?;
         if let Event::CurrentTime(time) = event {
             Ok(time)
-        } else if let Event::Error { message } = event {
-            Err(Error::Protocol { message })
         } else {
             Err(Error::Protocol {
                 message: "unexpected response".into(),
@@ -282,15 +496,70 @@ impl<T: Transport + 'static> MeshCore<T> {
         self.commands.set_time(current_timestamp()).await
     }
 
+    /// Compares the device clock with the host clock.
+    ///
+    /// Uses the fastest of [`DEFAULT_CLOCK_SAMPLES`] round trips to
+    /// compensate for latency.
+    pub async fn measure_clock(&self) -> Result<ClockSample> {
+        clock::measure(&self.commands, DEFAULT_CLOCK_SAMPLES).await
+    }
+
+    /// Sets the device clock to the host clock and verifies the result.
+    ///
+    /// Unlike [`Self::sync_time`], the time is sent aligned to a second
+    /// boundary and compensated for latency.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ClockSync`] if the device clock is still more
+    /// than [`DEFAULT_DRIFT_THRESHOLD`] off afterwards.
+    pub async fn sync_clock(&self) -> Result<ClockSample> {
+        clock::sync(
+            &self.commands,
+            DEFAULT_CLOCK_SAMPLES,
+            DEFAULT_DRIFT_THRESHOLD,
+        )
+        .await
+    }
+
+    /// Sets the radio parameters.
+    ///
+    /// The new parameters take effect immediately; they are stored in
+    /// the cached self info only after the next `AppStart`.
+    pub async fn set_radio(&self, radio: &RadioConfig) -> Result<()> {
+        self.commands
+            .set_radio(
+                radio.frequency_mhz,
+                radio.bandwidth_khz,
+                radio.spreading_factor,
+                radio.coding_rate,
+            )
+            .await
+    }
+
+    /// Exports the device's private key (32-byte seed followed by the
+    /// 32-byte public key).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if key export is disabled in the firmware.
+    pub async fn export_private_key(&self) -> Result<[u8; 64]> {
+        match self.commands.export_private_key().await? {
+            Event::PrivateKey(key) => Ok(key),
+            Event::Disabled => Err(Error::Protocol {
+                message: "private key export is disabled on the device".into(),
+            }),
+            _ => Err(Error::Protocol {
+                message: "unexpected response".into(),
+            }),
+        }
+    }
+
     /// Gets core statistics.
     pub async fn get_core_stats(&self) -> Result<CoreStats> {
         let event = self.commands.get_stats(StatsType::Core).await?;
-        if let Event::Stats(stats) = event {
-            if let StatsData::Core(core) = stats {
-                return Ok(core);
-            }
-        } else if let Event::Error { message } = event {
-            return Err(Error::Protocol { message });
+        if let Event::Stats(StatsData::Core(core)) = event {
+            return Ok(core);
         }
         Err(Error::Protocol {
             message: "unexpected response".into(),
@@ -300,12 +569,8 @@ impl<T: Transport + 'static> MeshCore<T> {
     /// Gets radio statistics.
     pub async fn get_radio_stats(&self) -> Result<RadioStats> {
         let event = self.commands.get_stats(StatsType::Radio).await?;
-        if let Event::Stats(stats) = event {
-            if let StatsData::Radio(radio) = stats {
-                return Ok(radio);
-            }
-        } else if let Event::Error { message } = event {
-            return Err(Error::Protocol { message });
+        if let Event::Stats(StatsData::Radio(radio)) = event {
+            return Ok(radio);
         }
         Err(Error::Protocol {
             message: "unexpected response".into(),
@@ -315,18 +580,52 @@ impl<T: Transport + 'static> MeshCore<T> {
     /// Gets packet statistics.
     pub async fn get_packet_stats(&self) -> Result<PacketStats> {
         let event = self.commands.get_stats(StatsType::Packets).await?;
-        if let Event::Stats(stats) = event {
-            if let StatsData::Packets(packets) = stats {
-                return Ok(packets);
-            }
-        } else if let Event::Error { message } = event {
-            return Err(Error::Protocol { message });
+        if let Event::Stats(StatsData::Packets(packets)) = event {
+            return Ok(packets);
         }
         Err(Error::Protocol {
             message: "unexpected response".into(),
         })
     }
 
+    /// Gets the device's custom variables.
+    pub async fn get_custom_vars(&self) -> Result<CustomVars> {
+        let event = self.commands.get_custom_vars().await?;
+        if let Event::CustomVars(vars) = event {
+            Ok(vars)
+        } else {
+            Err(Error::Protocol {
+                message: "unexpected response".into(),
+            })
+        }
+    }
+
+    /// Applies custom variables to the device.
+    ///
+    /// Only variables that differ from the device's current values are sent.
+    /// The variables are read back afterwards to verify they were applied.
+    ///
+    /// Returns the keys that were changed.
+    pub async fn set_custom_vars(&self, vars: &CustomVars) -> Result<Vec<String>> {
+        let current = self.get_custom_vars().await?;
+        let changes = current.diff(vars);
+
+        for (key, value) in &changes {
+            self.commands.set_custom_var(key, value).await?;
+        }
+
+        if !changes.is_empty() {
+            let applied = self.get_custom_vars().await?;
+            if let Some((key, _)) = applied.diff(vars).first() {
+                return Err(Error::Protocol {
+                    message: format!("custom variable {key} was not applied"),
+                });
+            }
+        }
+
+        Ok(changes.into_iter().map(|(k, _)| k.to_string()).collect())
+    }
+
     // ==================== High-Level Contact Methods ====================
 
     /// Gets the contact list from the device.
@@ -346,6 +645,74 @@ impl<T: Transport + 'static> MeshCore<T> {
         self.contacts.read().await.get(public_key).cloned()
     }
 
+    /// Finds a cached contact by name.
+    ///
+    /// An exact match wins; otherwise the name may be a case-insensitive
+    /// prefix matching exactly one contact.
+    pub async fn find_contact(&self, name: &str) -> Option<Contact> {
+        let contacts = self.contacts.read().await;
+        if let Some(contact) = contacts.values().find(|c| c.name == name) {
+            return Some(contact.clone());
+        }
+
+        let name = name.to_lowercase();
+        let mut matches = contacts
+            .values()
+            .filter(|c| c.name.to_lowercase().starts_with(&name));
+        match (matches.next(), matches.next()) {
+            (Some(contact), None) => Some(contact.clone()),
+            _ => None,
+        }
+    }
+
+    /// Finds a cached contact by public key prefix, as used to address
+    /// the sender of a message.
+    pub async fn find_contact_by_prefix(&self, prefix: &[u8]) -> Option<Contact> {
+        self.contacts
+            .read()
+            .await
+            .values()
+            .find(|c| c.public_key.as_bytes().starts_with(prefix))
+            .cloned()
+    }
+
+    /// Resolves a contact given by name, name prefix or hex public key.
+    ///
+    /// Names are matched as by [`find_contact`](Self::find_contact); the
+    /// contact list is fetched first if the cache has no match, as it may
+    /// be empty or stale.
+    pub async fn resolve_contact(&self, contact: &str) -> Result<PublicKey> {
+        if let Ok(key) = PublicKey::from_hex(contact) {
+            return Ok(key);
+        }
+        if self.find_contact(contact).await.is_none() {
+            self.get_contacts().await?;
+        }
+        self.find_contact(contact)
+            .await
+            .map(|c| c.public_key)
+            .ok_or_else(|| Error::UnknownContact {
+                name: contact.to_string(),
+            })
+    }
+
+    /// Exports a contact card (or the device's own card if no key provided).
+    pub async fn export_contact_card(&self, public_key: Option<&PublicKey>) -> Result<ContactCard> {
+        let event = self.commands.export_contact(public_key).await?;
+        if let Event::ContactUri(uri) = event {
+            ContactCard::parse_uri(&uri)
+        } else {
+            Err(Error::Protocol {
+                message: "unexpected response".into(),
+            })
+        }
+    }
+
+    /// Imports a contact card into the device's contact list.
+    pub async fn import_contact_card(&self, card: &ContactCard) -> Result<()> {
+        self.commands.import_contact(&card.to_bytes()?).await
+    }
+
     // ==================== High-Level Messaging Methods ====================
 
     /// Sends a private message.
@@ -366,8 +733,6 @@ impl<T: Transport + 'static> MeshCore<T> {
             let timeout = Duration::from_millis(u64::from(timeout_ms));
             self.commands.wait_for_ack(expected_ack, timeout).await?;
             Ok(())
-        } else if let Event::Error { message } = event {
-            Err(Error::Protocol { message })
         } else {
             Err(Error::Protocol {
                 message: "unexpected response".into(),
@@ -384,7 +749,6 @@ impl<T: Transport + 'static> MeshCore<T> {
 
         match event {
             Event::Ok => Ok(()),
-            Event::Error { message } => Err(Error::Protocol { message }),
             _ => Err(Error::Protocol {
                 message: "unexpected response".into(),
             }),
@@ -398,11 +762,6 @@ impl<T: Transport + 'static> MeshCore<T> {
         loop {
             let event = self.commands.get_message().await?;
             match &event {
-                Event::Error { message } => {
-                    return Err(Error::Protocol {
-                        message: message.clone(),
-                    });
-                }
                 Event::ContactMessage(_) | Event::ChannelMessage(_) => {
                     messages.push(event);
                 }
@@ -420,8 +779,6 @@ impl<T: Transport + 'static> MeshCore<T> {
         let event = self.commands.get_channel(index).await?;
         if let Event::ChannelInfo(channel) = event {
             Ok(*channel)
-        } else if let Event::Error { message } = event {
-            Err(Error::Protocol { message })
         } else {
             Err(Error::Protocol {
                 message: "unexpected response".into(),
@@ -429,6 +786,27 @@ impl<T: Transport + 'static> MeshCore<T> {
         }
     }
 
+    /// Gets all configured channels.
+    ///
+    /// Channels are read until the first index the device does not know;
+    /// unnamed (unused) channels are skipped.
+    pub async fn get_channels(&self) -> Result<Vec<Channel>> {
+        let max = self.get_device_info().await?.max_channels.unwrap_or(8);
+        let mut channels = Vec::new();
+        for index in 0..max {
+            match self.get_channel(index).await {
+                Ok(channel) if !channel.name.is_empty() => channels.push(channel),
+                Ok(_) => {}
+                Err(Error::Device {
+                    code: ErrorCode::NotFound,
+                    ..
+                }) => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(channels)
+    }
+
     // ==================== High-Level Status Methods ====================
 
     /// Sends a status request to a remote device.
@@ -439,8 +817,6 @@ impl<T: Transport + 'static> MeshCore<T> {
         let event = self.commands.send_status_request(destination).await?;
         if let Event::MessageSent { expected_ack, .. } = event {
             Ok(expected_ack)
-        } else if let Event::Error { message } = event {
-            Err(Error::Protocol { message })
         } else {
             Err(Error::Protocol {
                 message: "unexpected response".into(),
@@ -456,8 +832,6 @@ impl<T: Transport + 'static> MeshCore<T> {
         let event = self.commands.send_telemetry_request(destination).await?;
         if let Event::MessageSent { expected_ack, .. } = event {
             Ok(expected_ack)
-        } else if let Event::Error { message } = event {
-            Err(Error::Protocol { message })
         } else {
             Err(Error::Protocol {
                 message: "unexpected response".into(),
@@ -466,20 +840,432 @@ impl<T: Transport + 'static> MeshCore<T> {
     }
 
     /// Gets self telemetry.
+    ///
+    /// Responses to telemetry requests sent to contacts at the same time
+    /// are told apart by their public key prefix.
     pub async fn get_self_telemetry(&self) -> Result<Telemetry> {
-        let event = self.commands.get_self_telemetry().await?;
-        if let Event::TelemetryResponse(telemetry) = event {
-            Ok(*telemetry)
-        } else if let Event::Error { message } = event {
-            Err(Error::Protocol { message })
-        } else {
-            Err(Error::Protocol {
+        let prefix = self
+            .self_info()
+            .await
+            .ok_or(Error::NotConnected)?
+            .public_key
+            .prefix();
+
+        // Subscribe before sending so the response is not missed
+        let mut subscription = self.dispatcher.subscribe(None);
+        match self.commands.get_self_telemetry().await? {
+            Event::TelemetryResponse {
+                pubkey_prefix,
+                telemetry,
+            } if pubkey_prefix == prefix => return Ok(*telemetry),
+            // A contact's response came first
+            Event::TelemetryResponse { .. } => {}
+            _ => {
+                return Err(Error::Protocol {
+                    message: "unexpected response".into(),
+                });
+            }
+        }
+
+        let timeout_ms = u32::try_from(self.commands.timeout().as_millis()).unwrap_or(u32::MAX);
+        wait_for_response(&mut subscription, timeout_ms, |event| match event {
+            Event::TelemetryResponse {
+                pubkey_prefix,
+                telemetry,
+            } if pubkey_prefix == prefix => Some(*telemetry),
+            _ => None,
+        })
+        .await
+    }
+
+    // ==================== High-Level Remote Methods ====================
+
+    /// Logs in to a room server or repeater.
+    ///
+    /// Waits for the login response from `destination` within the
+    /// device-provided timeout.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::LoginFailed`] if the password is rejected.
+    pub async fn login(&self, destination: &PublicKey, password: &str) -> Result<LoginResponse> {
+        // Subscribe before sending so the response is not missed
+        let mut subscription = self.dispatcher.subscribe(None);
+        let Event::MessageSent { timeout_ms, .. } =
+            self.commands.send_login(destination, password).await?
+        else {
+            return Err(Error::Protocol {
                 message: "unexpected response".into(),
+            });
+        };
+
+        let prefix = destination.prefix();
+        let result = wait_for_response(&mut subscription, timeout_ms, |event| match event {
+            Event::LoginSuccess(login) if login.pubkey_prefix == prefix => Some(Ok(login)),
+            Event::LoginFailed { pubkey_prefix } if pubkey_prefix == prefix => Some(Err(())),
+            _ => None,
+        })
+        .await?;
+        result.map_err(|()| Error::LoginFailed {
+            destination: destination.to_hex(),
+        })
+    }
+
+    /// Returns a timestamp for a remote command.
+    ///
+    /// Remote nodes take a command with the same timestamp as the previous
+    /// one for a retransmission and do not run it, so timestamps strictly
+    /// increase even within one second.
+    pub(crate) fn next_command_timestamp(&self) -> u32 {
+        let now = current_timestamp();
+        let previous = self
+            .command_timestamp
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| {
+                Some(now.max(last.saturating_add(1)))
             })
+            .unwrap_or(now);
+        now.max(previous.saturating_add(1))
+    }
+
+    /// Sends a CLI command to a repeater or room server and returns its
+    /// reply.
+    ///
+    /// The reply is the next command-type message from `destination`,
+    /// awaited within the device-provided timeout. Unanswered commands are
+    /// sent again, up to [`REMOTE_COMMAND_ATTEMPTS`] times. Commands to the
+    /// same destination are sent one at a time so that each reply belongs
+    /// to its command.
+    ///
+    /// Replies carry no tag, so after a retry the reply may answer an
+    /// earlier attempt. The replies to the other attempts are then awaited
+    /// and discarded before returning, so they are not taken for the
+    /// replies to later commands.
+    ///
+    /// Most nodes only accept commands after an admin [`Self::login`].
+    pub async fn remote_command(&self, destination: &PublicKey, command: &str) -> Result<String> {
+        let prefix = destination.prefix();
+        let pending = Arc::clone(lock(&self.remote_locks).entry(prefix).or_default());
+        let _pending = pending.lock().await;
+
+        // One subscription for all attempts, so late replies are seen
+        let mut subscription = self.dispatcher.subscribe(None);
+        let mut attempt = 1;
+        loop {
+            let Event::MessageSent { timeout_ms, .. } = self
+                .commands
+                .send_command(destination, command, self.next_command_timestamp())
+                .await?
+            else {
+                return Err(Error::Protocol {
+                    message: "unexpected response".into(),
+                });
+            };
+
+            match self
+                .command_reply(&mut subscription, prefix, timeout_ms, 1)
+                .await
+            {
+                Ok(mut replies) => {
+                    if attempt > 1 {
+                        // Replies to the other attempts may still arrive
+                        let late = self
+                            .command_reply(&mut subscription, prefix, timeout_ms, attempt - 1)
+                            .await
+                            .map_or(0, |late| late.len());
+                        tracing::debug!("discarded {} late replies from {}", late, destination);
+                    }
+                    return Ok(replies.remove(0));
+                }
+                Err(Error::Timeout { timeout_ms }) if attempt < REMOTE_COMMAND_ATTEMPTS => {
+                    tracing::debug!(
+                        "no reply to {:?} from {} after {}ms, retrying",
+                        command,
+                        destination,
+                        timeout_ms
+                    );
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Waits for up to `count` command replies from `prefix`.
+    ///
+    /// Returns the replies received within the timeout, or a timeout error
+    /// if there were none.
+    async fn command_reply(
+        &self,
+        subscription: &mut Subscription,
+        prefix: [u8; 6],
+        timeout_ms: u32,
+        count: u32,
+    ) -> Result<Vec<String>> {
+        let mut replies = Vec::new();
+        let wait = async {
+            while let Some(event) = subscription.recv().await {
+                match event {
+                    Event::MessagesWaiting => {
+                        // The replies are dispatched to the subscription
+                        self.fetch_messages().await?;
+                    }
+                    Event::ContactMessage(message)
+                        if message.sender_prefix == prefix
+                            && message.text_type == TextType::Command =>
+                    {
+                        replies.push(message.text);
+                        if replies.len() >= count as usize {
+                            return Ok(());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Err(Error::ChannelClosed)
+        };
+
+        match tokio::time::timeout(Duration::from_millis(u64::from(timeout_ms)), wait).await {
+            Ok(Err(e)) => Err(e),
+            _ if replies.is_empty() => Err(Error::Timeout {
+                timeout_ms: u64::from(timeout_ms),
+            }),
+            _ => Ok(replies),
+        }
+    }
+
+    /// Sends a binary request and waits for the response.
+    ///
+    /// The response is matched to the request by its tag. Returns the
+    /// response payload, or times out after the device-provided timeout.
+    pub async fn binary_request(
+        &self,
+        destination: &PublicKey,
+        request_type: BinaryReqType,
+        data: &[u8],
+    ) -> Result<Vec<u8>> {
+        // Subscribe before sending so the response is not missed
+        let mut subscription = self.dispatcher.subscribe(None);
+        let Event::MessageSent {
+            expected_ack,
+            timeout_ms,
+        } = self
+            .commands
+            .binary_request(destination, request_type, data)
+            .await?
+        else {
+            return Err(Error::Protocol {
+                message: "unexpected response".into(),
+            });
+        };
+
+        wait_for_response(&mut subscription, timeout_ms, |event| match event {
+            // BinaryResponse format: [reserved:1] [tag:4LE] [response...]
+            Event::BinaryResponse(data)
+                if data.len() >= 5
+                    && u32::from_le_bytes([data[1], data[2], data[3], data[4]]) == expected_ack =>
+            {
+                Some(data[5..].to_vec())
+            }
+            _ => None,
+        })
+        .await
+    }
+
+    /// Requests telemetry from a contact and waits for the response.
+    ///
+    /// The response is the next [`Event::TelemetryResponse`] from
+    /// `destination`, awaited within the device-provided timeout.
+    pub async fn remote_telemetry(&self, destination: &PublicKey) -> Result<Telemetry> {
+        // Subscribe before sending so the response is not missed
+        let mut subscription = self.dispatcher.subscribe(None);
+        let Event::MessageSent { timeout_ms, .. } =
+            self.commands.send_telemetry_request(destination).await?
+        else {
+            return Err(Error::Protocol {
+                message: "unexpected response".into(),
+            });
+        };
+
+        let prefix = destination.prefix();
+        wait_for_response(&mut subscription, timeout_ms, |event| match event {
+            Event::TelemetryResponse {
+                pubkey_prefix,
+                telemetry,
+            } if pubkey_prefix == prefix => Some(*telemetry),
+            _ => None,
+        })
+        .await
+    }
+
+    /// Requests telemetry from a contact with a binary request and waits
+    /// for the response.
+    pub async fn binary_telemetry(&self, destination: &PublicKey) -> Result<Telemetry> {
+        let data = self
+            .binary_request(destination, BinaryReqType::Telemetry, &[])
+            .await?;
+        Ok(Telemetry::parse_lpp(&data))
+    }
+
+    // ==================== High-Level Signing Methods ====================
+
+    /// Signs data with the device's private key.
+    ///
+    /// Runs the `sign_start`/`sign_data`/`sign_finish` sequence, splitting the
+    /// data into chunks that fit the device's frame limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data exceeds the device's signing limit.
+    /// The device only reports the limit once signing has started, so the
+    /// session is finished before returning.
+    pub async fn sign(&self, data: &[u8]) -> Result<Signature> {
+        let Event::SignStarted { max_length } = self.commands.sign_start().await? else {
+            return Err(Error::Protocol {
+                message: "unexpected response to SignStart".into(),
+            });
+        };
+
+        if u32::try_from(data.len()).map_or(true, |len| len > max_length) {
+            // Close the session the device opened; the signature is discarded
+            if let Err(e) = self.commands.sign_finish().await {
+                tracing::debug!("failed to finish signing: {}", e);
+            }
+            return Err(Error::Protocol {
+                message: format!(
+                    "data length {} exceeds device signing limit {max_length}",
+                    data.len()
+                ),
+            });
+        }
+
+        for chunk in data.chunks(SIGN_CHUNK_SIZE) {
+            self.commands.sign_data(chunk).await?;
+        }
+
+        match self.commands.sign_finish().await? {
+            Event::Signature(bytes) => Signature::try_from(bytes.as_slice()),
+            _ => Err(Error::Protocol {
+                message: "unexpected response to SignFinish".into(),
+            }),
+        }
+    }
+
+    /// Signs data and verifies the signature against the device's public key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if not connected or the signature does not verify.
+    #[cfg(feature = "ed25519")]
+    pub async fn sign_verified(&self, data: &[u8]) -> Result<Signature> {
+        let signature = self.sign(data).await?;
+        let public_key = self
+            .self_info
+            .read()
+            .await
+            .as_ref()
+            .map(|info| info.public_key.clone())
+            .ok_or(Error::NotConnected)?;
+        signature.verify(&public_key, data)?;
+        Ok(signature)
+    }
+
+    // ==================== High-Level Discovery Methods ====================
+
+    /// Traces a path through a list of repeaters.
+    ///
+    /// `path` holds one hash byte per repeater. Returns the SNR measured at
+    /// each hop once the trace comes back, or times out after the
+    /// device-provided timeout.
+    pub async fn trace(&self, path: &[u8]) -> Result<TraceResult> {
+        // Subscribe before sending so the response is not missed
+        let mut subscription = self.dispatcher.subscribe(None);
+        let tag = self.commands.next_tag();
+        let Event::MessageSent { timeout_ms, .. } =
+            self.commands.send_trace(0, Some(tag), 0, path).await?
+        else {
+            return Err(Error::Protocol {
+                message: "unexpected response".into(),
+            });
+        };
+
+        wait_for_response(&mut subscription, timeout_ms, |event| {
+            let Event::TraceData(data) = event else {
+                return None;
+            };
+            match parse_trace_data(&data) {
+                Ok(trace) if trace.tag == tag => Some(trace),
+                Ok(_) => None,
+                Err(e) => {
+                    tracing::warn!("failed to parse TraceData: {}", e);
+                    None
+                }
+            }
+        })
+        .await
+    }
+
+    /// Discovers nodes in radio range.
+    ///
+    /// Sends a node discovery request and collects every response carrying
+    /// the request tag until `window` elapses. Responses are sorted by SNR,
+    /// strongest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - Device type filter bitmask (e.g., `1 << ContactType::Repeater as u8`)
+    /// * `prefix_only` - Ask nodes for a public key prefix instead of the full key
+    /// * `window` - How long to collect responses
+    pub async fn discover_nodes(
+        &self,
+        filter: u8,
+        prefix_only: bool,
+        window: Duration,
+    ) -> Result<Vec<DiscoverResponse>> {
+        // Subscribe before sending so early responses are not missed
+        let mut subscription = self.dispatcher.subscribe(None);
+        let tag = self
+            .commands
+            .node_discover(filter, prefix_only, None, None)
+            .await?;
+
+        let mut responses = Vec::new();
+        let deadline = tokio::time::Instant::now() + window;
+        while let Ok(Some(event)) = tokio::time::timeout_at(deadline, subscription.recv()).await {
+            if let Event::DiscoverResponse(resp) = event {
+                if resp.tag == tag {
+                    responses.push(*resp);
+                }
+            }
         }
+
+        responses.sort_by(|a, b| b.snr.total_cmp(&a.snr));
+        Ok(responses)
     }
 }
 
+/// Waits for the first event `matcher` accepts, within `timeout_ms` as
+/// reported by the device for a sent request.
+async fn wait_for_response<R>(
+    subscription: &mut Subscription,
+    timeout_ms: u32,
+    mut matcher: impl FnMut(Event) -> Option<R>,
+) -> Result<R> {
+    let wait = async {
+        while let Some(event) = subscription.recv().await {
+            if let Some(result) = matcher(event) {
+                return Ok(result);
+            }
+        }
+        Err(Error::ChannelClosed)
+    };
+
+    tokio::time::timeout(Duration::from_millis(u64::from(timeout_ms)), wait)
+        .await
+        .map_err(|_| Error::Timeout {
+            timeout_ms: u64::from(timeout_ms),
+        })?
+}
+
 /// Processes a received frame and dispatches the appropriate event.
 #[allow(clippy::too_many_lines)]
 async fn process_frame(
@@ -502,10 +1288,9 @@ async fn process_frame(
 
     let event = match PacketType::from_byte(packet_type) {
         Some(PacketType::Ok) => Event::Ok,
-        Some(PacketType::Error) => {
-            let message = String::from_utf8_lossy(data).into_owned();
-            Event::Error { message }
-        }
+        Some(PacketType::Error) => Event::Error {
+            code: ErrorCode::from_payload(data),
+        },
         Some(PacketType::SelfInfo) => {
             match parse_self_info(data) {
                 Ok(info) => {
@@ -719,13 +1504,13 @@ async fn process_frame(
         }
         Some(PacketType::TelemetryResponse) => {
             // TelemetryResponse format: [reserved:1] [pubkey:6] [lpp_data...]
-            // Skip reserved byte and pubkey, parse LPP data
-            if data.len() > 7 {
-                let lpp_data = &data[7..];
-                let telemetry = Telemetry::parse_lpp(lpp_data);
-                Event::TelemetryResponse(Box::new(telemetry))
-            } else {
-                Event::TelemetryResponse(Box::new(Telemetry::new()))
+            let mut pubkey_prefix = [0u8; 6];
+            if let Some(prefix) = data.get(1..7) {
+                pubkey_prefix.copy_from_slice(prefix);
+            }
+            Event::TelemetryResponse {
+                pubkey_prefix,
+                telemetry: Box::new(Telemetry::parse_lpp(data.get(7..).unwrap_or_default())),
             }
         }
         Some(PacketType::Stats) => {
@@ -762,8 +1547,29 @@ async fn process_frame(
                 }
             }
         }
-        Some(PacketType::LoginSuccess) => Event::LoginSuccess,
-        Some(PacketType::LoginFailed) => Event::LoginFailed,
+        Some(PacketType::LoginSuccess) => match parse_login_success(data) {
+            Ok(login) => Event::LoginSuccess(login),
+            Err(e) => {
+                tracing::warn!("failed to parse LoginSuccess: {}", e);
+                Event::Raw {
+                    packet_type,
+                    data: data.to_vec(),
+                }
+            }
+        },
+        Some(PacketType::LoginFailed) => {
+            // LoginFailed format: [reserved:1] [pubkey_prefix:6]
+            if data.len() >= 7 {
+                let mut pubkey_prefix = [0u8; 6];
+                pubkey_prefix.copy_from_slice(&data[1..7]);
+                Event::LoginFailed { pubkey_prefix }
+            } else {
+                Event::Raw {
+                    packet_type,
+                    data: data.to_vec(),
+                }
+            }
+        }
         Some(PacketType::PrivateKey) => {
             // Private key is 64 bytes (seed + public key)
             if data.len() >= 64 {
@@ -809,18 +1615,35 @@ async fn process_frame(
             }
         }
         Some(PacketType::RawData) => Event::RawData(data.to_vec()),
-        Some(PacketType::LogData) => {
-            let log = String::from_utf8_lossy(data).into_owned();
-            Event::LogData(log)
-        }
+        Some(PacketType::LogData) => match parse_rx_log(data) {
+            Ok(entry) => Event::RxLog(Box::new(entry)),
+            Err(_) => Event::LogData(String::from_utf8_lossy(data).into_owned()),
+        },
         Some(PacketType::TraceData) => Event::TraceData(data.to_vec()),
         Some(PacketType::CustomVars) => {
-            let vars = String::from_utf8_lossy(data).into_owned();
-            Event::CustomVars(vars)
+            let vars = String::from_utf8_lossy(data);
+            Event::CustomVars(CustomVars::parse(&vars))
         }
         Some(PacketType::BinaryResponse) => Event::BinaryResponse(data.to_vec()),
         Some(PacketType::PathDiscoveryResponse) => Event::PathDiscoveryResponse(data.to_vec()),
-        Some(PacketType::ControlData) => Event::ControlData(data.to_vec()),
+        Some(PacketType::ControlData) => {
+            // Byte 3 is the control type (after snr, rssi and path_len)
+            let ctrl_type = data.get(3).and_then(|&b| ControlDataType::from_byte(b));
+            if ctrl_type == Some(ControlDataType::NodeDiscoverResp) {
+                match parse_discover_response(data) {
+                    Ok(resp) => Event::DiscoverResponse(Box::new(resp)),
+                    Err(e) => {
+                        tracing::warn!("failed to parse DiscoverResponse: {}", e);
+                        Event::Raw {
+                            packet_type,
+                            data: data.to_vec(),
+                        }
+                    }
+                }
+            } else {
+                Event::ControlData(data.to_vec())
+            }
+        }
         Some(PacketType::SignStart) => {
             // SignStart has 1 reserved byte before the 4-byte max_length
             if data.len() >= 5 {
@@ -845,11 +1668,279 @@ async fn process_frame(
 impl<T> Drop for MeshCore<T> {
     fn drop(&mut self) {
         // Abort background tasks
-        if let Some(task) = self.read_task.take() {
+        for task in [self.health_task.take(), self.clock_task.take()]
+            .into_iter()
+            .flatten()
+        {
             task.abort();
         }
+        self.read_loop.stop();
         if let Some(task) = self.process_task.take() {
             task.abort();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::protocol::CommandOpcode;
+    use crate::testing::{self, Client};
+
+    const REPEATER: u8 = 0x3E;
+
+    /// Connects a fake device in front of a repeater answering command
+    /// `n` with `"<command> #<n>"`.
+    ///
+    /// The reply to command `lost` never arrives and the device does not
+    /// announce the reply to command `held`, so it is only fetched with the
+    /// next one. Returns a receiver for the command timestamps.
+    async fn connect_repeater(
+        lost: Option<u32>,
+        held: Option<u32>,
+    ) -> (Client, mpsc::UnboundedReceiver<u32>) {
+        let (timestamps_tx, timestamps_rx) = mpsc::unbounded_channel();
+        let mut replies = VecDeque::new();
+        let mut sent = 0;
+        let (client, _push) = testing::connect(testing::self_info(0x01, ""), move |request| {
+            match CommandOpcode::from_byte(request[0]) {
+                Some(CommandOpcode::SendMessage) => {
+                    sent += 1;
+                    timestamps_tx
+                        .send(u32::from_le_bytes(request[3..7].try_into().unwrap()))
+                        .unwrap();
+                    let mut responses = vec![testing::msg_sent(sent, 100)];
+                    if lost != Some(sent) {
+                        let command = String::from_utf8_lossy(&request[13..]);
+                        let mut reply = vec![PacketType::ContactMsgRecv as u8];
+                        reply.extend_from_slice(&[REPEATER; 6]);
+                        reply.extend_from_slice(&[0, TextType::Command as u8, 0, 0, 0, 0]);
+                        reply.extend_from_slice(format!("{command} #{sent}").as_bytes());
+                        replies.push_back(reply);
+                        if held != Some(sent) {
+                            responses.push(vec![PacketType::MessagesWaiting as u8]);
+                        }
+                    }
+                    responses
+                }
+                Some(CommandOpcode::GetMessage) => vec![
+                    replies
+                        .pop_front()
+                        .unwrap_or_else(|| vec![PacketType::NoMoreMsgs as u8]),
+                ],
+                _ => vec![vec![PacketType::Ok as u8]],
+            }
+        })
+        .await;
+        (client, timestamps_rx)
+    }
+
+    #[tokio::test]
+    async fn test_remote_command_retry() {
+        let (client, mut timestamps) = connect_repeater(Some(1), None).await;
+        let repeater = PublicKey::from_bytes(&[REPEATER; 32]);
+
+        let reply = client.remote_command(&repeater, "ver").await.unwrap();
+        assert_eq!(reply, "ver #2");
+
+        // The retry is not mistaken for a retransmission
+        let first = timestamps.recv().await.unwrap();
+        let second = timestamps.recv().await.unwrap();
+        assert!(second > first);
+    }
+
+    #[tokio::test]
+    async fn test_remote_command_discards_late_reply() {
+        // Both attempts are answered, the first one late
+        let (client, _timestamps) = connect_repeater(None, Some(1)).await;
+        let repeater = PublicKey::from_bytes(&[REPEATER; 32]);
+
+        let reply = client.remote_command(&repeater, "ver").await.unwrap();
+        assert_eq!(reply, "ver #1");
+
+        // The reply to the retry does not answer the next command
+        let reply = client.remote_command(&repeater, "clock").await.unwrap();
+        assert_eq!(reply, "clock #3");
+    }
+
+    #[tokio::test]
+    async fn test_self_telemetry_ignores_contacts() {
+        let (client, _push) = testing::connect(testing::self_info(0x01, ""), |request| {
+            match CommandOpcode::from_byte(request[0]) {
+                Some(CommandOpcode::Telemetry) => {
+                    // A contact's response arrives before the device's own
+                    let mut remote = vec![PacketType::TelemetryResponse as u8, 0];
+                    remote.extend_from_slice(&[0x5E; 6]);
+                    remote.extend_from_slice(&[2, 104, 80]);
+                    let mut local = vec![PacketType::TelemetryResponse as u8, 0];
+                    local.extend_from_slice(&[0x01; 6]);
+                    local.extend_from_slice(&[1, 103, 0x00, 0xEB]);
+                    vec![remote, local]
+                }
+                _ => vec![vec![PacketType::Ok as u8]],
+            }
+        })
+        .await;
+
+        let telemetry = client.get_self_telemetry().await.unwrap();
+        assert_eq!(telemetry.readings.len(), 1);
+        assert_eq!(telemetry.readings[0].channel, 1);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_contact() {
+        let (client, _push) =
+            testing::connect(
+                testing::self_info(0x01, ""),
+                |request| match CommandOpcode::from_byte(request[0]) {
+                    Some(CommandOpcode::GetContacts) => testing::contacts(&[
+                        testing::contact(0xAA, "Alice", -1),
+                        testing::contact(0xAB, "alfred", -1),
+                        testing::contact(0xB0, "Bob Repeater", 0),
+                    ]),
+                    _ => vec![vec![PacketType::Ok as u8]],
+                },
+            )
+            .await;
+
+        // The contacts are fetched on the first miss
+        let bob = client.resolve_contact("bob").await.unwrap();
+        assert_eq!(bob, PublicKey::from_bytes(&[0xB0; 32]));
+        let alice = client.resolve_contact("Alice").await.unwrap();
+        assert_eq!(alice, PublicKey::from_bytes(&[0xAA; 32]));
+        let key = "cd".repeat(32);
+        assert_eq!(
+            client.resolve_contact(&key).await.unwrap(),
+            PublicKey::from_bytes(&[0xCD; 32])
+        );
+        assert!(matches!(
+            client.resolve_contact("al").await,
+            Err(Error::UnknownContact { name }) if name == "al"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_discover_nodes() {
+        let (client, _push) = testing::connect(testing::self_info(0x01, ""), |request| {
+            match CommandOpcode::from_byte(request[0]) {
+                Some(CommandOpcode::SendControlData) => {
+                    let tag = u32::from_le_bytes(request[3..7].try_into().unwrap());
+                    let key_len = if request[1] & 1 == 1 { 8 } else { 32 };
+                    let response = |snr: i8, tag: u32, key: u8| {
+                        let mut frame = vec![PacketType::ControlData as u8];
+                        frame.extend_from_slice(&[snr.to_le_bytes()[0], 0xB0, 0, 0x92, 0]);
+                        frame.extend_from_slice(&tag.to_le_bytes());
+                        frame.extend_from_slice(&vec![key; key_len]);
+                        frame
+                    };
+                    vec![
+                        vec![PacketType::Ok as u8],
+                        response(8, tag, 0xAA),
+                        // Answer to an earlier request
+                        response(40, tag.wrapping_add(1), 0xCC),
+                        response(20, tag, 0xBB),
+                    ]
+                }
+                _ => vec![vec![PacketType::Ok as u8]],
+            }
+        })
+        .await;
+
+        let window = Duration::from_millis(200);
+        let nodes = client.discover_nodes(0xFF, true, window).await.unwrap();
+        assert_eq!(nodes.len(), 2);
+        // Strongest first
+        assert_eq!(nodes[0].key.as_bytes(), &[0xBB; 8]);
+        assert_eq!(nodes[1].key.as_bytes(), &[0xAA; 8]);
+
+        let nodes = client.discover_nodes(0xFF, false, window).await.unwrap();
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0].key.as_bytes(), &[0xBB; 32]);
+    }
+
+    /// Connects a fake device that signs with a fixed signature and
+    /// records the signing requests.
+    async fn connect_signer(max_length: u32) -> (Client, Arc<std::sync::Mutex<Vec<Vec<u8>>>>) {
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&requests);
+        let (client, _push) = testing::connect(testing::self_info(0x01, ""), move |request| {
+            recorded.lock().unwrap().push(request.to_vec());
+            match CommandOpcode::from_byte(request[0]) {
+                Some(CommandOpcode::SignStart) => {
+                    let mut started = vec![PacketType::SignStart as u8, 0];
+                    started.extend_from_slice(&max_length.to_le_bytes());
+                    vec![started]
+                }
+                Some(CommandOpcode::SignFinish) => {
+                    let mut signature = vec![PacketType::Signature as u8];
+                    signature.extend_from_slice(&[0x5A; 64]);
+                    vec![signature]
+                }
+                _ => vec![vec![PacketType::Ok as u8]],
+            }
+        })
+        .await;
+        (client, requests)
+    }
+
+    #[tokio::test]
+    async fn test_sign_in_chunks() {
+        let (client, requests) = connect_signer(8 * 1024).await;
+        let data: Vec<u8> = (0..2 * SIGN_CHUNK_SIZE + 10)
+            .map(|i| u8::try_from(i % 251).unwrap())
+            .collect();
+
+        let signature = client.sign(&data).await.unwrap();
+        assert_eq!(signature.as_bytes(), &[0x5A; 64]);
+
+        let requests = requests.lock().unwrap();
+        let opcodes: Vec<_> = requests
+            .iter()
+            .map(|r| CommandOpcode::from_byte(r[0]).unwrap())
+            .collect();
+        assert_eq!(
+            opcodes,
+            [
+                CommandOpcode::SignStart,
+                CommandOpcode::SignData,
+                CommandOpcode::SignData,
+                CommandOpcode::SignData,
+                CommandOpcode::SignFinish,
+            ]
+        );
+        // The chunks carry the data in order
+        let chunks: Vec<u8> = requests[1..4]
+            .iter()
+            .flat_map(|r| r[1..].iter().copied())
+            .collect();
+        assert_eq!(chunks, data);
+        assert_eq!(requests[1].len() - 1, SIGN_CHUNK_SIZE);
+    }
+
+    #[tokio::test]
+    async fn test_sign_rejects_data_over_limit() {
+        let (client, requests) = connect_signer(16).await;
+
+        let err = client.sign(&[0; 17]).await.unwrap_err();
+        assert!(err.to_string().contains("signing limit"), "{err}");
+
+        // No data is sent and the signing session is closed
+        let opcodes: Vec<_> = requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|r| CommandOpcode::from_byte(r[0]).unwrap())
+            .collect();
+        assert_eq!(
+            opcodes,
+            [CommandOpcode::SignStart, CommandOpcode::SignFinish]
+        );
+
+        // Data within the limit is signed afterwards
+        assert!(client.sign(&[0; 16]).await.is_ok());
+    }
+}
@@ -0,0 +1,342 @@
+//! Device clock drift monitoring and synchronisation.
+//!
+//! Companion radios lose their RTC on every battery swap, after which
+//! message timestamps and `last_advert` values are meaningless. The clock
+//! functions here compare the device clock against the host clock,
+//! compensating for round-trip latency, and resynchronise when the drift
+//! exceeds a threshold:
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use meshcore::MeshCore;
+//! use meshcore::clock::ClockConfig;
+//!
+//! # async fn example() -> meshcore::Result<()> {
+//! let mut client = MeshCore::serial("/dev/ttyUSB0");
+//! client.connect().await?;
+//!
+//! let sample = client.measure_clock().await?;
+//! println!("device clock is {:+.1}s off", sample.drift);
+//!
+//! client.start_clock_monitor(ClockConfig::default().auto_sync(true));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::commands::CommandHandler;
+use crate::error::{Error, Result};
+use crate::event::{Event, EventDispatcher};
+use crate::transport::Transport;
+
+/// Default interval between clock checks.
+pub const DEFAULT_CLOCK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Default drift above which the clock is considered wrong.
+pub const DEFAULT_DRIFT_THRESHOLD: Duration = Duration::from_secs(5);
+
+/// Default number of round trips per measurement.
+pub const DEFAULT_CLOCK_SAMPLES: u32 = 3;
+
+/// Returns the host time as fractional Unix seconds.
+fn host_now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64())
+}
+
+/// A comparison of the device clock with the host clock.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ClockSample {
+    /// Device time (Unix seconds) as reported by the device.
+    pub device_time: u32,
+    /// Host time (fractional Unix seconds) halfway through the round trip.
+    pub host_time: f64,
+    /// Round-trip time of the `get_time` request.
+    pub round_trip: Duration,
+    /// Estimated device clock offset in seconds (positive if ahead).
+    ///
+    /// The device reports whole seconds, so the estimate assumes the
+    /// device was halfway through the reported second.
+    pub drift: f64,
+}
+
+impl ClockSample {
+    /// Returns true if the absolute drift exceeds `threshold`.
+    #[must_use]
+    pub fn exceeds(&self, threshold: Duration) -> bool {
+        self.drift.abs() > threshold.as_secs_f64()
+    }
+}
+
+/// Measures the device clock once.
+async fn sample<T: Transport>(commands: &CommandHandler<T>) -> Result<ClockSample> {
+    let start = host_now();
+    let event = commands.get_time().await?;
+    let end = host_now();
+
+    let Event::CurrentTime(device_time) = event else {
+        return Err(Error::Protocol {
+            message: "unexpected response".into(),
+        });
+    };
+
+    let host_time = f64::midpoint(start, end);
+    Ok(ClockSample {
+        device_time,
+        host_time,
+        round_trip: Duration::from_secs_f64((end - start).max(0.0)),
+        drift: f64::from(device_time) + 0.5 - host_time,
+    })
+}
+
+/// Measures the device clock, keeping the sample with the lowest
+/// round-trip time out of `samples` (at least one).
+pub(crate) async fn measure<T: Transport>(
+    commands: &CommandHandler<T>,
+    samples: u32,
+) -> Result<ClockSample> {
+    let mut best = sample(commands).await?;
+    for _ in 1..samples {
+        let next = sample(commands).await?;
+        if next.round_trip < best.round_trip {
+            best = next;
+        }
+    }
+    Ok(best)
+}
+
+/// Sets the device clock to the host clock and verifies the result.
+///
+/// The device clock has one-second resolution, so the time is sent just
+/// before a host second boundary, early by the one-way latency.
+pub(crate) async fn sync<T: Transport>(
+    commands: &CommandHandler<T>,
+    samples: u32,
+    threshold: Duration,
+) -> Result<ClockSample> {
+    let latency = measure(commands, samples).await?.round_trip / 2;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let mut target = now.as_secs() + 1;
+    let mut wait = Duration::from_secs(target).saturating_sub(now);
+    if wait < latency {
+        target += 1;
+        wait += Duration::from_secs(1);
+    }
+    tokio::time::sleep(wait.saturating_sub(latency)).await;
+    commands
+        .set_time(u32::try_from(target).unwrap_or(u32::MAX))
+        .await?;
+
+    let verified = measure(commands, samples).await?;
+    if verified.exceeds(threshold) {
+        // Some firmware refuses to move the clock backwards
+        return Err(Error::ClockSync {
+            reason: format!("device clock still {:+.1}s off after sync", verified.drift),
+        });
+    }
+    Ok(verified)
+}
+
+/// Clock monitor configuration.
+#[derive(Debug, Clone)]
+pub struct ClockConfig {
+    /// Interval between clock checks.
+    pub interval: Duration,
+    /// Drift above which [`Event::ClockDrift`] is emitted.
+    pub threshold: Duration,
+    /// Round trips per measurement (the fastest one is used).
+    pub samples: u32,
+    /// Whether to resync when the drift exceeds the threshold.
+    pub auto_sync: bool,
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            interval: DEFAULT_CLOCK_INTERVAL,
+            threshold: DEFAULT_DRIFT_THRESHOLD,
+            samples: DEFAULT_CLOCK_SAMPLES,
+            auto_sync: false,
+        }
+    }
+}
+
+impl ClockConfig {
+    /// Sets the interval between clock checks.
+    #[must_use]
+    pub const fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets the drift threshold.
+    #[must_use]
+    pub const fn threshold(mut self, threshold: Duration) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Sets the number of round trips per measurement.
+    #[must_use]
+    pub const fn samples(mut self, samples: u32) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    /// Sets whether to resync automatically.
+    #[must_use]
+    pub const fn auto_sync(mut self, auto_sync: bool) -> Self {
+        self.auto_sync = auto_sync;
+        self
+    }
+}
+
+/// Background task checking the device clock.
+pub(crate) struct ClockMonitor<T> {
+    config: ClockConfig,
+    commands: CommandHandler<T>,
+    dispatcher: EventDispatcher,
+}
+
+impl<T: Transport> ClockMonitor<T> {
+    pub(crate) const fn new(
+        config: ClockConfig,
+        commands: CommandHandler<T>,
+        dispatcher: EventDispatcher,
+    ) -> Self {
+        Self {
+            config,
+            commands,
+            dispatcher,
+        }
+    }
+
+    async fn check(&self) -> Result<()> {
+        let sample = measure(&self.commands, self.config.samples).await?;
+        tracing::debug!(
+            "device clock drift {:+.2}s (rtt {:?})",
+            sample.drift,
+            sample.round_trip
+        );
+        if !sample.exceeds(self.config.threshold) {
+            return Ok(());
+        }
+
+        tracing::warn!("device clock is {:+.1}s off", sample.drift);
+        self.dispatcher.dispatch(Event::ClockDrift(sample));
+
+        if self.config.auto_sync {
+            let verified = sync(&self.commands, self.config.samples, self.config.threshold).await?;
+            tracing::info!("device clock synced ({:+.2}s)", verified.drift);
+            self.dispatcher.dispatch(Event::ClockSynced(verified));
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn run(self) {
+        let mut interval = tokio::time::interval(self.config.interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.check().await {
+                tracing::warn!("clock check failed: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicI64, Ordering};
+
+    use super::*;
+    use crate::protocol::{CommandOpcode, PacketType};
+    use crate::testing;
+
+    /// Connects a client to a fake device whose clock is `offset` seconds
+    /// off. If `forward_only`, the device refuses to move its clock back.
+    async fn connect(offset: i64, forward_only: bool) -> (testing::Client, Arc<AtomicI64>) {
+        let offset = Arc::new(AtomicI64::new(offset));
+        let device_offset = Arc::clone(&offset);
+
+        let (client, _push) = testing::connect(testing::self_info(0xAB, ""), move |request| {
+            let now = host_now() as i64;
+            let response = match CommandOpcode::from_byte(request[0]) {
+                Some(CommandOpcode::GetTime) => {
+                    let time = u32::try_from(now + device_offset.load(Ordering::SeqCst)).unwrap();
+                    let mut response = vec![PacketType::CurrentTime as u8];
+                    response.extend_from_slice(&time.to_le_bytes());
+                    response
+                }
+                Some(CommandOpcode::SetTime) => {
+                    let time = i64::from(u32::from_le_bytes(request[1..5].try_into().unwrap()));
+                    let current = now + device_offset.load(Ordering::SeqCst);
+                    if forward_only && time < current {
+                        vec![PacketType::Error as u8, 6]
+                    } else {
+                        device_offset.store(time - now, Ordering::SeqCst);
+                        vec![PacketType::Ok as u8]
+                    }
+                }
+                _ => vec![PacketType::Ok as u8],
+            };
+            vec![response]
+        })
+        .await;
+        (client, offset)
+    }
+
+    #[tokio::test]
+    async fn test_measure_and_sync_clock() {
+        let (client, offset) = connect(-3600, true).await;
+
+        let sample = client.measure_clock().await.unwrap();
+        assert!(
+            (sample.drift + 3600.0).abs() <= 1.0,
+            "drift {}",
+            sample.drift
+        );
+        assert!(sample.exceeds(DEFAULT_DRIFT_THRESHOLD));
+
+        let verified = client.sync_clock().await.unwrap();
+        assert!(verified.drift.abs() <= 1.0, "drift {}", verified.drift);
+        assert!(offset.load(Ordering::SeqCst).abs() <= 1);
+    }
+
+    #[tokio::test]
+    async fn test_sync_clock_fails_verification() {
+        // The device is ahead and refuses to go back
+        let (client, _offset) = connect(600, true).await;
+        let err = client.sync_clock().await.unwrap_err();
+        assert!(matches!(err, Error::ClockSync { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_clock_monitor_emits_drift_and_syncs() {
+        let (mut client, _offset) = connect(-120, false).await;
+        let mut events = client.subscribe();
+        client.start_clock_monitor(ClockConfig::default().samples(1).auto_sync(true));
+
+        let (drift, synced) = tokio::time::timeout(Duration::from_secs(3), async {
+            let mut drift = None;
+            loop {
+                match events.recv().await {
+                    Some(Event::ClockDrift(sample)) => drift = Some(sample),
+                    Some(Event::ClockSynced(sample)) => return (drift.unwrap(), sample),
+                    _ => {}
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert!(drift.drift < -100.0);
+        assert!(synced.drift.abs() <= 1.0);
+    }
+}
@@ -12,9 +12,11 @@ use tokio::sync::Mutex;
 
 use crate::error::{Error, Result};
 use crate::event::{Event, EventDispatcher, EventFilter};
-use crate::protocol::{BinaryReqType, CommandOpcode, ControlDataType, PacketType, StatsType};
+use crate::protocol::{
+    BinaryReqType, CommandOpcode, ControlDataType, MAX_DEVICE_FRAME_SIZE, PacketType, StatsType,
+};
 use crate::transport::Transport;
-use crate::types::PublicKey;
+use crate::types::{CustomVars, PublicKey};
 
 /// Coordinate scaling factor (multiply by 1e6 for storage).
 const COORD_SCALE: f64 = 1_000_000.0;
@@ -42,6 +44,29 @@ pub struct ContactUpdateParams<'a> {
     pub longitude: Option<f64>,
 }
 
+/// Maximum data chunk size for `sign_data` (device frame limit minus opcode).
+pub const SIGN_CHUNK_SIZE: usize = MAX_DEVICE_FRAME_SIZE - 1;
+
+/// `AppStart` command payload.
+///
+/// Format: 0x01 0x03 <6 spaces> "mccli"
+/// The 0x03 byte and spaces are part of the protocol handshake.
+pub(crate) const APP_START: [u8; 13] = [
+    CommandOpcode::AppStart as u8,
+    0x03,
+    b' ',
+    b' ',
+    b' ',
+    b' ',
+    b' ',
+    b' ',
+    b'm',
+    b'c',
+    b'c',
+    b'l',
+    b'i',
+];
+
 /// Default command timeout.
 pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
 
@@ -70,12 +95,20 @@ impl<T: Transport> CommandHandler<T> {
         self.timeout = timeout;
     }
 
+    /// Returns the command timeout.
+    #[must_use]
+    pub const fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
     /// Gets the next binary request tag.
-    fn next_tag(&self) -> u32 {
+    pub(crate) fn next_tag(&self) -> u32 {
         self.binary_tag.fetch_add(1, Ordering::SeqCst)
     }
 
     /// Sends a raw command and waits for specific response types.
+    ///
+    /// An `Error` response is returned as [`Error::Device`].
     async fn send_and_wait(&self, data: Bytes, expected: &[PacketType]) -> Result<Event> {
         // IMPORTANT: Subscribe BEFORE sending to avoid race conditions.
         // With broadcast channels, events are only delivered to subscribers
@@ -85,6 +118,8 @@ impl<T: Transport> CommandHandler<T> {
         let filter = EventFilter::packet_types(expected.to_vec());
         let mut subscription = self.dispatcher.subscribe(None);
 
+        let command = data.first().and_then(|&b| CommandOpcode::from_byte(b));
+
         // Send the command
         {
             let mut transport = self.transport.lock().await;
@@ -93,7 +128,7 @@ impl<T: Transport> CommandHandler<T> {
 
         // Wait for matching response with timeout
         let timeout = self.timeout;
-        tokio::select! {
+        let event = tokio::select! {
             biased;
             result = async {
                 loop {
@@ -111,7 +146,13 @@ impl<T: Transport> CommandHandler<T> {
             () = tokio::time::sleep(timeout) => Err(Error::Timeout {
                 timeout_ms: u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX),
             }),
+        }?;
+
+        // Firmware error responses become typed errors
+        if let Event::Error { code } = event {
+            return Err(Error::Device { code, command });
         }
+        Ok(event)
     }
 
     /// Sends a command and expects OK/Error response.
@@ -121,7 +162,6 @@ impl<T: Transport> CommandHandler<T> {
             .await?;
         match event {
             Event::Ok => Ok(()),
-            Event::Error { message } => Err(Error::Protocol { message }),
             _ => Err(Error::Protocol {
                 message: "unexpected response".into(),
             }),
@@ -149,23 +189,7 @@ impl<T: Transport> CommandHandler<T> {
     /// Sends the "mccli" marker to identify as a `MeshCore` CLI client.
     /// Returns `SelfInfo` with device configuration.
     pub async fn app_start(&self) -> Result<Event> {
-        // Format: 0x01 0x03 <6 spaces> "mccli"
-        // The 0x03 byte and spaces are part of the protocol handshake
-        let data = Bytes::from_static(&[
-            CommandOpcode::AppStart as u8,
-            0x03,
-            b' ',
-            b' ',
-            b' ',
-            b' ',
-            b' ',
-            b' ',
-            b'm',
-            b'c',
-            b'c',
-            b'l',
-            b'i',
-        ]);
+        let data = Bytes::from_static(&APP_START);
         self.send_and_wait(data, &[PacketType::SelfInfo, PacketType::Error])
             .await
     }
@@ -409,7 +433,12 @@ impl<T: Transport> CommandHandler<T> {
     /// Sets a custom variable.
     ///
     /// Note: Fire-and-forget command. Use `get_custom_vars` to verify.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key or value contains `:` or `,`.
     pub async fn set_custom_var(&self, key: &str, value: &str) -> Result<()> {
+        CustomVars::validate(key, value)?;
         let kv = format!("{key}:{value}");
         let mut buf = BytesMut::with_capacity(1 + kv.len());
         buf.put_u8(CommandOpcode::SetCustomVar as u8);
@@ -444,7 +473,6 @@ impl<T: Transport> CommandHandler<T> {
 
         match event {
             Event::ContactListEnd { .. } => Ok(()),
-            Event::Error { message } => Err(Error::Protocol { message }),
             _ => Err(Error::Protocol {
                 message: "unexpected response to GetContacts".into(),
             }),
@@ -868,17 +896,22 @@ impl<T: Transport> CommandHandler<T> {
     /// * `prefix_only` - Return only pubkey prefixes (default true)
     /// * `tag` - Optional request tag for matching responses
     /// * `since` - Optional timestamp to get nodes updated since
+    ///
+    /// Returns the request tag. Responses arrive as `DiscoverResponse`
+    /// push notifications carrying the same tag.
     pub async fn node_discover(
         &self,
         filter: u8,
         prefix_only: bool,
         tag: Option<u32>,
         since: Option<u32>,
-    ) -> Result<()> {
+    ) -> Result<u32> {
+        let tag = tag.unwrap_or_else(|| self.next_tag());
+
         // Build payload: [filter:1] [tag:4LE] [since:4LE if provided]
         let mut payload = BytesMut::with_capacity(9);
         payload.put_u8(filter);
-        payload.put_u32_le(tag.unwrap_or_else(|| self.next_tag()));
+        payload.put_u32_le(tag);
         if let Some(ts) = since {
             payload.put_u32_le(ts);
         }
@@ -891,7 +924,8 @@ impl<T: Transport> CommandHandler<T> {
         buf.put_u8(control_type);
         buf.put_slice(&payload);
 
-        self.send_expect_ok(buf.freeze()).await
+        self.send_expect_ok(buf.freeze()).await?;
+        Ok(tag)
     }
 
     // ==================== Signature Commands ====================
@@ -904,6 +938,8 @@ impl<T: Transport> CommandHandler<T> {
     }
 
     /// Sends data chunk for signing.
+    ///
+    /// Chunks must not exceed [`SIGN_CHUNK_SIZE`] bytes.
     pub async fn sign_data(&self, chunk: &[u8]) -> Result<()> {
         let mut buf = BytesMut::with_capacity(1 + chunk.len());
         buf.put_u8(CommandOpcode::SignData as u8);
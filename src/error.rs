@@ -2,6 +2,8 @@
 
 use thiserror::Error;
 
+use crate::protocol::{CommandOpcode, ErrorCode};
+
 /// The main error type for meshcore operations.
 #[derive(Debug, Error)]
 pub enum Error {
@@ -21,6 +23,16 @@ pub enum Error {
     #[error("protocol error: {message}")]
     Protocol { message: String },
 
+    /// Error code reported by the device firmware.
+    #[error(
+        "device error: {code}{}",
+        .command.map_or_else(String::new, |c| format!(" (command {c:?})"))
+    )]
+    Device {
+        code: ErrorCode,
+        command: Option<CommandOpcode>,
+    },
+
     /// Command timed out waiting for response.
     #[error("command timed out after {timeout_ms}ms")]
     Timeout { timeout_ms: u64 },
@@ -29,14 +41,58 @@ pub enum Error {
     #[error("not connected")]
     NotConnected,
 
+    /// No managed device knows a route to the destination.
+    #[error("no route to {destination}")]
+    NoRoute { destination: String },
+
+    /// No cached contact matches a name, or several do.
+    #[error("unknown or ambiguous contact: {name}")]
+    UnknownContact { name: String },
+
+    /// A room server or repeater rejected the login.
+    #[error("login to {destination} failed")]
+    LoginFailed { destination: String },
+
+    /// A remote CLI command was rejected.
+    #[error("remote command {command:?} failed: {reply}")]
+    RemoteCommand { command: String, reply: String },
+
+    /// No `MeshCore` device answered on any serial port.
+    #[error("no MeshCore device found")]
+    NoDeviceFound,
+
     /// Invalid public key format.
     #[error("invalid public key: {reason}")]
     InvalidPublicKey { reason: String },
 
+    /// Invalid or mismatching signature.
+    #[error("invalid signature: {reason}")]
+    InvalidSignature { reason: String },
+
+    /// Invalid contact card or contact URI.
+    #[error("invalid contact card: {reason}")]
+    InvalidContactCard { reason: String },
+
+    /// Invalid custom variable key or value.
+    #[error("invalid custom variable: {reason}")]
+    InvalidCustomVar { reason: String },
+
+    /// Device clock could not be synchronised.
+    #[error("clock sync failed: {reason}")]
+    ClockSync { reason: String },
+
+    /// Malformed frame capture file.
+    #[error("invalid capture: {reason}")]
+    InvalidCapture { reason: String },
+
     /// Invalid coordinates.
     #[error("invalid coordinates: {reason}")]
     InvalidCoordinates { reason: String },
 
+    /// MQTT broker connection or request failed.
+    #[error("MQTT error: {reason}")]
+    Mqtt { reason: String },
+
     /// Channel send error.
     #[error("channel send error")]
     ChannelSend,
@@ -46,6 +102,18 @@ pub enum Error {
     ChannelClosed,
 }
 
+impl Error {
+    /// Returns the firmware error code if this is a device error.
+    #[must_use]
+    pub const fn device_code(&self) -> Option<ErrorCode> {
+        if let Self::Device { code, .. } = self {
+            Some(*code)
+        } else {
+            None
+        }
+    }
+}
+
 /// Frame-specific errors.
 #[derive(Debug, Error)]
 pub enum FrameError {
@@ -7,14 +7,18 @@ use std::sync::Arc;
 
 use tokio::sync::{broadcast, mpsc};
 
-use crate::protocol::PacketType;
+use crate::clock::ClockSample;
+use crate::protocol::{ErrorCode, PacketType};
 use crate::types::{
     Acknowledgment, BatteryStatus, Channel, ChannelMessage, Contact, ContactMessage, CoreStats,
-    DeviceInfo, DeviceStatus, PacketStats, PublicKey, RadioStats, SelfInfo, Telemetry,
+    CustomVars, DeviceInfo, DeviceStatus, DiscoverResponse, LoginResponse, PacketStats, PublicKey,
+    RadioStats, RxLogEntry, SelfInfo, Telemetry,
 };
 
 /// Statistics data variants.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
 pub enum StatsData {
     /// Core statistics.
     Core(CoreStats),
@@ -26,15 +30,26 @@ pub enum StatsData {
 
 /// Event types that can be dispatched.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "type", content = "data", rename_all = "snake_case")
+)]
 pub enum Event {
     /// Connection established.
     Connected,
     /// Connection lost.
     Disconnected,
+    /// The device stopped answering health probes.
+    ConnectionStalled { failures: u32 },
+    /// The device clock drifted beyond the configured threshold.
+    ClockDrift(ClockSample),
+    /// The device clock was resynchronised (sample taken afterwards).
+    ClockSynced(ClockSample),
     /// Command completed successfully.
     Ok,
-    /// Command failed with error.
-    Error { message: String },
+    /// Command failed with a firmware error code.
+    Error { code: ErrorCode },
     /// Self info received.
     SelfInfo(Box<SelfInfo>),
     /// Device info received.
@@ -71,40 +86,106 @@ pub enum Event {
     Stats(StatsData),
     /// Channel information received.
     ChannelInfo(Box<Channel>),
-    /// Telemetry response received.
-    TelemetryResponse(Box<Telemetry>),
-    /// Login was successful.
-    LoginSuccess,
-    /// Login failed.
-    LoginFailed,
+    /// Telemetry response received, from the device itself or a contact.
+    TelemetryResponse {
+        #[cfg_attr(
+            feature = "serde",
+            serde(serialize_with = "crate::types::serde_hex::serialize")
+        )]
+        pubkey_prefix: [u8; 6],
+        telemetry: Box<Telemetry>,
+    },
+    /// Login to a room server or repeater was successful.
+    LoginSuccess(LoginResponse),
+    /// Login to a room server or repeater failed.
+    LoginFailed {
+        #[cfg_attr(
+            feature = "serde",
+            serde(serialize_with = "crate::types::serde_hex::serialize")
+        )]
+        pubkey_prefix: [u8; 6],
+    },
     /// Private key received (64 bytes: seed + public key).
-    PrivateKey([u8; 64]),
+    PrivateKey(
+        #[cfg_attr(
+            feature = "serde",
+            serde(serialize_with = "crate::types::serde_hex::serialize")
+        )]
+        [u8; 64],
+    ),
     /// Device is disabled.
     Disabled,
     /// Signature received (variable length).
-    Signature(Vec<u8>),
+    Signature(
+        #[cfg_attr(
+            feature = "serde",
+            serde(serialize_with = "crate::types::serde_hex::serialize")
+        )]
+        Vec<u8>,
+    ),
     /// Contact URI received.
     ContactUri(String),
     /// Path update notification (contains public key of updated contact).
     PathUpdate(PublicKey),
     /// Raw binary data received.
-    RawData(Vec<u8>),
-    /// Log data received.
+    RawData(
+        #[cfg_attr(
+            feature = "serde",
+            serde(serialize_with = "crate::types::serde_hex::serialize")
+        )]
+        Vec<u8>,
+    ),
+    /// Log data received that could not be decoded as an RX log entry.
     LogData(String),
+    /// Packet received over the air (decoded log data).
+    RxLog(Box<RxLogEntry>),
     /// Trace data received.
-    TraceData(Vec<u8>),
-    /// Custom variables received (comma-separated key:value pairs).
-    CustomVars(String),
+    TraceData(
+        #[cfg_attr(
+            feature = "serde",
+            serde(serialize_with = "crate::types::serde_hex::serialize")
+        )]
+        Vec<u8>,
+    ),
+    /// Custom variables received.
+    CustomVars(CustomVars),
     /// Binary response received.
-    BinaryResponse(Vec<u8>),
+    BinaryResponse(
+        #[cfg_attr(
+            feature = "serde",
+            serde(serialize_with = "crate::types::serde_hex::serialize")
+        )]
+        Vec<u8>,
+    ),
     /// Path discovery response received.
-    PathDiscoveryResponse(Vec<u8>),
+    PathDiscoveryResponse(
+        #[cfg_attr(
+            feature = "serde",
+            serde(serialize_with = "crate::types::serde_hex::serialize")
+        )]
+        Vec<u8>,
+    ),
     /// Control data received.
-    ControlData(Vec<u8>),
+    ControlData(
+        #[cfg_attr(
+            feature = "serde",
+            serde(serialize_with = "crate::types::serde_hex::serialize")
+        )]
+        Vec<u8>,
+    ),
+    /// Node discovery response received (decoded control data).
+    DiscoverResponse(Box<DiscoverResponse>),
     /// Sign operation started, returns max data length.
     SignStarted { max_length: u32 },
     /// Raw/unknown packet received.
-    Raw { packet_type: u8, data: Vec<u8> },
+    Raw {
+        packet_type: u8,
+        #[cfg_attr(
+            feature = "serde",
+            serde(serialize_with = "crate::types::serde_hex::serialize")
+        )]
+        data: Vec<u8>,
+    },
 }
 
 impl Event {
@@ -132,23 +213,28 @@ impl Event {
             Self::CurrentTime(_) => Some(PacketType::CurrentTime),
             Self::Stats(_) => Some(PacketType::Stats),
             Self::ChannelInfo(_) => Some(PacketType::ChannelInfo),
-            Self::TelemetryResponse(_) => Some(PacketType::TelemetryResponse),
-            Self::LoginSuccess => Some(PacketType::LoginSuccess),
-            Self::LoginFailed => Some(PacketType::LoginFailed),
+            Self::TelemetryResponse { .. } => Some(PacketType::TelemetryResponse),
+            Self::LoginSuccess(_) => Some(PacketType::LoginSuccess),
+            Self::LoginFailed { .. } => Some(PacketType::LoginFailed),
             Self::PrivateKey(_) => Some(PacketType::PrivateKey),
             Self::Disabled => Some(PacketType::Disabled),
             Self::Signature(_) => Some(PacketType::Signature),
             Self::ContactUri(_) => Some(PacketType::ContactUri),
             Self::PathUpdate(_) => Some(PacketType::PathUpdate),
             Self::RawData(_) => Some(PacketType::RawData),
-            Self::LogData(_) => Some(PacketType::LogData),
+            Self::LogData(_) | Self::RxLog(_) => Some(PacketType::LogData),
             Self::TraceData(_) => Some(PacketType::TraceData),
             Self::CustomVars(_) => Some(PacketType::CustomVars),
             Self::BinaryResponse(_) => Some(PacketType::BinaryResponse),
             Self::PathDiscoveryResponse(_) => Some(PacketType::PathDiscoveryResponse),
-            Self::ControlData(_) => Some(PacketType::ControlData),
+            Self::ControlData(_) | Self::DiscoverResponse(_) => Some(PacketType::ControlData),
             Self::SignStarted { .. } => Some(PacketType::SignStart),
-            Self::Connected | Self::Disconnected | Self::Raw { .. } => None,
+            Self::Connected
+            | Self::Disconnected
+            | Self::ConnectionStalled { .. }
+            | Self::ClockDrift(_)
+            | Self::ClockSynced(_)
+            | Self::Raw { .. } => None,
         }
     }
 }
@@ -334,7 +420,7 @@ mod tests {
 
         assert!(filter.matches(&Event::Ok));
         assert!(filter.matches(&Event::Error {
-            message: "test".into()
+            code: ErrorCode::NotFound
         }));
         assert!(!filter.matches(&Event::Connected));
     }
@@ -347,4 +433,32 @@ mod tests {
         assert!(!filter.matches(&Event::Ack(Acknowledgment { code: 99999 })));
         assert!(!filter.matches(&Event::Ok));
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_event_serialization() {
+        use crate::types::{TextType, message::ContactMessage};
+
+        let event = Event::ContactMessage(Box::new(ContactMessage {
+            sender_prefix: [0xAB, 0xCD, 1, 2, 3, 4],
+            path_len: -1,
+            text_type: TextType::Plain,
+            timestamp: 1_700_000_000,
+            signature: None,
+            text: "hello".into(),
+            signal: None,
+        }));
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "contact_message");
+        assert_eq!(json["data"]["sender_prefix"], "abcd01020304");
+        assert_eq!(json["data"]["text_type"], "plain");
+
+        let json =
+            serde_json::to_value(Event::PathUpdate(PublicKey::from_bytes(&[1; 32]))).unwrap();
+        assert_eq!(json["data"], "01".repeat(32));
+        assert_eq!(
+            serde_json::to_value(Event::Connected).unwrap()["type"],
+            "connected"
+        );
+    }
 }
@@ -0,0 +1,460 @@
+//! HTTP/JSON gateway to a radio.
+//!
+//! The [`Gateway`] exposes a connected [`MeshCore`] client over HTTP so
+//! that dashboards and home automation can use the radio without linking
+//! Rust:
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use meshcore::MeshCore;
+//! use meshcore::gateway::Gateway;
+//! use tokio::net::TcpListener;
+//!
+//! # async fn example() -> meshcore::Result<()> {
+//! let mut client = MeshCore::serial("/dev/ttyUSB0");
+//! client.connect().await?;
+//! let gateway = Gateway::new(Arc::new(client));
+//! gateway.serve(TcpListener::bind("127.0.0.1:8080").await?).await
+//! # }
+//! ```
+//!
+//! # Endpoints
+//!
+//! | Method | Path | Description |
+//! |--------|------|-------------|
+//! | `GET` | `/self` | Self info |
+//! | `GET` | `/contacts` | Contacts, sorted by name |
+//! | `GET` | `/channels` | Configured channels, without their secrets |
+//! | `GET` | `/stats` | Core, radio and packet statistics |
+//! | `GET` | `/battery` | Battery status |
+//! | `POST` | `/messages` | Private message, `{"to": "<name or key>", "text": "..."}` |
+//! | `POST` | `/channels/{index}/messages` | Channel message, `{"text": "..."}` |
+//! | `GET` | `/events` | Server-sent events, one [`Event`] per message |
+//!
+//! Responses are the JSON serialisation of the library types (see the
+//! `serde` feature). `POST /messages` returns the expected ACK code,
+//! which is matched by a later `ack` event. Errors are returned as
+//! `{"error": "..."}` with a status code reflecting the cause.
+//!
+//! The gateway does no authentication, so it never hands out key
+//! material: channel secrets are left out of `/channels`, and
+//! `channel_info` and `private_key` events are not forwarded on
+//! `/events`.
+//!
+//! [`Gateway::serve`] fetches waiting messages as the device announces
+//! them; when only [`Gateway::router`] is used, the application has to
+//! call [`MeshCore::fetch_messages`] itself.
+
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::sse::{Event as SseEvent, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures::Stream;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tokio::net::TcpListener;
+
+use crate::client::{MeshCore, current_timestamp};
+use crate::error::{Error, Result};
+use crate::event::Event;
+use crate::protocol::ErrorCode;
+use crate::transport::Transport;
+use crate::types::{BatteryStatus, Contact, SelfInfo};
+
+/// Shared client handed to the request handlers.
+type Client<T> = Arc<MeshCore<T>>;
+
+/// Result of a request handler.
+type ApiResult<T> = std::result::Result<T, ApiError>;
+
+/// Error response with a status code and a `{"error": ...}` body.
+#[derive(Debug)]
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<Error> for ApiError {
+    fn from(error: Error) -> Self {
+        let status = match &error {
+            Error::NotConnected | Error::ChannelClosed | Error::ChannelSend => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            Error::Timeout { .. } => StatusCode::GATEWAY_TIMEOUT,
+            Error::Device {
+                code: ErrorCode::NotFound,
+                ..
+            }
+            | Error::UnknownContact { .. } => StatusCode::NOT_FOUND,
+            Error::InvalidPublicKey { .. } | Error::NoRoute { .. } => StatusCode::BAD_REQUEST,
+            _ => StatusCode::BAD_GATEWAY,
+        };
+        Self::new(status, error.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(json!({ "error": self.message }))).into_response()
+    }
+}
+
+/// Body of `POST /messages`.
+#[derive(Deserialize)]
+struct MessageRequest {
+    /// Contact name, unique name prefix or hex public key.
+    to: String,
+    text: String,
+}
+
+/// Body of `POST /channels/{index}/messages`.
+#[derive(Deserialize)]
+struct ChannelMessageRequest {
+    text: String,
+}
+
+/// HTTP/JSON gateway serving one client.
+pub struct Gateway<T> {
+    client: Client<T>,
+}
+
+impl<T: Transport + 'static> Gateway<T> {
+    /// Creates a gateway for a connected client.
+    #[must_use]
+    pub const fn new(client: Arc<MeshCore<T>>) -> Self {
+        Self { client }
+    }
+
+    /// Returns the routes of the gateway, e.g. to nest them into a larger
+    /// application.
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/self", get(self_info::<T>))
+            .route("/contacts", get(contacts::<T>))
+            .route("/channels", get(channels::<T>))
+            .route("/stats", get(stats::<T>))
+            .route("/battery", get(battery::<T>))
+            .route("/messages", post(send_message::<T>))
+            .route(
+                "/channels/{index}/messages",
+                post(send_channel_message::<T>),
+            )
+            .route("/events", get(events::<T>))
+            .with_state(Arc::clone(&self.client))
+    }
+
+    /// Serves HTTP requests until the listener fails.
+    ///
+    /// While serving, messages waiting on the device are fetched as soon
+    /// as they are announced, so that they appear on `/events`.
+    pub async fn serve(&self, listener: TcpListener) -> Result<()> {
+        let client = Arc::clone(&self.client);
+        let mut subscription = client.subscribe();
+        let fetcher = tokio::spawn(async move {
+            loop {
+                if let Err(e) = client.fetch_messages().await {
+                    tracing::warn!("gateway: failed to fetch messages: {}", e);
+                }
+                loop {
+                    match subscription.recv().await {
+                        Some(Event::MessagesWaiting) => break,
+                        Some(_) => {}
+                        None => return,
+                    }
+                }
+            }
+        });
+
+        let result = axum::serve(listener, self.router()).await;
+        fetcher.abort();
+        result.map_err(Into::into)
+    }
+}
+
+async fn self_info<T: Transport + 'static>(
+    State(client): State<Client<T>>,
+) -> ApiResult<Json<SelfInfo>> {
+    client
+        .self_info()
+        .await
+        .map(Json)
+        .ok_or_else(|| Error::NotConnected.into())
+}
+
+async fn contacts<T: Transport + 'static>(
+    State(client): State<Client<T>>,
+) -> ApiResult<Json<Vec<Contact>>> {
+    let mut contacts: Vec<_> = client.get_contacts().await?.into_values().collect();
+    contacts.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(Json(contacts))
+}
+
+async fn channels<T: Transport + 'static>(
+    State(client): State<Client<T>>,
+) -> ApiResult<Json<Vec<Value>>> {
+    let channels = client.get_channels().await?;
+    Ok(Json(
+        channels
+            .iter()
+            .map(|channel| json!({ "index": channel.index, "name": channel.name }))
+            .collect(),
+    ))
+}
+
+async fn stats<T: Transport + 'static>(State(client): State<Client<T>>) -> ApiResult<Json<Value>> {
+    let core = client.get_core_stats().await?;
+    let radio = client.get_radio_stats().await?;
+    let packets = client.get_packet_stats().await?;
+    Ok(Json(
+        json!({ "core": core, "radio": radio, "packets": packets }),
+    ))
+}
+
+async fn battery<T: Transport + 'static>(
+    State(client): State<Client<T>>,
+) -> ApiResult<Json<BatteryStatus>> {
+    Ok(Json(client.get_battery().await?))
+}
+
+async fn send_message<T: Transport + 'static>(
+    State(client): State<Client<T>>,
+    Json(request): Json<MessageRequest>,
+) -> ApiResult<Json<Value>> {
+    let key = client.resolve_contact(&request.to).await?;
+    match client
+        .commands()
+        .send_message(&key, &request.text, 0, current_timestamp())
+        .await?
+    {
+        Event::MessageSent {
+            expected_ack,
+            timeout_ms,
+        } => Ok(Json(json!({
+            "destination": key,
+            "expected_ack": expected_ack,
+            "timeout_ms": timeout_ms,
+        }))),
+        _ => Err(Error::Protocol {
+            message: "unexpected response".into(),
+        }
+        .into()),
+    }
+}
+
+async fn send_channel_message<T: Transport + 'static>(
+    State(client): State<Client<T>>,
+    Path(index): Path<u8>,
+    Json(request): Json<ChannelMessageRequest>,
+) -> ApiResult<StatusCode> {
+    client.send_channel_message(index, &request.text).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn events<T: Transport + 'static>(
+    State(client): State<Client<T>>,
+) -> Sse<impl Stream<Item = std::result::Result<SseEvent, Infallible>>> {
+    let stream = futures::stream::unfold(client.subscribe(), |mut subscription| async move {
+        let event = loop {
+            match subscription.recv().await? {
+                // Key material stays on the host. Channel info carries the
+                // secret and is sent for every channel read, e.g. by
+                // `GET /channels`.
+                Event::PrivateKey(_) | Event::ChannelInfo(_) => {}
+                event => break event,
+            }
+        };
+        let value = serde_json::to_value(&event).unwrap_or(Value::Null);
+        let mut sse = SseEvent::default().data(value.to_string());
+        // The event type doubles as the SSE event name for `addEventListener`
+        if let Some(name) = value.get("type").and_then(Value::as_str) {
+            sse = sse.event(name);
+        }
+        Some((Ok(sse), subscription))
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::body::{Body, to_bytes};
+    use axum::http::Request;
+    use bytes::Bytes;
+    use futures::StreamExt;
+    use tokio::sync::mpsc;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::protocol::{CommandOpcode, PacketType};
+    use crate::testing::{self, Client as TestClient};
+
+    /// Connects a fake device with one contact ("alice") and one channel
+    /// ("Public"), and returns a sender for push frames.
+    async fn connect() -> (TestClient, mpsc::UnboundedSender<Bytes>) {
+        testing::connect(testing::self_info(0xA0, "gateway"), |request| {
+            match CommandOpcode::from_byte(request[0]) {
+                Some(CommandOpcode::GetContacts) => {
+                    testing::contacts(&[testing::contact(0xCC, "alice", -1)])
+                }
+                Some(CommandOpcode::GetBattery) => {
+                    vec![vec![PacketType::Battery as u8, 0xD4, 0x0D]]
+                }
+                Some(CommandOpcode::DeviceQuery) => {
+                    // Firmware v3 with 2 channels
+                    let mut info = vec![PacketType::DeviceInfo as u8, 3, 0, 2];
+                    info.extend_from_slice(&[0; 76]);
+                    vec![info]
+                }
+                Some(CommandOpcode::GetChannel) if request[1] == 0 => {
+                    let mut channel = vec![PacketType::ChannelInfo as u8, 0];
+                    let mut name = [0u8; 32];
+                    name[..6].copy_from_slice(b"Public");
+                    channel.extend_from_slice(&name);
+                    channel.extend_from_slice(&[0; 16]);
+                    vec![channel]
+                }
+                Some(CommandOpcode::GetChannel) => vec![vec![PacketType::Error as u8, 2]],
+                Some(CommandOpcode::SendMessage) => vec![testing::msg_sent(1234, 5000)],
+                _ => vec![vec![PacketType::Ok as u8]],
+            }
+        })
+        .await
+    }
+
+    async fn request(router: Router, request: Request<Body>) -> (StatusCode, Value) {
+        let response = router.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let value = if body.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(&body).unwrap()
+        };
+        (status, value)
+    }
+
+    fn get(path: &str) -> Request<Body> {
+        Request::get(path).body(Body::empty()).unwrap()
+    }
+
+    fn post(path: &str, body: &Value) -> Request<Body> {
+        Request::post(path)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_gateway_endpoints() {
+        let (client, _push) = connect().await;
+        let router = Gateway::new(Arc::new(client)).router();
+
+        let (status, info) = request(router.clone(), get("/self")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(info["name"], "gateway");
+
+        let (_, contacts) = request(router.clone(), get("/contacts")).await;
+        assert_eq!(contacts[0]["name"], "alice");
+
+        let (_, channels) = request(router.clone(), get("/channels")).await;
+        assert_eq!(channels.as_array().unwrap().len(), 1);
+        assert_eq!(channels[0]["name"], "Public");
+        assert!(channels[0].get("secret").is_none());
+
+        let (_, battery) = request(router.clone(), get("/battery")).await;
+        assert_eq!(battery["millivolts"], 3540);
+
+        let body = json!({ "to": "ali", "text": "hello" });
+        let (status, sent) = request(router.clone(), post("/messages", &body)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(sent["expected_ack"], 1234);
+        assert_eq!(sent["destination"], "cc".repeat(32));
+
+        let body = json!({ "to": "bob", "text": "hello" });
+        let (status, error) = request(router.clone(), post("/messages", &body)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(error["error"].as_str().unwrap().contains("bob"));
+
+        let body = json!({ "text": "hello all" });
+        let (status, _) = request(router, post("/channels/0/messages", &body)).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn test_gateway_event_stream() {
+        let (client, push) = connect().await;
+        // The stream outlives the router, so keep the client connected
+        let client = Arc::new(client);
+        let router = Gateway::new(Arc::clone(&client)).router();
+
+        let response = router.oneshot(get("/events")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let mut body = response.into_body().into_data_stream();
+
+        // Private key push, which is not forwarded
+        let mut key = vec![PacketType::PrivateKey as u8];
+        key.extend_from_slice(&[0x55; 64]);
+        push.send(Bytes::from(key)).unwrap();
+
+        // Ack push with code 1234
+        let mut ack = vec![PacketType::Ack as u8];
+        ack.extend_from_slice(&1234u32.to_le_bytes());
+        push.send(Bytes::from(ack)).unwrap();
+
+        let chunk = tokio::time::timeout(Duration::from_secs(1), body.next())
+            .await
+            .expect("timed out")
+            .unwrap()
+            .unwrap();
+        let text = String::from_utf8(chunk.to_vec()).unwrap();
+        assert!(text.lines().any(|l| l == "event: ack"), "{text}");
+        let data = text.lines().find_map(|l| l.strip_prefix("data: ")).unwrap();
+        let value: Value = serde_json::from_str(data).unwrap();
+        assert_eq!(value["data"]["code"], 1234);
+    }
+
+    #[tokio::test]
+    async fn test_gateway_event_stream_hides_secrets() {
+        let (client, push) = connect().await;
+        let client = Arc::new(client);
+        let router = Gateway::new(Arc::clone(&client)).router();
+
+        let response = router.clone().oneshot(get("/events")).await.unwrap();
+        let mut body = response.into_body().into_data_stream();
+
+        // Reading the channels broadcasts their channel info
+        let (status, _) = request(router, get("/channels")).await;
+        assert_eq!(status, StatusCode::OK);
+        let mut ack = vec![PacketType::Ack as u8];
+        ack.extend_from_slice(&1234u32.to_le_bytes());
+        push.send(Bytes::from(ack)).unwrap();
+
+        let mut text = String::new();
+        while !text.contains("event: ack") {
+            let chunk = tokio::time::timeout(Duration::from_secs(1), body.next())
+                .await
+                .expect("timed out")
+                .unwrap()
+                .unwrap();
+            text.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+        assert!(!text.contains("channel_info"), "{text}");
+        assert!(!text.contains("secret"), "{text}");
+    }
+}
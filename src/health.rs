@@ -0,0 +1,236 @@
+//! Connection health monitoring.
+//!
+//! A firmware hang looks the same as an idle mesh: the port stays open and
+//! the read loop stays alive. The health monitor periodically issues a
+//! cheap request and emits [`Event::ConnectionStalled`] when the device
+//! stops answering, optionally reconnecting:
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use meshcore::MeshCore;
+//! use meshcore::health::HealthConfig;
+//!
+//! # async fn example() -> meshcore::Result<()> {
+//! let mut client = MeshCore::serial("/dev/ttyUSB0");
+//! client.connect().await?;
+//! client.start_health_monitor(
+//!     HealthConfig::default()
+//!         .interval(Duration::from_secs(30))
+//!         .reconnect(true),
+//! );
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::Duration;
+
+use crate::client::Reconnector;
+use crate::commands::CommandHandler;
+use crate::error::{Error, Result};
+use crate::event::{Event, EventDispatcher};
+use crate::transport::Transport;
+
+/// Default interval between health probes.
+pub const DEFAULT_HEALTH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Default time to wait for a probe response.
+pub const DEFAULT_HEALTH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default number of failed probes before the connection is stalled.
+pub const DEFAULT_MAX_FAILURES: u32 = 3;
+
+/// Request used to check that the device is responsive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HealthProbe {
+    /// Query the device clock.
+    #[default]
+    GetTime,
+    /// Query the battery status.
+    GetBattery,
+}
+
+/// Health monitor configuration.
+#[derive(Debug, Clone)]
+pub struct HealthConfig {
+    /// Interval between probes.
+    pub interval: Duration,
+    /// Time to wait for a probe response.
+    pub timeout: Duration,
+    /// Consecutive failed probes before the connection is stalled.
+    pub max_failures: u32,
+    /// Request used as the probe.
+    pub probe: HealthProbe,
+    /// Whether to reconnect when the connection is stalled.
+    pub reconnect: bool,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            interval: DEFAULT_HEALTH_INTERVAL,
+            timeout: DEFAULT_HEALTH_TIMEOUT,
+            max_failures: DEFAULT_MAX_FAILURES,
+            probe: HealthProbe::default(),
+            reconnect: false,
+        }
+    }
+}
+
+impl HealthConfig {
+    /// Sets the interval between probes.
+    #[must_use]
+    pub const fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets the time to wait for a probe response.
+    #[must_use]
+    pub const fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the number of failed probes before the connection is stalled.
+    #[must_use]
+    pub const fn max_failures(mut self, max_failures: u32) -> Self {
+        self.max_failures = max_failures;
+        self
+    }
+
+    /// Sets the probe request.
+    #[must_use]
+    pub const fn probe(mut self, probe: HealthProbe) -> Self {
+        self.probe = probe;
+        self
+    }
+
+    /// Sets whether to reconnect when the connection is stalled.
+    #[must_use]
+    pub const fn reconnect(mut self, reconnect: bool) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+}
+
+/// Background task probing the device.
+pub(crate) struct HealthMonitor<T> {
+    config: HealthConfig,
+    commands: CommandHandler<T>,
+    reconnector: Reconnector<T>,
+    dispatcher: EventDispatcher,
+}
+
+impl<T: Transport> HealthMonitor<T> {
+    pub(crate) const fn new(
+        config: HealthConfig,
+        commands: CommandHandler<T>,
+        reconnector: Reconnector<T>,
+        dispatcher: EventDispatcher,
+    ) -> Self {
+        Self {
+            config,
+            commands,
+            reconnector,
+            dispatcher,
+        }
+    }
+
+    /// Sends one probe. Returns true if the device answered.
+    async fn probe(&self) -> bool {
+        let result: Result<Event> = match self.config.probe {
+            HealthProbe::GetTime => self.commands.get_time().await,
+            HealthProbe::GetBattery => self.commands.get_battery().await,
+        };
+        // An error code from the firmware still proves it is alive
+        matches!(result, Ok(_) | Err(Error::Device { .. }))
+    }
+
+    pub(crate) async fn run(self) {
+        let mut interval = tokio::time::interval(self.config.interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // The first tick completes immediately
+        interval.tick().await;
+
+        let mut failures = 0u32;
+        loop {
+            interval.tick().await;
+
+            if self.probe().await {
+                if failures > 0 {
+                    tracing::info!("device responsive again after {} failed probes", failures);
+                }
+                failures = 0;
+                continue;
+            }
+
+            failures += 1;
+            tracing::debug!("health probe failed ({} in a row)", failures);
+            if failures < self.config.max_failures {
+                continue;
+            }
+            if failures == self.config.max_failures {
+                tracing::warn!("connection stalled after {} failed probes", failures);
+                self.dispatcher
+                    .dispatch(Event::ConnectionStalled { failures });
+            }
+
+            if self.config.reconnect {
+                match self.reconnector.reconnect(&self.commands).await {
+                    Ok(_) => failures = 0,
+                    Err(e) => tracing::warn!("reconnect failed: {}", e),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::*;
+    use crate::protocol::{CommandOpcode, PacketType};
+    use crate::testing;
+
+    #[tokio::test]
+    async fn test_health_monitor_detects_stall() {
+        let hung = Arc::new(AtomicBool::new(false));
+        let device_hung = Arc::clone(&hung);
+        let (mut client, _push) = testing::connect(testing::self_info(0xAB, ""), move |request| {
+            if device_hung.load(Ordering::SeqCst) {
+                return Vec::new();
+            }
+            match CommandOpcode::from_byte(request[0]) {
+                Some(CommandOpcode::GetTime) => {
+                    vec![vec![PacketType::CurrentTime as u8, 0, 0, 0, 0]]
+                }
+                _ => vec![vec![PacketType::Ok as u8]],
+            }
+        })
+        .await;
+        let mut events = client.subscribe();
+        client.start_health_monitor(
+            HealthConfig::default()
+                .interval(Duration::from_millis(20))
+                .timeout(Duration::from_millis(20))
+                .max_failures(2),
+        );
+
+        // Healthy probes do not raise events
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        hung.store(true, Ordering::SeqCst);
+
+        let stalled = tokio::time::timeout(Duration::from_secs(1), async {
+            loop {
+                if let Some(Event::ConnectionStalled { failures }) = events.recv().await {
+                    return failures;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(stalled, 2);
+    }
+}
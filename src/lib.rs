@@ -41,16 +41,40 @@
 //!
 //! - [`protocol`] - Low-level protocol types (frames, packets, commands)
 //! - [`types`] - Data structures (contacts, devices, messages, statistics)
-//! - [`transport`] - Transport implementations (currently USB/Serial)
+//! - [`transport`] - Transport implementations (USB/Serial, generic streams, capture/replay)
 //! - [`event`] - Async event system for handling notifications
 //! - [`commands`] - Command handler for device operations
 //! - [`client`] - High-level [`MeshCore`] client
+//! - [`health`] - Connection health monitoring
+//! - [`clock`] - Device clock drift monitoring and synchronisation
+//! - [`manager`] - Several radios in one process
+//! - [`bot`] - Command bots answering direct and channel messages
+//! - [`room`] - Room server sessions
+//! - [`repeater`] - Repeater remote administration
+//! - [`poller`] - Scheduled telemetry polling
+//! - `gateway` - HTTP/JSON gateway (feature `gateway`)
+//! - `mqtt` - MQTT bridge (feature `mqtt`)
 
+pub mod bot;
 pub mod client;
+pub mod clock;
 pub mod commands;
 pub mod error;
 pub mod event;
+#[cfg(feature = "gateway")]
+pub mod gateway;
+pub mod health;
+pub mod manager;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+pub mod poller;
 pub mod protocol;
+#[cfg(feature = "proxy")]
+pub mod proxy;
+pub mod repeater;
+pub mod room;
+#[cfg(test)]
+mod testing;
 pub mod transport;
 pub mod types;
 
@@ -59,11 +83,14 @@ pub use client::MeshCore;
 pub use commands::ContactUpdateParams;
 pub use error::{Error, FrameError, Result};
 pub use event::{Event, EventDispatcher, EventFilter, StatsData, Subscription};
-pub use protocol::{BinaryReqType, CommandOpcode, PacketType, StatsType};
-pub use transport::{SerialTransport, serial::list_ports};
+pub use protocol::{BinaryReqType, CommandOpcode, ErrorCode, PacketType, StatsType};
+pub use transport::{
+    DiscoveredDevice, SerialTransport, StreamTransport, discover_devices, serial::list_ports,
+};
 pub use types::{
-    Acknowledgment, BatteryStatus, Channel, ChannelMessage, Contact, ContactFlags, ContactMessage,
-    ContactType, CoreStats, DeviceInfo, DeviceStatus, PacketStats, PublicKey, RadioConfig,
-    RadioStats, SelfInfo, SignalQuality, Telemetry, TelemetryMode, TelemetryReading,
-    TelemetryValue, TextType,
+    Acknowledgment, BatteryStatus, Channel, ChannelMessage, Contact, ContactCard, ContactFlags,
+    ContactMessage, ContactType, CoreStats, CustomVars, DeviceInfo, DeviceStatus, DiscoverResponse,
+    DiscoveredKey, PacketStats, PayloadType, PublicKey, RadioConfig, RadioStats, RouteType,
+    RxLogEntry, SelfInfo, SignalQuality, Signature, Telemetry, TelemetryMode, TelemetryReading,
+    TelemetryValue, TextType, TraceResult,
 };
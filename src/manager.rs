@@ -0,0 +1,386 @@
+//! Management of several radios in one process.
+//!
+//! [`DeviceManager`] owns connected [`MeshCore`] clients keyed by their
+//! public key. It merges their events into one stream tagged with the
+//! source device, aggregates their contact caches and routes outgoing
+//! messages through the best radio for a contact:
+//!
+//! ```no_run
+//! use meshcore::MeshCore;
+//! use meshcore::manager::DeviceManager;
+//!
+//! # async fn example() -> meshcore::Result<()> {
+//! let mut manager = DeviceManager::new();
+//! for port in ["/dev/ttyACM0", "/dev/ttyACM1"] {
+//!     let mut client = MeshCore::serial(port);
+//!     client.connect().await?;
+//!     manager.add(client).await?;
+//! }
+//!
+//! let mut events = manager.subscribe();
+//! while let Some(event) = events.recv().await {
+//!     println!("{}: {:?}", event.device, event.event);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+use crate::client::MeshCore;
+use crate::error::{Error, Result};
+use crate::event::Event;
+use crate::transport::Transport;
+use crate::types::{Contact, PublicKey, SelfInfo};
+
+/// Capacity of the merged event channel.
+const EVENT_CAPACITY: usize = 256;
+
+/// An event tagged with the device it came from.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DeviceEvent {
+    /// Public key of the source device.
+    pub device: PublicKey,
+    /// The event.
+    pub event: Event,
+}
+
+/// A subscription to the merged events of all devices.
+pub struct DeviceSubscription {
+    receiver: broadcast::Receiver<DeviceEvent>,
+}
+
+impl DeviceSubscription {
+    /// Receives the next event.
+    ///
+    /// Returns `None` when the manager is dropped.
+    pub async fn recv(&mut self) -> Option<DeviceEvent> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) => return Some(event),
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+/// A contact as known by one device.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DeviceContact {
+    /// Public key of the device that knows the contact.
+    pub device: PublicKey,
+    /// The contact as cached by that device.
+    pub contact: Contact,
+}
+
+/// Routing preference of a contact entry (lower is better).
+type RouteRank = (bool, i8, Reverse<u32>);
+
+/// Ranks a contact entry for routing.
+///
+/// Prefers a known direct path, then fewer hops, then the most recent
+/// advertisement.
+fn route_rank(contact: &Contact) -> RouteRank {
+    (
+        contact.is_flood(),
+        contact.out_path_len,
+        Reverse(contact.last_advert),
+    )
+}
+
+struct ManagedDevice<T> {
+    client: MeshCore<T>,
+    self_info: SelfInfo,
+    forward_task: JoinHandle<()>,
+}
+
+/// Manager for several connected `MeshCore` devices.
+pub struct DeviceManager<T> {
+    // Insertion order breaks routing ties
+    devices: Vec<ManagedDevice<T>>,
+    events: broadcast::Sender<DeviceEvent>,
+}
+
+impl<T: Transport + 'static> Default for DeviceManager<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Transport + 'static> DeviceManager<T> {
+    /// Creates an empty manager.
+    #[must_use]
+    pub fn new() -> Self {
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        Self {
+            devices: Vec::new(),
+            events,
+        }
+    }
+
+    /// Adds a connected client.
+    ///
+    /// Returns the device's public key, which identifies it in the
+    /// manager. A device with the same key replaces the previous one.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NotConnected`] if the client has not connected.
+    pub async fn add(&mut self, client: MeshCore<T>) -> Result<PublicKey> {
+        let self_info = client.self_info().await.ok_or(Error::NotConnected)?;
+        let device = self_info.public_key.clone();
+
+        let mut subscription = client.subscribe();
+        let events = self.events.clone();
+        let source = device.clone();
+        let forward_task = tokio::spawn(async move {
+            while let Some(event) = subscription.recv().await {
+                // No subscribers is fine
+                let _ = events.send(DeviceEvent {
+                    device: source.clone(),
+                    event,
+                });
+            }
+        });
+
+        self.remove(&device);
+        tracing::info!("managing device {} ({})", self_info.name, device);
+        self.devices.push(ManagedDevice {
+            client,
+            self_info,
+            forward_task,
+        });
+        Ok(device)
+    }
+
+    /// Removes a device and returns its client.
+    pub fn remove(&mut self, device: &PublicKey) -> Option<MeshCore<T>> {
+        let index = self
+            .devices
+            .iter()
+            .position(|d| d.self_info.public_key == *device)?;
+        let removed = self.devices.remove(index);
+        removed.forward_task.abort();
+        Some(removed.client)
+    }
+
+    /// Returns the client for a device.
+    #[must_use]
+    pub fn get(&self, device: &PublicKey) -> Option<&MeshCore<T>> {
+        self.devices
+            .iter()
+            .find(|d| d.self_info.public_key == *device)
+            .map(|d| &d.client)
+    }
+
+    /// Returns the client for a device by its advertised name.
+    #[must_use]
+    pub fn get_by_name(&self, name: &str) -> Option<&MeshCore<T>> {
+        self.devices
+            .iter()
+            .find(|d| d.self_info.name == name)
+            .map(|d| &d.client)
+    }
+
+    /// Returns the managed devices in the order they were added.
+    pub fn devices(&self) -> impl Iterator<Item = (&SelfInfo, &MeshCore<T>)> {
+        self.devices.iter().map(|d| (&d.self_info, &d.client))
+    }
+
+    /// Returns the number of managed devices.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.devices.len()
+    }
+
+    /// Returns true if no devices are managed.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.devices.is_empty()
+    }
+
+    /// Subscribes to the merged events of all devices.
+    #[must_use]
+    pub fn subscribe(&self) -> DeviceSubscription {
+        DeviceSubscription {
+            receiver: self.events.subscribe(),
+        }
+    }
+
+    /// Returns the cached contacts of all devices, grouped by contact.
+    pub async fn contacts(&self) -> HashMap<PublicKey, Vec<DeviceContact>> {
+        let mut all: HashMap<PublicKey, Vec<DeviceContact>> = HashMap::new();
+        for device in &self.devices {
+            for (key, contact) in device.client.contacts().await {
+                all.entry(key).or_default().push(DeviceContact {
+                    device: device.self_info.public_key.clone(),
+                    contact,
+                });
+            }
+        }
+        all
+    }
+
+    /// Fetches the contact lists of all devices.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error encountered; contacts of the other devices
+    /// are still refreshed.
+    pub async fn refresh_contacts(&self) -> Result<()> {
+        let results =
+            futures::future::join_all(self.devices.iter().map(|d| d.client.get_contacts())).await;
+        results.into_iter().try_for_each(|r| r.map(|_| ()))
+    }
+
+    /// Returns the device best suited to reach a contact.
+    ///
+    /// Devices with a known direct path are preferred over flood routing,
+    /// then shorter paths, then the most recent advertisement.
+    pub async fn route_for(&self, contact: &PublicKey) -> Option<PublicKey> {
+        let mut best: Option<(PublicKey, RouteRank)> = None;
+        for device in &self.devices {
+            let Some(entry) = device.client.get_contact(contact).await else {
+                continue;
+            };
+            let rank = route_rank(&entry);
+            if best.as_ref().is_none_or(|(_, best_rank)| rank < *best_rank) {
+                best = Some((device.self_info.public_key.clone(), rank));
+            }
+        }
+        best.map(|(device, _)| device)
+    }
+
+    /// Sends a message through the best device for the destination.
+    ///
+    /// Returns the device that sent the message.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NoRoute`] if no device knows the destination, or
+    /// any error from sending.
+    pub async fn send_message(&self, destination: &PublicKey, message: &str) -> Result<PublicKey> {
+        let device = self
+            .route_for(destination)
+            .await
+            .ok_or_else(|| Error::NoRoute {
+                destination: destination.to_hex(),
+            })?;
+        let client = self.get(&device).ok_or(Error::NotConnected)?;
+        client.send_message(destination, message).await?;
+        Ok(device)
+    }
+
+    /// Disconnects all devices.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error encountered; all devices are disconnected.
+    pub async fn disconnect_all(&mut self) -> Result<()> {
+        let mut result = Ok(());
+        for device in &mut self.devices {
+            if let Err(e) = device.client.disconnect().await {
+                result = result.and(Err(e));
+            }
+        }
+        result
+    }
+}
+
+impl<T> Drop for DeviceManager<T> {
+    fn drop(&mut self) {
+        for device in &self.devices {
+            device.forward_task.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::protocol::{CommandOpcode, PacketType};
+    use crate::testing::{self, Client};
+
+    /// Connects a fake device with the given key byte and contacts, and
+    /// returns a sender for push frames.
+    async fn connect(key: u8, contacts: Vec<Vec<u8>>) -> (Client, mpsc::UnboundedSender<Bytes>) {
+        testing::connect(
+            testing::self_info(key, &format!("radio{key}")),
+            move |request| match CommandOpcode::from_byte(request[0]) {
+                Some(CommandOpcode::GetContacts) => testing::contacts(&contacts),
+                _ => vec![vec![PacketType::Ok as u8]],
+            },
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_manager_routes_via_direct_path() {
+        let mut manager = DeviceManager::new();
+        let (a, _) = connect(0xA0, vec![testing::contact(0xCC, "", -1)]).await;
+        let (b, _) = connect(
+            0xB0,
+            vec![
+                testing::contact(0xCC, "", 2),
+                testing::contact(0xDD, "", -1),
+            ],
+        )
+        .await;
+        let a = manager.add(a).await.unwrap();
+        let b = manager.add(b).await.unwrap();
+        assert_eq!(manager.len(), 2);
+        assert!(manager.get_by_name("radio176").is_some());
+
+        manager.refresh_contacts().await.unwrap();
+        let contacts = manager.contacts().await;
+        assert_eq!(contacts[&PublicKey::from_bytes(&[0xCC; 32])].len(), 2);
+
+        assert_eq!(
+            manager.route_for(&PublicKey::from_bytes(&[0xCC; 32])).await,
+            Some(b.clone())
+        );
+        assert_eq!(
+            manager.route_for(&PublicKey::from_bytes(&[0xDD; 32])).await,
+            Some(b)
+        );
+        assert_eq!(
+            manager.route_for(&PublicKey::from_bytes(&[0xEE; 32])).await,
+            None
+        );
+
+        assert!(manager.remove(&a).is_some());
+        assert_eq!(manager.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_manager_tags_events_with_device() {
+        let mut manager = DeviceManager::new();
+        let (a, _a_push) = connect(0xA0, Vec::new()).await;
+        let (b, b_push) = connect(0xB0, Vec::new()).await;
+        manager.add(a).await.unwrap();
+        let b = manager.add(b).await.unwrap();
+        let mut events = manager.subscribe();
+
+        b_push
+            .send(Bytes::from_static(&[PacketType::MessagesWaiting as u8]))
+            .unwrap();
+        let event = tokio::time::timeout(Duration::from_secs(1), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.device, b);
+        assert!(matches!(event.event, Event::MessagesWaiting));
+    }
+}
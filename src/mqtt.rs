@@ -0,0 +1,535 @@
+//! MQTT bridge.
+//!
+//! The [`MqttBridge`] publishes events of a connected [`MeshCore`] client
+//! to an MQTT broker and sends messages requested on command topics, so
+//! that Grafana, Home Assistant and similar tools can use the mesh:
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use meshcore::MeshCore;
+//! use meshcore::mqtt::MqttBridge;
+//! use rumqttc::MqttOptions;
+//!
+//! # async fn example() -> meshcore::Result<()> {
+//! let mut client = MeshCore::serial("/dev/ttyUSB0");
+//! client.connect().await?;
+//! let options = MqttOptions::new("meshcore-bridge", "localhost", 1883);
+//! MqttBridge::new(Arc::new(client), options).prefix("mesh").run().await
+//! # }
+//! ```
+//!
+//! # Topics
+//!
+//! All topics are below `<prefix>/<node>`, where `<node>` is the hex
+//! public key of the connected radio. Payloads are JSON.
+//!
+//! | Topic | Payload |
+//! |-------|---------|
+//! | `online` | `true`/`false` (retained, `false` is the last will) |
+//! | `message/direct/<sender prefix>` | [`ContactMessage`](crate::ContactMessage) plus `sender_name` |
+//! | `message/channel/<index>` | [`ChannelMessage`](crate::ChannelMessage) |
+//! | `advert/<public key>` | Contact, or `{"public_key": ...}` for known contacts |
+//! | `ack` | [`Acknowledgment`](crate::Acknowledgment) |
+//! | `telemetry/<sender prefix>` | [`Telemetry`](crate::Telemetry) plus `pubkey_prefix` |
+//! | `status/<sender prefix>` | [`DeviceStatus`](crate::DeviceStatus) |
+//! | `stats` | `{"core": ..., "radio": ..., "packets": ...}`, polled periodically |
+//! | `sent` | `{"to": ..., "expected_ack": ..., "timeout_ms": ...}` after a command |
+//! | `error` | `{"topic": ..., "error": ...}` for a failed command |
+//!
+//! Commands are published by other clients:
+//!
+//! | Topic | Payload |
+//! |-------|---------|
+//! | `command/send` | `{"to": "<name, name prefix or hex key>", "text": "..."}` |
+//! | `command/channel/<index>` | `{"text": "..."}` |
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use rumqttc::{AsyncClient, LastWill, MqttOptions, Packet, QoS};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::client::{MeshCore, current_timestamp};
+use crate::error::{Error, Result};
+use crate::event::Event;
+use crate::transport::Transport;
+
+/// Default topic prefix.
+pub const DEFAULT_PREFIX: &str = "meshcore";
+
+/// Default interval between statistics polls.
+pub const DEFAULT_STATS_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Delay before polling the broker connection again after an error.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Number of MQTT requests buffered while the broker is unreachable.
+const REQUEST_BUFFER: usize = 64;
+
+/// Returns the topic (relative to the node) and payload for an event.
+fn event_message(event: &Event) -> Option<(String, Value)> {
+    let message = match event {
+        Event::ContactMessage(message) => (
+            format!("message/direct/{}", hex::encode(message.sender_prefix)),
+            serde_json::to_value(message).ok()?,
+        ),
+        Event::ChannelMessage(message) => (
+            format!("message/channel/{}", message.channel_index),
+            serde_json::to_value(message).ok()?,
+        ),
+        Event::Advertisement(key) => (format!("advert/{key}"), json!({ "public_key": key })),
+        Event::NewContactAdvert(contact) => (
+            format!("advert/{}", contact.public_key),
+            serde_json::to_value(contact).ok()?,
+        ),
+        Event::Ack(ack) => ("ack".to_string(), serde_json::to_value(ack).ok()?),
+        Event::TelemetryResponse {
+            pubkey_prefix,
+            telemetry,
+        } => {
+            let prefix = hex::encode(pubkey_prefix);
+            let mut payload = serde_json::to_value(telemetry).ok()?;
+            payload["pubkey_prefix"] = json!(prefix);
+            (format!("telemetry/{prefix}"), payload)
+        }
+        Event::StatusResponse(status) => (
+            format!("status/{}", hex::encode(status.pubkey_prefix)),
+            serde_json::to_value(status).ok()?,
+        ),
+        _ => return None,
+    };
+    Some(message)
+}
+
+/// A command received on a command topic.
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Message { to: String, text: String },
+    Channel { index: u8, text: String },
+}
+
+#[derive(Deserialize)]
+struct MessagePayload {
+    to: String,
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct ChannelPayload {
+    text: String,
+}
+
+/// Parses a command from its topic (relative to `command/`) and payload.
+fn parse_command(topic: &str, payload: &[u8]) -> Result<Command> {
+    let invalid = |reason: String| Error::Mqtt { reason };
+    match topic.split('/').collect::<Vec<_>>().as_slice() {
+        ["send"] => {
+            let payload: MessagePayload = serde_json::from_slice(payload)
+                .map_err(|e| invalid(format!("invalid send command: {e}")))?;
+            Ok(Command::Message {
+                to: payload.to,
+                text: payload.text,
+            })
+        }
+        ["channel", index] => {
+            let index = index
+                .parse()
+                .map_err(|_| invalid(format!("invalid channel index: {index}")))?;
+            let payload: ChannelPayload = serde_json::from_slice(payload)
+                .map_err(|e| invalid(format!("invalid channel command: {e}")))?;
+            Ok(Command::Channel {
+                index,
+                text: payload.text,
+            })
+        }
+        _ => Err(invalid(format!("unknown command: {topic}"))),
+    }
+}
+
+/// Bridge between a client and an MQTT broker.
+pub struct MqttBridge<T> {
+    client: Arc<MeshCore<T>>,
+    options: MqttOptions,
+    prefix: String,
+    stats_interval: Option<Duration>,
+}
+
+impl<T: Transport + 'static> MqttBridge<T> {
+    /// Creates a bridge for a connected client and broker options.
+    #[must_use]
+    pub fn new(client: Arc<MeshCore<T>>, options: MqttOptions) -> Self {
+        Self {
+            client,
+            options,
+            prefix: DEFAULT_PREFIX.to_string(),
+            stats_interval: Some(DEFAULT_STATS_INTERVAL),
+        }
+    }
+
+    /// Sets the topic prefix (default [`DEFAULT_PREFIX`]).
+    #[must_use]
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Sets the statistics poll interval, or disables polling with `None`
+    /// (default [`DEFAULT_STATS_INTERVAL`]).
+    #[must_use]
+    pub const fn stats_interval(mut self, interval: Option<Duration>) -> Self {
+        self.stats_interval = interval;
+        self
+    }
+
+    /// Runs the bridge until the client disconnects.
+    ///
+    /// Broker connection errors are logged and retried; the client's
+    /// waiting messages are fetched as the device announces them.
+    pub async fn run(mut self) -> Result<()> {
+        let node = self
+            .client
+            .self_info()
+            .await
+            .ok_or(Error::NotConnected)?
+            .public_key;
+        let base = format!("{}/{}", self.prefix, node);
+        self.options.set_last_will(LastWill::new(
+            format!("{base}/online"),
+            "false",
+            QoS::AtLeastOnce,
+            true,
+        ));
+        let (mqtt, mut eventloop) = AsyncClient::new(self.options, REQUEST_BUFFER);
+        let bridge = Bridge {
+            client: self.client,
+            mqtt,
+            base,
+        };
+
+        let mut events = bridge.client.subscribe();
+        let mut stats = self.stats_interval.map(tokio::time::interval);
+        bridge.client.fetch_messages().await?;
+
+        loop {
+            tokio::select! {
+                notification = eventloop.poll() => match notification {
+                    Ok(rumqttc::Event::Incoming(Packet::ConnAck(_))) => bridge.connected(),
+                    Ok(rumqttc::Event::Incoming(Packet::Publish(publish))) => {
+                        bridge.command(&publish.topic, &publish.payload).await;
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        tracing::warn!("mqtt: broker connection failed: {}", e);
+                        tokio::time::sleep(RECONNECT_DELAY).await;
+                    }
+                },
+                event = events.recv() => match event {
+                    Some(Event::MessagesWaiting) => {
+                        if let Err(e) = bridge.client.fetch_messages().await {
+                            tracing::warn!("mqtt: failed to fetch messages: {}", e);
+                        }
+                    }
+                    Some(Event::Disconnected) | None => {
+                        bridge.publish("online", &false.into(), true);
+                        return Ok(());
+                    }
+                    Some(event) => bridge.event(&event).await,
+                },
+                () = async {
+                    match stats.as_mut() {
+                        Some(stats) => drop(stats.tick().await),
+                        None => std::future::pending().await,
+                    }
+                } => bridge.stats().await,
+            }
+        }
+    }
+}
+
+/// Running bridge state.
+struct Bridge<T> {
+    client: Arc<MeshCore<T>>,
+    mqtt: AsyncClient,
+    /// Topic prefix including the node key.
+    base: String,
+}
+
+impl<T: Transport + 'static> Bridge<T> {
+    fn topic(&self, suffix: &str) -> String {
+        format!("{}/{suffix}", self.base)
+    }
+
+    /// Queues a publish without waiting, so a slow broker cannot block the
+    /// event loop.
+    fn publish(&self, suffix: &str, payload: &Value, retain: bool) {
+        let topic = self.topic(suffix);
+        if let Err(e) = self
+            .mqtt
+            .try_publish(&topic, QoS::AtLeastOnce, retain, payload.to_string())
+        {
+            tracing::warn!("mqtt: dropping message for {}: {}", topic, e);
+        }
+    }
+
+    /// Subscribes to commands and announces the bridge after (re)connecting.
+    fn connected(&self) {
+        tracing::info!("mqtt: connected, publishing to {}", self.base);
+        if let Err(e) = self
+            .mqtt
+            .try_subscribe(self.topic("command/#"), QoS::AtLeastOnce)
+        {
+            tracing::warn!("mqtt: failed to subscribe to commands: {}", e);
+        }
+        self.publish("online", &true.into(), true);
+    }
+
+    async fn event(&self, event: &Event) {
+        let Some((suffix, mut payload)) = event_message(event) else {
+            return;
+        };
+        if let (Event::ContactMessage(message), Value::Object(fields)) = (event, &mut payload) {
+            let name = self
+                .client
+                .find_contact_by_prefix(&message.sender_prefix)
+                .await
+                .map(|c| c.name);
+            fields.insert("sender_name".into(), json!(name));
+        }
+        self.publish(&suffix, &payload, false);
+    }
+
+    async fn stats(&self) {
+        let stats = async {
+            Ok::<_, Error>(json!({
+                "core": self.client.get_core_stats().await?,
+                "radio": self.client.get_radio_stats().await?,
+                "packets": self.client.get_packet_stats().await?,
+            }))
+        };
+        match stats.await {
+            Ok(stats) => self.publish("stats", &stats, false),
+            Err(e) => tracing::warn!("mqtt: failed to poll stats: {}", e),
+        }
+    }
+
+    async fn command(&self, topic: &str, payload: &[u8]) {
+        let Some(command) = topic
+            .strip_prefix(&self.base)
+            .and_then(|t| t.strip_prefix("/command/"))
+        else {
+            return;
+        };
+        let result = match parse_command(command, payload) {
+            Ok(command) => self.execute(command).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(Some(sent)) => self.publish("sent", &sent, false),
+            Ok(None) => {}
+            Err(e) => self.publish(
+                "error",
+                &json!({ "topic": topic, "error": e.to_string() }),
+                false,
+            ),
+        }
+    }
+
+    async fn execute(&self, command: Command) -> Result<Option<Value>> {
+        match command {
+            Command::Message { to, text } => {
+                let key = self.client.resolve_contact(&to).await?;
+                match self
+                    .client
+                    .commands()
+                    .send_message(&key, &text, 0, current_timestamp())
+                    .await?
+                {
+                    Event::MessageSent {
+                        expected_ack,
+                        timeout_ms,
+                    } => Ok(Some(json!({
+                        "to": key,
+                        "expected_ack": expected_ack,
+                        "timeout_ms": timeout_ms,
+                    }))),
+                    _ => Err(Error::Protocol {
+                        message: "unexpected response".into(),
+                    }),
+                }
+            }
+            Command::Channel { index, text } => {
+                self.client.send_channel_message(index, &text).await?;
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::protocol::{CommandOpcode, PacketType};
+    use crate::testing;
+    use crate::types::{
+        Acknowledgment, ChannelMessage, ContactMessage, PublicKey, Telemetry, TextType,
+    };
+
+    /// Waits for a publish on a topic.
+    async fn wait_for(eventloop: &mut rumqttc::EventLoop, topic: &str) -> Bytes {
+        loop {
+            if let rumqttc::Event::Incoming(Packet::Publish(p)) = eventloop.poll().await.unwrap() {
+                if p.topic == topic {
+                    return p.payload;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_event_topics() {
+        let message = ContactMessage {
+            sender_prefix: [0xAB; 6],
+            path_len: 0,
+            text_type: TextType::Plain,
+            timestamp: 1,
+            signature: None,
+            text: "hi".into(),
+            signal: None,
+        };
+        let (topic, payload) = event_message(&Event::ContactMessage(Box::new(message))).unwrap();
+        assert_eq!(topic, "message/direct/abababababab");
+        assert_eq!(payload["text"], "hi");
+
+        let message = ChannelMessage {
+            channel_index: 3,
+            path_len: -1,
+            text_type: TextType::Plain,
+            timestamp: 1,
+            text: "a: b".into(),
+            signal: None,
+        };
+        let (topic, _) = event_message(&Event::ChannelMessage(Box::new(message))).unwrap();
+        assert_eq!(topic, "message/channel/3");
+
+        let key = PublicKey::from_bytes(&[0x11; 32]);
+        let (topic, payload) = event_message(&Event::Advertisement(key)).unwrap();
+        assert_eq!(topic, format!("advert/{}", "11".repeat(32)));
+        assert_eq!(payload["public_key"], "11".repeat(32));
+
+        let (topic, payload) = event_message(&Event::Ack(Acknowledgment { code: 7 })).unwrap();
+        assert_eq!((topic.as_str(), &payload["code"]), ("ack", &json!(7)));
+
+        let telemetry = Event::TelemetryResponse {
+            pubkey_prefix: [0x5E; 6],
+            telemetry: Box::new(Telemetry::parse_lpp(&[1, 103, 0x00, 0xEB])),
+        };
+        let (topic, payload) = event_message(&telemetry).unwrap();
+        assert_eq!(topic, "telemetry/5e5e5e5e5e5e");
+        assert_eq!(payload["pubkey_prefix"], "5e5e5e5e5e5e");
+        assert_eq!(payload["readings"][0]["channel"], 1);
+
+        assert!(event_message(&Event::Ok).is_none());
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(
+            parse_command("send", br#"{"to": "alice", "text": "hi"}"#).unwrap(),
+            Command::Message {
+                to: "alice".into(),
+                text: "hi".into()
+            }
+        );
+        assert_eq!(
+            parse_command("channel/2", br#"{"text": "hello"}"#).unwrap(),
+            Command::Channel {
+                index: 2,
+                text: "hello".into()
+            }
+        );
+        assert!(parse_command("send", b"hi").is_err());
+        assert!(parse_command("channel/x", br#"{"text": "hello"}"#).is_err());
+        assert!(parse_command("reboot", b"").is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "Requires an MQTT broker on localhost:1883"]
+    async fn test_bridge_with_broker() {
+        // Fake device that records channel messages
+        let (sent_tx, mut sent_rx) = tokio::sync::mpsc::unbounded_channel::<Bytes>();
+        let (client, push_tx) =
+            testing::connect(testing::self_info(0xB7, "bridge"), move |request| {
+                let response = match CommandOpcode::from_byte(request[0]) {
+                    Some(CommandOpcode::SendChannelMsg) => {
+                        sent_tx.send(Bytes::copy_from_slice(request)).unwrap();
+                        vec![PacketType::Ok as u8]
+                    }
+                    Some(CommandOpcode::GetMessage) => vec![PacketType::NoMoreMsgs as u8],
+                    _ => vec![PacketType::Ok as u8],
+                };
+                vec![response]
+            })
+            .await;
+
+        let base = format!("test-bridge/{}", "b7".repeat(32));
+        let bridge = MqttBridge::new(
+            Arc::new(client),
+            MqttOptions::new("meshcore-test-bridge", "localhost", 1883),
+        )
+        .prefix("test-bridge")
+        .stats_interval(None);
+        tokio::spawn(bridge.run());
+
+        let (observer, mut eventloop) = AsyncClient::new(
+            MqttOptions::new("meshcore-test-observer", "localhost", 1883),
+            16,
+        );
+        observer
+            .subscribe(format!("{base}/#"), QoS::AtLeastOnce)
+            .await
+            .unwrap();
+
+        let timeout = Duration::from_secs(5);
+
+        tokio::time::timeout(timeout, wait_for(&mut eventloop, &format!("{base}/online")))
+            .await
+            .unwrap();
+
+        let mut ack = vec![PacketType::Ack as u8];
+        ack.extend_from_slice(&42u32.to_le_bytes());
+        push_tx.send(Bytes::from(ack)).unwrap();
+        let payload =
+            tokio::time::timeout(timeout, wait_for(&mut eventloop, &format!("{base}/ack")))
+                .await
+                .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&payload).unwrap()["code"],
+            42
+        );
+
+        observer
+            .publish(
+                format!("{base}/command/channel/1"),
+                QoS::AtLeastOnce,
+                false,
+                r#"{"text": "from mqtt"}"#,
+            )
+            .await
+            .unwrap();
+        let request = tokio::time::timeout(timeout, async {
+            loop {
+                tokio::select! {
+                    request = sent_rx.recv() => return request.unwrap(),
+                    _ = eventloop.poll() => {}
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert!(request.ends_with(b"from mqtt"));
+    }
+}
@@ -0,0 +1,489 @@
+//! Scheduled telemetry polling.
+//!
+//! Sensor networks are read by asking every node for its telemetry at a
+//! fixed interval. A [`TelemetryPoller`] polls the device itself and a list
+//! of contacts, spreads the requests with random jitter so nodes do not
+//! answer at the same time, retries failed requests with exponential
+//! backoff and keeps the readings as an in-memory time series:
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! use futures::StreamExt;
+//! use meshcore::MeshCore;
+//! use meshcore::poller::{PollerConfig, TelemetryPoller};
+//!
+//! # async fn example() -> meshcore::Result<()> {
+//! let mut client = MeshCore::serial("/dev/ttyUSB0");
+//! client.connect().await?;
+//! client.get_contacts().await?;
+//! let sensor = client.find_contact("garden").await.unwrap().public_key;
+//!
+//! let poller = TelemetryPoller::start(
+//!     &Arc::new(client),
+//!     [sensor],
+//!     PollerConfig::default().interval(Duration::from_secs(600)),
+//! );
+//! let mut samples = std::pin::pin!(poller.stream());
+//! while let Some(sample) = samples.next().await {
+//!     println!("{}: {:?}", sample.source, sample.telemetry.readings);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::hash::BuildHasher;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::Stream;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+use crate::client::{MeshCore, current_timestamp};
+use crate::error::Result;
+use crate::transport::Transport;
+use crate::types::{PublicKey, Telemetry};
+
+/// Default interval between polls of a source.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Default jitter, as a fraction of the interval.
+pub const DEFAULT_POLL_JITTER: f64 = 0.1;
+
+/// Default number of retries of a failed poll.
+pub const DEFAULT_POLL_RETRIES: u32 = 3;
+
+/// Default delay before the first retry.
+pub const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Default number of samples kept per source.
+pub const DEFAULT_HISTORY: usize = 1024;
+
+/// Capacity of the sample channel.
+const SAMPLE_CAPACITY: usize = 256;
+
+/// How telemetry is requested from contacts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TelemetryMethod {
+    /// Binary request, see [`MeshCore::binary_telemetry`].
+    #[default]
+    Binary,
+    /// Telemetry request, see [`MeshCore::remote_telemetry`].
+    Request,
+}
+
+/// Telemetry poller configuration.
+#[derive(Debug, Clone)]
+pub struct PollerConfig {
+    /// Interval between polls of a source.
+    pub interval: Duration,
+    /// Random deviation of the interval, as a fraction of it (0.0 to 1.0).
+    pub jitter: f64,
+    /// How telemetry is requested from contacts.
+    pub method: TelemetryMethod,
+    /// Whether the device's own telemetry is polled as well.
+    pub local: bool,
+    /// Retries of a failed poll before waiting for the next interval.
+    pub retries: u32,
+    /// Delay before the first retry, doubled for each further retry.
+    pub backoff: Duration,
+    /// Samples kept per source.
+    pub history: usize,
+}
+
+impl Default for PollerConfig {
+    fn default() -> Self {
+        Self {
+            interval: DEFAULT_POLL_INTERVAL,
+            jitter: DEFAULT_POLL_JITTER,
+            method: TelemetryMethod::default(),
+            local: true,
+            retries: DEFAULT_POLL_RETRIES,
+            backoff: DEFAULT_RETRY_BACKOFF,
+            history: DEFAULT_HISTORY,
+        }
+    }
+}
+
+impl PollerConfig {
+    /// Sets the interval between polls of a source.
+    #[must_use]
+    pub const fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets the jitter as a fraction of the interval.
+    #[must_use]
+    pub const fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = clamp_jitter(jitter);
+        self
+    }
+
+    /// Sets how telemetry is requested from contacts.
+    #[must_use]
+    pub const fn method(mut self, method: TelemetryMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Enables or disables polling the device's own telemetry.
+    #[must_use]
+    pub const fn local(mut self, enabled: bool) -> Self {
+        self.local = enabled;
+        self
+    }
+
+    /// Sets the number of retries and the delay before the first one.
+    #[must_use]
+    pub const fn retries(mut self, retries: u32, backoff: Duration) -> Self {
+        self.retries = retries;
+        self.backoff = backoff;
+        self
+    }
+
+    /// Sets the number of samples kept per source.
+    #[must_use]
+    pub const fn history(mut self, history: usize) -> Self {
+        self.history = history;
+        self
+    }
+}
+
+/// Where a telemetry sample came from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TelemetrySource {
+    /// The connected device.
+    Local,
+    /// A contact.
+    Remote(PublicKey),
+}
+
+impl fmt::Display for TelemetrySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Local => write!(f, "local"),
+            Self::Remote(key) => write!(f, "{key}"),
+        }
+    }
+}
+
+/// A telemetry reading of one source.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TelemetrySample {
+    /// Where the reading came from.
+    pub source: TelemetrySource,
+    /// Host time of the reading (Unix seconds).
+    pub timestamp: u32,
+    /// The reading.
+    pub telemetry: Telemetry,
+}
+
+type Series = Arc<Mutex<HashMap<TelemetrySource, VecDeque<TelemetrySample>>>>;
+
+/// Polls telemetry in the background.
+///
+/// Polling stops with [`TelemetryPoller::stop`] or when the poller is
+/// dropped.
+pub struct TelemetryPoller {
+    series: Series,
+    samples: broadcast::Sender<TelemetrySample>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl TelemetryPoller {
+    /// Starts polling the given contacts and, if enabled, the device itself.
+    #[must_use]
+    pub fn start<T: Transport + 'static>(
+        client: &Arc<MeshCore<T>>,
+        contacts: impl IntoIterator<Item = PublicKey>,
+        config: PollerConfig,
+    ) -> Self {
+        let series = Series::default();
+        let (samples, _) = broadcast::channel(SAMPLE_CAPACITY);
+        let config = Arc::new(config);
+
+        let sources = config
+            .local
+            .then_some(TelemetrySource::Local)
+            .into_iter()
+            .chain(contacts.into_iter().map(TelemetrySource::Remote));
+        let tasks = sources
+            .map(|source| {
+                let poll = Poll {
+                    client: Arc::clone(client),
+                    source,
+                    config: Arc::clone(&config),
+                    series: Arc::clone(&series),
+                    samples: samples.clone(),
+                };
+                tokio::spawn(poll.run())
+            })
+            .collect();
+
+        Self {
+            series,
+            samples,
+            tasks,
+        }
+    }
+
+    /// Returns the samples of a source, oldest first.
+    #[must_use]
+    pub fn history(&self, source: &TelemetrySource) -> Vec<TelemetrySample> {
+        lock(&self.series)
+            .get(source)
+            .map(|samples| samples.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the most recent sample of a source.
+    #[must_use]
+    pub fn latest(&self, source: &TelemetrySource) -> Option<TelemetrySample> {
+        lock(&self.series)
+            .get(source)
+            .and_then(|samples| samples.back().cloned())
+    }
+
+    /// Returns a stream of new samples.
+    pub fn stream(&self) -> impl Stream<Item = TelemetrySample> + use<> {
+        futures::stream::unfold(self.samples.subscribe(), |mut samples| async move {
+            loop {
+                match samples.recv().await {
+                    Ok(sample) => return Some((sample, samples)),
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+
+    /// Stops polling.
+    ///
+    /// Aborts the polling tasks, which ends the sample streams.
+    pub fn stop(self) {
+        self.abort();
+    }
+
+    fn abort(&self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+impl Drop for TelemetryPoller {
+    fn drop(&mut self) {
+        self.abort();
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Returns a random number between 0.0 and 1.0.
+fn random_unit() -> f64 {
+    let bits = std::collections::hash_map::RandomState::new().hash_one(current_timestamp());
+    f64::from((bits >> 32) as u32) / f64::from(u32::MAX)
+}
+
+/// Limits a jitter to 0.0 to 1.0, treating NaN as no jitter.
+const fn clamp_jitter(jitter: f64) -> f64 {
+    if jitter.is_nan() {
+        0.0
+    } else {
+        jitter.clamp(0.0, 1.0)
+    }
+}
+
+/// Returns the interval deviated randomly by up to `jitter` of it.
+fn jittered(interval: Duration, jitter: f64) -> Duration {
+    let jitter = clamp_jitter(jitter);
+    interval.mul_f64(jitter.mul_add(2.0f64.mul_add(random_unit(), -1.0), 1.0))
+}
+
+/// Background task polling one source.
+struct Poll<T> {
+    client: Arc<MeshCore<T>>,
+    source: TelemetrySource,
+    config: Arc<PollerConfig>,
+    series: Series,
+    samples: broadcast::Sender<TelemetrySample>,
+}
+
+impl<T: Transport + 'static> Poll<T> {
+    async fn run(self) {
+        // Spread the first polls of all sources over the jitter window
+        let delay = self
+            .config
+            .interval
+            .mul_f64(clamp_jitter(self.config.jitter) * random_unit());
+        tokio::time::sleep(delay).await;
+
+        loop {
+            if let Some(telemetry) = self.poll_with_retries().await {
+                self.record(telemetry);
+            }
+            tokio::time::sleep(jittered(self.config.interval, self.config.jitter)).await;
+        }
+    }
+
+    async fn poll_with_retries(&self) -> Option<Telemetry> {
+        let mut backoff = self.config.backoff;
+        let mut retry = 0;
+        loop {
+            match self.poll().await {
+                Ok(telemetry) => return Some(telemetry),
+                Err(e) if retry < self.config.retries => {
+                    tracing::debug!(
+                        "telemetry poll of {} failed: {}, retrying in {:?}",
+                        self.source,
+                        e,
+                        backoff
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = backoff.saturating_mul(2).min(self.config.interval);
+                    retry += 1;
+                }
+                Err(e) => {
+                    tracing::warn!("telemetry poll of {} failed: {}", self.source, e);
+                    return None;
+                }
+            }
+        }
+    }
+
+    async fn poll(&self) -> Result<Telemetry> {
+        match (&self.source, self.config.method) {
+            (TelemetrySource::Local, _) => self.client.get_self_telemetry().await,
+            (TelemetrySource::Remote(key), TelemetryMethod::Binary) => {
+                self.client.binary_telemetry(key).await
+            }
+            (TelemetrySource::Remote(key), TelemetryMethod::Request) => {
+                self.client.remote_telemetry(key).await
+            }
+        }
+    }
+
+    fn record(&self, telemetry: Telemetry) {
+        let sample = TelemetrySample {
+            source: self.source.clone(),
+            timestamp: current_timestamp(),
+            telemetry,
+        };
+        {
+            let mut series = lock(&self.series);
+            let samples = series.entry(self.source.clone()).or_default();
+            samples.push_back(sample.clone());
+            while samples.len() > self.config.history {
+                samples.pop_front();
+            }
+        }
+        // No receivers is fine, the sample is kept in the series
+        let _ = self.samples.send(sample);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::protocol::{CommandOpcode, PacketType};
+    use crate::testing;
+    use crate::types::TelemetryValue;
+
+    const SENSOR: u8 = 0x5E;
+
+    #[test]
+    fn test_jittered() {
+        let interval = Duration::from_secs(100);
+        for _ in 0..100 {
+            let delay = jittered(interval, 0.1);
+            assert!(delay >= Duration::from_secs(90) && delay <= Duration::from_secs(110));
+        }
+        assert_eq!(jittered(interval, 0.0), interval);
+        assert_eq!(jittered(interval, f64::NAN), interval);
+        assert!(jittered(interval, 5.0) <= Duration::from_secs(200));
+    }
+
+    #[tokio::test]
+    async fn test_poller() {
+        let mut binary_requests = 0u32;
+        let (client, _push) = testing::connect(testing::self_info(0x01, ""), move |request| {
+            match CommandOpcode::from_byte(request[0]) {
+                Some(CommandOpcode::Telemetry) => {
+                    // Temperature 23.5 on channel 1
+                    let mut telemetry = vec![PacketType::TelemetryResponse as u8, 0];
+                    telemetry.extend_from_slice(&[0x01; 6]);
+                    telemetry.extend_from_slice(&[1, 103, 0x00, 0xEB]);
+                    vec![telemetry]
+                }
+                Some(CommandOpcode::BinaryReq) => {
+                    binary_requests += 1;
+                    let mut responses = vec![testing::msg_sent(binary_requests, 50)];
+                    // The first request goes unanswered
+                    if binary_requests > 1 {
+                        // Humidity 40% on channel 2
+                        let mut response = vec![PacketType::BinaryResponse as u8, 0];
+                        response.extend_from_slice(&binary_requests.to_le_bytes());
+                        response.extend_from_slice(&[2, 104, 80]);
+                        responses.push(response);
+                    }
+                    responses
+                }
+                _ => vec![vec![PacketType::Ok as u8]],
+            }
+        })
+        .await;
+        let sensor = PublicKey::from_bytes(&[SENSOR; 32]);
+        let poller = TelemetryPoller::start(
+            &Arc::new(client),
+            [sensor.clone()],
+            PollerConfig::default()
+                .interval(Duration::from_secs(3600))
+                .jitter(0.0)
+                .retries(2, Duration::from_millis(10)),
+        );
+
+        let samples: Vec<_> = tokio::time::timeout(
+            Duration::from_secs(5),
+            poller.stream().take(2).collect::<Vec<_>>(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(samples.len(), 2);
+
+        let local = poller.latest(&TelemetrySource::Local).unwrap();
+        assert!(matches!(
+            local.telemetry.readings[0].value,
+            TelemetryValue::Temperature(t) if (t - 23.5).abs() < f32::EPSILON
+        ));
+
+        let remote = poller.history(&TelemetrySource::Remote(sensor));
+        assert_eq!(remote.len(), 1);
+        assert_eq!(remote[0].telemetry.readings[0].channel, 2);
+        assert!(matches!(
+            remote[0].telemetry.readings[0].value,
+            TelemetryValue::Humidity(h) if (h - 40.0).abs() < f32::EPSILON
+        ));
+
+        // The stream ends once polling stops
+        let stream = poller.stream();
+        poller.stop();
+        let rest = tokio::time::timeout(Duration::from_secs(1), stream.collect::<Vec<_>>())
+            .await
+            .unwrap();
+        assert!(rest.is_empty());
+    }
+}
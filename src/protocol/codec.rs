@@ -0,0 +1,185 @@
+//! [`tokio_util::codec`] implementation of the frame protocol.
+//!
+//! [`FrameCodec`] turns any `AsyncRead + AsyncWrite` stream into a
+//! [`Framed`](tokio_util::codec::Framed) sink and stream of frame payloads:
+//!
+//! ```no_run
+//! use futures::{SinkExt, StreamExt};
+//! use meshcore::protocol::FrameCodec;
+//! use tokio_util::codec::Framed;
+//!
+//! # async fn example() -> Result<(), meshcore::Error> {
+//! let stream = tokio::net::TcpStream::connect("127.0.0.1:5000").await?;
+//! let mut framed = Framed::new(stream, FrameCodec::new());
+//!
+//! framed.send(bytes::Bytes::from_static(&[0x14])).await?; // GetBattery
+//! if let Some(payload) = framed.next().await {
+//!     println!("response: {:02x?}", payload?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use bytes::{BufMut, Bytes, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::error::{Error, FrameError};
+use crate::protocol::frame::{
+    DEVICE_FRAME_HEADER, DecoderStats, FRAME_HEADER, FrameDecoder, MAX_FRAME_SIZE, MIN_FRAME_SIZE,
+};
+
+/// Codec for `MeshCore` frames.
+///
+/// Frames with an invalid length are skipped (and counted in
+/// [`DecoderStats`]) rather than terminating the stream.
+#[derive(Debug)]
+pub struct FrameCodec {
+    decoder: FrameDecoder,
+    header: u8,
+}
+
+impl Default for FrameCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameCodec {
+    /// Creates a host-side codec: decodes device-to-host frames and
+    /// encodes host-to-device frames.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_decoder(FrameDecoder::new())
+    }
+
+    /// Creates a device-side codec: decodes host-to-device frames and
+    /// encodes device-to-host frames.
+    ///
+    /// Useful for device emulators, proxies and tests.
+    #[must_use]
+    pub fn device() -> Self {
+        Self {
+            decoder: FrameDecoder::with_header(FRAME_HEADER),
+            header: DEVICE_FRAME_HEADER,
+        }
+    }
+
+    /// Creates a host-side codec around an existing decoder.
+    #[must_use]
+    pub const fn with_decoder(decoder: FrameDecoder) -> Self {
+        Self {
+            decoder,
+            header: FRAME_HEADER,
+        }
+    }
+
+    /// Returns the decoder statistics.
+    #[must_use]
+    pub const fn stats(&self) -> DecoderStats {
+        self.decoder.stats()
+    }
+}
+
+impl Decoder for FrameCodec {
+    type Item = Bytes;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Bytes>, Error> {
+        let discarded_before = self.decoder.stats().bytes_discarded;
+        let mut rejected = None;
+        let frame = loop {
+            match self.decoder.decode_from(src) {
+                Ok(frame) => break frame,
+                // The decoder resynchronises to the next header; the error is
+                // reported with the discarded bytes below
+                Err(e) => rejected = Some(e),
+            }
+        };
+
+        let discarded = self.decoder.stats().bytes_discarded - discarded_before;
+        if let Some(e) = rejected {
+            tracing::warn!(
+                "frame decode error: {}, discarded {} bytes while resynchronising",
+                e,
+                discarded
+            );
+        } else if discarded > 0 {
+            tracing::debug!("discarded {} bytes while resynchronising", discarded);
+        }
+        Ok(frame)
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Bytes>, Error> {
+        // A trailing partial frame is dropped rather than reported as an error
+        self.decode(src)
+    }
+}
+
+impl Encoder<&[u8]> for FrameCodec {
+    type Error = Error;
+
+    fn encode(&mut self, payload: &[u8], dst: &mut BytesMut) -> Result<(), Error> {
+        let len = u16::try_from(payload.len()).map_err(|_| FrameError::TooLarge {
+            size: payload.len(),
+            max: MAX_FRAME_SIZE,
+        })?;
+        dst.reserve(MIN_FRAME_SIZE + payload.len());
+        dst.put_u8(self.header);
+        dst.put_u16_le(len);
+        dst.put_slice(payload);
+        Ok(())
+    }
+}
+
+impl Encoder<Bytes> for FrameCodec {
+    type Error = Error;
+
+    fn encode(&mut self, payload: Bytes, dst: &mut BytesMut) -> Result<(), Error> {
+        self.encode(payload.as_ref(), dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{SinkExt, StreamExt};
+    use tokio_util::codec::Framed;
+
+    use super::*;
+
+    #[test]
+    fn test_codec_encode() {
+        let mut codec = FrameCodec::new();
+        let mut buf = BytesMut::new();
+        codec.encode(&b"hi"[..], &mut buf).unwrap();
+        assert_eq!(&buf[..], &[FRAME_HEADER, 0x02, 0x00, b'h', b'i']);
+    }
+
+    #[test]
+    fn test_codec_skips_invalid_frames() {
+        let mut codec = FrameCodec::new();
+        let mut buf = BytesMut::from(&[0x3e, 0xFF, 0xFF, 0x3e, 0x02, 0x00, b'o', b'k'][..]);
+        assert_eq!(
+            codec.decode(&mut buf).unwrap(),
+            Some(Bytes::from_static(b"ok"))
+        );
+        assert_eq!(codec.stats().frames_rejected, 1);
+    }
+
+    #[tokio::test]
+    async fn test_codec_duplex() {
+        let (host, device) = tokio::io::duplex(256);
+        let mut host = Framed::new(host, FrameCodec::new());
+        let mut device = Framed::new(device, FrameCodec::device());
+
+        host.send(Bytes::from_static(&[0x14])).await.unwrap();
+        let request = device.next().await.unwrap().unwrap();
+        assert_eq!(&request[..], &[0x14]);
+
+        device
+            .send(Bytes::from_static(&[0x0C, 0xD4, 0x0D]))
+            .await
+            .unwrap();
+        let response = host.next().await.unwrap().unwrap();
+        assert_eq!(&response[..], &[0x0C, 0xD4, 0x0D]);
+    }
+}
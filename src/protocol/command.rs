@@ -96,6 +96,59 @@ pub enum CommandOpcode {
     GetStats = 0x38,
 }
 
+impl CommandOpcode {
+    /// Attempts to parse a command opcode from a byte.
+    #[must_use]
+    pub const fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0x01 => Some(Self::AppStart),
+            0x02 => Some(Self::SendMessage),
+            0x03 => Some(Self::SendChannelMsg),
+            0x04 => Some(Self::GetContacts),
+            0x05 => Some(Self::GetTime),
+            0x06 => Some(Self::SetTime),
+            0x07 => Some(Self::SendAdvert),
+            0x08 => Some(Self::SetName),
+            0x09 => Some(Self::UpdateContact),
+            0x0A => Some(Self::GetMessage),
+            0x0B => Some(Self::SetRadio),
+            0x0C => Some(Self::SetTxPower),
+            0x0D => Some(Self::ResetPath),
+            0x0E => Some(Self::SetCoords),
+            0x0F => Some(Self::RemoveContact),
+            0x10 => Some(Self::ShareContact),
+            0x11 => Some(Self::ExportContact),
+            0x12 => Some(Self::ImportContact),
+            0x13 => Some(Self::Reboot),
+            0x14 => Some(Self::GetBattery),
+            0x15 => Some(Self::SetTuning),
+            0x16 => Some(Self::DeviceQuery),
+            0x17 => Some(Self::ExportPrivateKey),
+            0x18 => Some(Self::ImportPrivateKey),
+            0x1A => Some(Self::SendLogin),
+            0x1B => Some(Self::SendStatusReq),
+            0x1D => Some(Self::SendLogout),
+            0x1F => Some(Self::GetChannel),
+            0x20 => Some(Self::SetChannel),
+            0x21 => Some(Self::SignStart),
+            0x22 => Some(Self::SignData),
+            0x23 => Some(Self::SignFinish),
+            0x24 => Some(Self::SendTrace),
+            0x25 => Some(Self::SetDevicePin),
+            0x26 => Some(Self::SetOtherParams),
+            0x27 => Some(Self::Telemetry),
+            0x28 => Some(Self::GetCustomVars),
+            0x29 => Some(Self::SetCustomVar),
+            0x32 => Some(Self::BinaryReq),
+            0x34 => Some(Self::PathDiscovery),
+            0x36 => Some(Self::SetFloodScope),
+            0x37 => Some(Self::SendControlData),
+            0x38 => Some(Self::GetStats),
+            _ => None,
+        }
+    }
+}
+
 impl From<CommandOpcode> for u8 {
     fn from(cmd: CommandOpcode) -> Self {
         cmd as Self
@@ -174,6 +227,20 @@ impl From<StatsType> for u8 {
 pub enum ControlDataType {
     /// Node discovery request.
     NodeDiscoverReq = 0x80,
+    /// Node discovery response (lower 4 bits carry the node type).
+    NodeDiscoverResp = 0x90,
+}
+
+impl ControlDataType {
+    /// Parses a control data type from a byte, ignoring the lower 4 flag bits.
+    #[must_use]
+    pub const fn from_byte(byte: u8) -> Option<Self> {
+        match byte & 0xF0 {
+            0x80 => Some(Self::NodeDiscoverReq),
+            0x90 => Some(Self::NodeDiscoverResp),
+            _ => None,
+        }
+    }
 }
 
 impl From<ControlDataType> for u8 {
@@ -196,6 +263,19 @@ mod tests {
         assert_eq!(CommandOpcode::BinaryReq as u8, 0x32);
     }
 
+    #[test]
+    fn test_command_opcode_from_byte() {
+        assert_eq!(
+            CommandOpcode::from_byte(0x01),
+            Some(CommandOpcode::AppStart)
+        );
+        assert_eq!(
+            CommandOpcode::from_byte(0x38),
+            Some(CommandOpcode::GetStats)
+        );
+        assert_eq!(CommandOpcode::from_byte(0xFF), None);
+    }
+
     #[test]
     fn test_message_type_values() {
         assert_eq!(MessageType::Private as u8, 0x00);
@@ -216,6 +296,19 @@ mod tests {
         assert_eq!(BinaryReqType::Telemetry as u8, 0x03);
     }
 
+    #[test]
+    fn test_control_data_type_from_byte() {
+        assert_eq!(
+            ControlDataType::from_byte(0x81),
+            Some(ControlDataType::NodeDiscoverReq)
+        );
+        assert_eq!(
+            ControlDataType::from_byte(0x92),
+            Some(ControlDataType::NodeDiscoverResp)
+        );
+        assert_eq!(ControlDataType::from_byte(0x10), None);
+    }
+
     #[test]
     fn test_command_from_conversion() {
         let cmd: u8 = CommandOpcode::AppStart.into();
@@ -0,0 +1,102 @@
+//! Error codes reported by the device firmware.
+//!
+//! Error responses carry a single code byte after the `Error` packet type.
+
+/// Firmware error codes returned in `Error` responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ErrorCode {
+    /// Command is not supported by this firmware.
+    UnsupportedCommand,
+    /// Requested item (contact, channel, ...) was not found.
+    NotFound,
+    /// Table (e.g. contacts) is full.
+    TableFull,
+    /// Device is in the wrong state for this command.
+    BadState,
+    /// File system I/O error on the device.
+    FileIoError,
+    /// Illegal argument.
+    IllegalArgument,
+    /// Unknown error code (or none given).
+    Unknown(u8),
+}
+
+impl ErrorCode {
+    /// Parses an error code from a byte.
+    #[must_use]
+    pub const fn from_byte(byte: u8) -> Self {
+        match byte {
+            1 => Self::UnsupportedCommand,
+            2 => Self::NotFound,
+            3 => Self::TableFull,
+            4 => Self::BadState,
+            5 => Self::FileIoError,
+            6 => Self::IllegalArgument,
+            other => Self::Unknown(other),
+        }
+    }
+
+    /// Parses an error code from an `Error` response payload.
+    ///
+    /// Older firmware may send an empty payload, which maps to `Unknown(0)`.
+    #[must_use]
+    pub fn from_payload(data: &[u8]) -> Self {
+        data.first()
+            .map_or(Self::Unknown(0), |&b| Self::from_byte(b))
+    }
+
+    /// Returns the raw byte value.
+    #[must_use]
+    pub const fn as_byte(self) -> u8 {
+        match self {
+            Self::UnsupportedCommand => 1,
+            Self::NotFound => 2,
+            Self::TableFull => 3,
+            Self::BadState => 4,
+            Self::FileIoError => 5,
+            Self::IllegalArgument => 6,
+            Self::Unknown(byte) => byte,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnsupportedCommand => write!(f, "unsupported command"),
+            Self::NotFound => write!(f, "not found"),
+            Self::TableFull => write!(f, "table full"),
+            Self::BadState => write!(f, "bad state"),
+            Self::FileIoError => write!(f, "file I/O error"),
+            Self::IllegalArgument => write!(f, "illegal argument"),
+            Self::Unknown(byte) => write!(f, "unknown error {byte}"),
+        }
+    }
+}
+
+impl From<ErrorCode> for u8 {
+    fn from(code: ErrorCode) -> Self {
+        code.as_byte()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_code_from_byte() {
+        assert_eq!(ErrorCode::from_byte(3), ErrorCode::TableFull);
+        assert_eq!(ErrorCode::from_byte(6), ErrorCode::IllegalArgument);
+        assert_eq!(ErrorCode::from_byte(42), ErrorCode::Unknown(42));
+        assert_eq!(ErrorCode::from_byte(2).as_byte(), 2);
+    }
+
+    #[test]
+    fn test_error_code_from_payload() {
+        assert_eq!(ErrorCode::from_payload(&[2]), ErrorCode::NotFound);
+        assert_eq!(ErrorCode::from_payload(&[]), ErrorCode::Unknown(0));
+    }
+}
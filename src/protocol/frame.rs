@@ -3,21 +3,30 @@
 //! The wire format uses a simple framing protocol:
 //! ```text
 //! ┌──────────┬──────────────┬─────────────────┐
-//! │  0x3c    │  size (LE)   │    payload      │
+//! │  header  │  size (LE)   │    payload      │
 //! │  1 byte  │   2 bytes    │   size bytes    │
 //! └──────────┴──────────────┴─────────────────┘
 //! ```
+//!
+//! The header is `0x3c` (`<`) for host-to-device frames and `0x3e` (`>`)
+//! for device-to-host frames.
 
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 
 use crate::error::FrameError;
 
-/// Frame header byte.
+/// Frame header byte for host-to-device frames.
 pub const FRAME_HEADER: u8 = 0x3c;
 
+/// Frame header byte for device-to-host frames.
+pub const DEVICE_FRAME_HEADER: u8 = 0x3e;
+
 /// Maximum frame payload size (64KB - 1).
 pub const MAX_FRAME_SIZE: usize = 65535;
 
+/// Maximum frame payload size accepted by the companion firmware.
+pub const MAX_DEVICE_FRAME_SIZE: usize = 172;
+
 /// Minimum frame size (header + 2-byte length).
 pub const MIN_FRAME_SIZE: usize = 3;
 
@@ -49,21 +58,62 @@ pub fn encode(payload: &[u8]) -> Bytes {
     buf.freeze()
 }
 
+/// Frame decoder statistics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecoderStats {
+    /// Frames successfully decoded.
+    pub frames_decoded: u64,
+    /// Frames rejected because of an invalid length.
+    pub frames_rejected: u64,
+    /// Bytes discarded while resynchronising to a frame header.
+    pub bytes_discarded: u64,
+}
+
 /// Frame decoder that handles partial data.
-#[derive(Debug, Default)]
+///
+/// The decoder only accepts frames starting with its expected header byte
+/// and skips anything else, so it resynchronises after corrupted input.
+#[derive(Debug)]
 pub struct FrameDecoder {
     buffer: BytesMut,
+    header: u8,
+    max_size: usize,
+    stats: DecoderStats,
+}
+
+impl Default for FrameDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl FrameDecoder {
-    /// Creates a new frame decoder.
+    /// Creates a decoder for device-to-host frames.
     #[must_use]
     pub fn new() -> Self {
+        Self::with_header(DEVICE_FRAME_HEADER)
+    }
+
+    /// Creates a decoder expecting the given header byte.
+    ///
+    /// Use [`FRAME_HEADER`] to decode host-to-device frames.
+    #[must_use]
+    pub fn with_header(header: u8) -> Self {
         Self {
             buffer: BytesMut::new(),
+            header,
+            max_size: MAX_DEVICE_FRAME_SIZE,
+            stats: DecoderStats::default(),
         }
     }
 
+    /// Sets the maximum accepted payload size.
+    #[must_use]
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size.min(MAX_FRAME_SIZE);
+        self
+    }
+
     /// Feeds data into the decoder.
     pub fn feed(&mut self, data: &[u8]) {
         self.buffer.extend_from_slice(data);
@@ -74,44 +124,90 @@ impl FrameDecoder {
     /// Returns `Ok(Some(payload))` if a complete frame was decoded,
     /// `Ok(None)` if more data is needed, or an error if the frame is invalid.
     ///
-    /// The frame format is: `<header> <length_lo> <length_hi> <payload...>`
-    /// where header is typically `0x3c` but we accept any header byte
-    /// (matching Python library behavior).
+    /// The frame format is: `<header> <length_lo> <length_hi> <payload...>`.
+    /// Bytes before the next header byte are discarded.
     ///
     /// # Errors
     ///
     /// Returns a `FrameError` if:
-    /// - The frame size exceeds the maximum
+    /// - The frame size exceeds the maximum. The header byte is dropped so
+    ///   the next call resynchronises to the following header.
     pub fn decode(&mut self) -> Result<Option<Bytes>, FrameError> {
-        if self.buffer.len() < MIN_FRAME_SIZE {
+        let mut buffer = std::mem::take(&mut self.buffer);
+        let result = self.decode_from(&mut buffer);
+        self.buffer = buffer;
+        result
+    }
+
+    /// Decodes the next complete frame from an external buffer.
+    ///
+    /// Behaves like [`Self::decode`] but consumes data from `buffer`
+    /// instead of the internal buffer (used by [`super::codec::FrameCodec`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns a `FrameError` if the frame size exceeds the maximum.
+    pub fn decode_from(&mut self, buffer: &mut BytesMut) -> Result<Option<Bytes>, FrameError> {
+        // Skip to the next header byte
+        match buffer.iter().position(|&b| b == self.header) {
+            Some(0) => {}
+            Some(pos) => self.discard(buffer, pos),
+            None => {
+                self.discard(buffer, buffer.len());
+                return Ok(None);
+            }
+        }
+
+        if buffer.len() < MIN_FRAME_SIZE {
             return Ok(None);
         }
 
         // Read length (little-endian u16) from bytes 1-2
-        // Note: We don't validate the header byte (byte 0) - matching Python behavior
-        let length = u16::from_le_bytes([self.buffer[1], self.buffer[2]]) as usize;
+        let length = u16::from_le_bytes([buffer[1], buffer[2]]) as usize;
 
-        if length > MAX_FRAME_SIZE {
+        if length > self.max_size {
+            self.stats.frames_rejected += 1;
+            self.discard(buffer, 1);
             return Err(FrameError::TooLarge {
                 size: length,
-                max: MAX_FRAME_SIZE,
+                max: self.max_size,
             });
         }
 
         let total_frame_size = MIN_FRAME_SIZE + length;
 
         // Check if we have the complete frame
-        if self.buffer.len() < total_frame_size {
+        if buffer.len() < total_frame_size {
+            buffer.reserve(total_frame_size - buffer.len());
             return Ok(None);
         }
 
         // Extract the frame
-        self.buffer.advance(MIN_FRAME_SIZE); // Skip header and length
-        let payload = self.buffer.split_to(length).freeze();
+        buffer.advance(MIN_FRAME_SIZE); // Skip header and length
+        let payload = buffer.split_to(length).freeze();
+        self.stats.frames_decoded += 1;
 
         Ok(Some(payload))
     }
 
+    /// Discards bytes from the front of a buffer.
+    fn discard(&mut self, buffer: &mut BytesMut, count: usize) {
+        buffer.advance(count);
+        self.stats.bytes_discarded += count as u64;
+    }
+
+    /// Returns the expected header byte.
+    #[must_use]
+    pub const fn header(&self) -> u8 {
+        self.header
+    }
+
+    /// Returns the decoder statistics.
+    #[must_use]
+    pub const fn stats(&self) -> DecoderStats {
+        self.stats
+    }
+
     /// Returns the number of bytes currently buffered.
     #[must_use]
     pub fn buffered(&self) -> usize {
@@ -142,7 +238,7 @@ mod tests {
     #[test]
     fn test_decode_complete_frame() {
         let mut decoder = FrameDecoder::new();
-        decoder.feed(&[0x3c, 0x05, 0x00, b'h', b'e', b'l', b'l', b'o']);
+        decoder.feed(&[0x3e, 0x05, 0x00, b'h', b'e', b'l', b'l', b'o']);
 
         let result = decoder.decode().unwrap();
         assert_eq!(result, Some(Bytes::from_static(b"hello")));
@@ -153,7 +249,7 @@ mod tests {
         let mut decoder = FrameDecoder::new();
 
         // Feed partial data
-        decoder.feed(&[0x3c, 0x05, 0x00, b'h', b'e']);
+        decoder.feed(&[0x3e, 0x05, 0x00, b'h', b'e']);
         assert_eq!(decoder.decode().unwrap(), None);
 
         // Feed remaining data
@@ -163,21 +259,53 @@ mod tests {
     }
 
     #[test]
-    fn test_decode_any_header() {
-        // Any header byte should be accepted (matching Python behavior)
+    fn test_decode_skips_wrong_header() {
+        // Host-to-device frames are not accepted by the default decoder
         let mut decoder = FrameDecoder::new();
-        decoder.feed(&[0x3e, 0x02, 0x00, b'o', b'k']); // 0x3e instead of 0x3c
+        decoder.feed(&[0x3c, 0x02, 0x00, b'o', b'k']);
+        assert_eq!(decoder.decode().unwrap(), None);
+        assert_eq!(decoder.stats().bytes_discarded, 5);
 
-        let result = decoder.decode().unwrap();
-        assert_eq!(result, Some(Bytes::from_static(b"ok")));
+        // ...but are by a decoder expecting the host header
+        let mut decoder = FrameDecoder::with_header(FRAME_HEADER);
+        decoder.feed(&[0x3c, 0x02, 0x00, b'o', b'k']);
+        assert_eq!(decoder.decode().unwrap(), Some(Bytes::from_static(b"ok")));
+    }
+
+    #[test]
+    fn test_decode_resync_after_garbage() {
+        let mut decoder = FrameDecoder::new();
+        decoder.feed(&[0x00, 0xFF, 0x12, 0x3e, 0x02, 0x00, b'h', b'i']);
+
+        assert_eq!(decoder.decode().unwrap(), Some(Bytes::from_static(b"hi")));
+        assert_eq!(decoder.stats().bytes_discarded, 3);
+        assert_eq!(decoder.stats().frames_decoded, 1);
+    }
+
+    #[test]
+    fn test_decode_rejects_oversized_frame() {
+        let mut decoder = FrameDecoder::new();
+        // Bogus header with a 60KB length, followed by a valid frame
+        decoder.feed(&[0x3e, 0x60, 0xEA, 0x3e, 0x02, 0x00, b'o', b'k']);
+
+        assert!(matches!(
+            decoder.decode(),
+            Err(FrameError::TooLarge { size: 60_000, .. })
+        ));
+        // The bogus length bytes are skipped while resynchronising
+        assert_eq!(decoder.decode().unwrap(), Some(Bytes::from_static(b"ok")));
+
+        let stats = decoder.stats();
+        assert_eq!(stats.frames_rejected, 1);
+        assert_eq!(stats.bytes_discarded, 3);
     }
 
     #[test]
     fn test_decode_multiple_frames() {
         let mut decoder = FrameDecoder::new();
         decoder.feed(&[
-            0x3c, 0x02, 0x00, b'h', b'i', // first frame
-            0x3c, 0x03, 0x00, b'b', b'y', b'e', // second frame
+            0x3e, 0x02, 0x00, b'h', b'i', // first frame
+            0x3e, 0x03, 0x00, b'b', b'y', b'e', // second frame
         ]);
 
         let first = decoder.decode().unwrap();
@@ -1,21 +1,30 @@
 //! Protocol definitions for `MeshCore` communication.
 //!
 //! This module contains the low-level protocol types including:
-//! - Frame encoding/decoding
+//! - Frame encoding/decoding (and a `tokio_util` codec)
 //! - Packet type definitions
 //! - Command opcodes
+//! - Firmware error codes
 //! - Binary data parsing
 
+pub mod codec;
 pub mod command;
+pub mod error_code;
 pub mod frame;
 pub mod packet;
 pub mod parser;
 
+pub use codec::FrameCodec;
 pub use command::{BinaryReqType, CommandOpcode, ControlDataType, MessageType, StatsType};
-pub use frame::{FRAME_HEADER, FrameDecoder, MAX_FRAME_SIZE, encode as encode_frame};
+pub use error_code::ErrorCode;
+pub use frame::{
+    DEVICE_FRAME_HEADER, DecoderStats, FRAME_HEADER, FrameDecoder, MAX_DEVICE_FRAME_SIZE,
+    MAX_FRAME_SIZE, encode as encode_frame,
+};
 pub use packet::PacketType;
 pub use parser::{
     parse_battery, parse_channel, parse_channel_message, parse_contact, parse_contact_message,
-    parse_core_stats, parse_device_info, parse_device_status, parse_packet_stats,
-    parse_radio_stats, parse_self_info,
+    parse_core_stats, parse_device_info, parse_device_status, parse_discover_response,
+    parse_login_success, parse_packet_stats, parse_radio_stats, parse_rx_log, parse_self_info,
+    parse_trace_data,
 };
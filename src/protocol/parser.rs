@@ -5,9 +5,12 @@
 use bytes::Buf;
 
 use crate::error::{Error, Result};
+use crate::protocol::ControlDataType;
 use crate::types::{
     BatteryStatus, Channel, Contact, ContactFlags, ContactMessage, ContactType, DeviceInfo,
-    DeviceStatus, PublicKey, RadioConfig, SelfInfo, SignalQuality, TelemetryMode, TextType,
+    DeviceStatus, DiscoverResponse, DiscoveredKey, LoginResponse, PayloadType, PublicKey,
+    RadioConfig, RouteType, RxLogEntry, SelfInfo, SignalQuality, TelemetryMode, TextType,
+    TraceResult,
 };
 
 /// Coordinate scaling factor (multiply by 1e6 for storage).
@@ -533,6 +536,177 @@ pub fn parse_packet_stats(data: &[u8]) -> Result<crate::types::PacketStats> {
     })
 }
 
+/// Parses a node discovery response from control data.
+///
+/// Format:
+/// ```text
+/// [snr:1Signed/4] [rssi:1Signed] [path_len:1]
+/// [ctrl_type:1 (0x90 | node_type)] [snr_in:1Signed/4] [tag:4LE] [pubkey:8 or 32]
+/// ```
+pub fn parse_discover_response(data: &[u8]) -> Result<DiscoverResponse> {
+    // Minimum size: 3 bytes header + 1 + 1 + 4 = 9 bytes, plus at least 1 key byte
+    if data.len() < 10 {
+        return Err(Error::Protocol {
+            message: format!("DiscoverResponse too short: {} bytes", data.len()),
+        });
+    }
+
+    let mut cursor = std::io::Cursor::new(data);
+
+    let snr = f32::from(cursor.get_i8()) / SNR_SCALE;
+    let rssi = cursor.get_i8();
+    let path_len = cursor.get_u8();
+
+    let ctrl_type = cursor.get_u8();
+    if ControlDataType::from_byte(ctrl_type) != Some(ControlDataType::NodeDiscoverResp) {
+        return Err(Error::Protocol {
+            message: format!("not a discover response: control type 0x{ctrl_type:02x}"),
+        });
+    }
+    let node_type = ContactType::from_byte(ctrl_type & 0x0F);
+
+    let snr_in = f32::from(cursor.get_i8()) / SNR_SCALE;
+    let tag = cursor.get_u32_le();
+
+    let key_bytes = &data[cursor.position() as usize..];
+    let key = PublicKey::try_from_bytes(key_bytes).map_or_else(
+        || DiscoveredKey::Prefix(bytes::Bytes::copy_from_slice(key_bytes)),
+        DiscoveredKey::Full,
+    );
+
+    Ok(DiscoverResponse {
+        node_type,
+        snr,
+        rssi,
+        snr_in,
+        path_len,
+        tag,
+        key,
+    })
+}
+
+/// Parses an RX log entry (push notification).
+///
+/// Format:
+/// ```text
+/// [snr:1Signed] [rssi:1Signed] [header:1] [transport_codes:4 (transport routes only)]
+/// [path_len:1] [path:path_len] [payload...]
+/// ```
+pub fn parse_rx_log(data: &[u8]) -> Result<RxLogEntry> {
+    let too_short = || Error::Protocol {
+        message: format!("RX log entry too short: {} bytes", data.len()),
+    };
+    if data.len() < 4 {
+        return Err(too_short());
+    }
+
+    let mut cursor = std::io::Cursor::new(data);
+    let snr = f32::from(cursor.get_i8()) / SNR_SCALE;
+    let rssi = cursor.get_i8();
+    let header = cursor.get_u8();
+    let route_type = RouteType::from_header(header);
+    if route_type.has_transport_codes() {
+        if cursor.remaining() < 5 {
+            return Err(too_short());
+        }
+        cursor.advance(4);
+    }
+
+    let path_len = usize::from(cursor.get_u8());
+    if cursor.remaining() < path_len {
+        return Err(too_short());
+    }
+    let start = cursor.position() as usize;
+
+    Ok(RxLogEntry {
+        snr,
+        rssi,
+        route_type,
+        payload_type: PayloadType::from_header(header),
+        payload_version: header >> 6,
+        path: data[start..start + path_len].to_vec(),
+        payload: data[start + path_len..].to_vec(),
+    })
+}
+
+/// Parses trace data (push notification).
+///
+/// Format:
+/// ```text
+/// [reserved:1] [path_len:1] [flags:1] [tag:4LE] [auth_code:4LE]
+/// [path:path_len] [snrs:path_len] [final_snr:1]
+/// ```
+pub fn parse_trace_data(data: &[u8]) -> Result<TraceResult> {
+    if data.len() < 11 {
+        return Err(Error::Protocol {
+            message: format!("TraceData too short: {} bytes", data.len()),
+        });
+    }
+
+    let mut cursor = std::io::Cursor::new(data);
+    cursor.advance(1); // Reserved
+    let path_len = usize::from(cursor.get_u8());
+    let flags = cursor.get_u8();
+    let tag = cursor.get_u32_le();
+    let auth_code = cursor.get_u32_le();
+
+    let rest = &data[11..];
+    if rest.len() < 2 * path_len + 1 {
+        return Err(Error::Protocol {
+            message: format!(
+                "TraceData truncated: {} hops, {} bytes",
+                path_len,
+                rest.len()
+            ),
+        });
+    }
+
+    let snrs = rest[path_len..=2 * path_len]
+        .iter()
+        .map(|&b| f32::from(i8::from_le_bytes([b])) / SNR_SCALE)
+        .collect();
+
+    Ok(TraceResult {
+        tag,
+        auth_code,
+        flags,
+        path: rest[..path_len].to_vec(),
+        snrs,
+    })
+}
+
+/// Parses a login success notification.
+///
+/// Format:
+/// ```text
+/// [permissions:1] [pubkey_prefix:6]
+/// (newer firmware: [server_time:4LE] [acl_permissions:1] [firmware_level:1])
+/// ```
+pub fn parse_login_success(data: &[u8]) -> Result<LoginResponse> {
+    if data.len() < 7 {
+        return Err(Error::Protocol {
+            message: format!("LoginSuccess too short: {} bytes", data.len()),
+        });
+    }
+
+    let mut cursor = std::io::Cursor::new(data);
+    let permissions = cursor.get_u8();
+    let mut pubkey_prefix = [0u8; 6];
+    cursor.copy_to_slice(&mut pubkey_prefix);
+
+    let server_time = (cursor.remaining() >= 4).then(|| cursor.get_u32_le());
+    let acl_permissions = cursor.has_remaining().then(|| cursor.get_u8());
+    let firmware_level = cursor.has_remaining().then(|| cursor.get_u8());
+
+    Ok(LoginResponse {
+        pubkey_prefix,
+        permissions,
+        server_time,
+        acl_permissions,
+        firmware_level,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -654,4 +828,103 @@ mod tests {
         assert_eq!(stats.flood_rx, 40);
         assert_eq!(stats.direct_rx, 60);
     }
+
+    #[test]
+    fn test_parse_discover_response() {
+        let mut data = vec![
+            40,   // snr * 4 = 10.0
+            0xB0, // rssi = -80
+            0,    // path_len
+            0x92, // discover response from a repeater
+            0xF8, // snr_in * 4 = -2.0
+        ];
+        data.extend_from_slice(&0x1234_5678_u32.to_le_bytes()); // tag
+        data.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]); // pubkey prefix
+
+        let resp = parse_discover_response(&data).unwrap();
+        assert!((resp.snr - 10.0).abs() < 0.01);
+        assert_eq!(resp.rssi, -80);
+        assert!((resp.snr_in - (-2.0)).abs() < 0.01);
+        assert_eq!(resp.node_type, ContactType::Repeater);
+        assert_eq!(resp.tag, 0x1234_5678);
+        assert_eq!(resp.key.as_bytes(), &[1, 2, 3, 4, 5, 6, 7, 8]);
+
+        // Full public key
+        data.truncate(9);
+        data.extend_from_slice(&[0xAB; 32]);
+        let resp = parse_discover_response(&data).unwrap();
+        assert_eq!(
+            resp.key,
+            DiscoveredKey::Full(PublicKey::from_bytes(&[0xAB; 32]))
+        );
+
+        // Other control data is rejected
+        data[3] = 0x80;
+        assert!(parse_discover_response(&data).is_err());
+    }
+
+    #[test]
+    fn test_parse_trace_data() {
+        let mut data = vec![0, 2, 0];
+        data.extend_from_slice(&7u32.to_le_bytes()); // tag
+        data.extend_from_slice(&0u32.to_le_bytes()); // auth code
+        data.extend_from_slice(&[0xAA, 0xBB]); // path
+        data.extend_from_slice(&[40, 0xF8, 20]); // snrs
+
+        let trace = parse_trace_data(&data).unwrap();
+        assert_eq!(trace.tag, 7);
+        assert_eq!(trace.path, vec![0xAA, 0xBB]);
+        assert_eq!(trace.snrs, vec![10.0, -2.0, 5.0]);
+        assert_eq!(trace.final_snr(), Some(5.0));
+
+        data.pop();
+        assert!(parse_trace_data(&data).is_err());
+    }
+
+    #[test]
+    fn test_parse_rx_log() {
+        // Flood advert via two repeaters
+        let data = [40, 0xB0, 0x11, 2, 0xAA, 0xBB, 1, 2, 3];
+        let entry = parse_rx_log(&data).unwrap();
+        assert!((entry.snr - 10.0).abs() < 0.01);
+        assert_eq!(entry.rssi, -80);
+        assert_eq!(entry.route_type, RouteType::Flood);
+        assert_eq!(entry.payload_type, PayloadType::Advert);
+        assert_eq!(entry.path, vec![0xAA, 0xBB]);
+        assert_eq!(entry.payload, vec![1, 2, 3]);
+
+        // Transport codes are skipped
+        let data = [0, 0, 0x07, 1, 2, 3, 4, 0, 9];
+        let entry = parse_rx_log(&data).unwrap();
+        assert_eq!(entry.route_type, RouteType::TransportDirect);
+        assert_eq!(entry.payload_type, PayloadType::Response);
+        assert!(entry.path.is_empty());
+        assert_eq!(entry.payload, vec![9]);
+
+        // Zero-hop discovery request
+        let entry = parse_rx_log(&[0, 0, 0x2E, 0, 0x80]).unwrap();
+        assert_eq!(entry.payload_type, PayloadType::Control);
+
+        // Path longer than the packet
+        assert!(parse_rx_log(&[0, 0, 0x11, 5, 1]).is_err());
+    }
+
+    #[test]
+    fn test_parse_login_success() {
+        // Legacy firmware: permissions and prefix only
+        let mut data = vec![1, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF];
+        let login = parse_login_success(&data).unwrap();
+        assert_eq!(login.pubkey_prefix, [0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF]);
+        assert!(login.is_admin());
+        assert_eq!(login.server_time, None);
+
+        data.extend_from_slice(&1_700_000_000u32.to_le_bytes());
+        data.extend_from_slice(&[3, 8]);
+        let login = parse_login_success(&data).unwrap();
+        assert_eq!(login.server_time, Some(1_700_000_000));
+        assert_eq!(login.acl_permissions, Some(3));
+        assert_eq!(login.firmware_level, Some(8));
+
+        assert!(parse_login_success(&data[..6]).is_err());
+    }
 }
@@ -0,0 +1,432 @@
+//! Multi-client proxy sharing one radio.
+//!
+//! Only one process can open a serial port. The [`Proxy`] owns the radio
+//! transport and exposes the same framed companion protocol to any number
+//! of clients (typically over TCP), so that a bot, a monitoring agent and
+//! a CLI can share one device:
+//!
+//! ```no_run
+//! use meshcore::SerialTransport;
+//! use meshcore::proxy::Proxy;
+//! use tokio::net::TcpListener;
+//!
+//! # async fn example() -> meshcore::Result<()> {
+//! let proxy = Proxy::new(SerialTransport::with_port("/dev/ttyUSB0")).start().await?;
+//! proxy.serve(TcpListener::bind("127.0.0.1:5000").await?).await
+//! # }
+//! ```
+//!
+//! Clients connect with [`StreamTransport`](crate::transport::StreamTransport)
+//! (e.g. `MeshCore::stream(TcpStream::connect(...).await?)`).
+//!
+//! # Routing
+//!
+//! Commands from all clients are sent to the radio one at a time. Every
+//! response frame is routed to the client that sent the pending command;
+//! a contact list stays with that client until [`PacketType::ContactEnd`].
+//! Push frames ([`PacketType::is_push`]) are sent to all clients. If the
+//! radio does not respond within the command timeout, the next command is
+//! sent and late responses go to the client that sent the last command.
+//!
+//! Multi-command sequences (such as signing) are not reserved for one
+//! client, so clients should avoid running them concurrently.
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::{SinkExt, StreamExt};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio::sync::{Mutex, mpsc};
+use tokio::time::Instant;
+use tokio_util::codec::Framed;
+
+use crate::error::{Error, Result};
+use crate::protocol::{FrameCodec, PacketType};
+use crate::transport::Transport;
+
+/// Default time to wait for the radio to respond to a command.
+pub const DEFAULT_COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Number of frames buffered per client before it is considered stalled.
+const CLIENT_BUFFER: usize = 256;
+
+/// Identifier of a proxy client.
+pub type ClientId = u64;
+
+/// Returns true if a received frame is a push notification.
+fn is_push_frame(frame: &[u8]) -> bool {
+    frame.first().is_some_and(|&byte| {
+        PacketType::from_byte(byte).map_or(byte >= 0x80, |packet_type| packet_type.is_push())
+    })
+}
+
+/// Returns true if a response frame completes the pending command.
+fn is_final_response(frame: &[u8]) -> bool {
+    !matches!(
+        frame.first().and_then(|&b| PacketType::from_byte(b)),
+        Some(PacketType::ContactStart | PacketType::Contact)
+    )
+}
+
+/// Proxy that shares one radio transport between multiple clients.
+pub struct Proxy<T> {
+    transport: T,
+    command_timeout: Duration,
+}
+
+impl<T: Transport + 'static> Proxy<T> {
+    /// Creates a proxy for the given radio transport.
+    #[must_use]
+    pub const fn new(transport: T) -> Self {
+        Self {
+            transport,
+            command_timeout: DEFAULT_COMMAND_TIMEOUT,
+        }
+    }
+
+    /// Sets the time to wait for the radio to respond to a command.
+    #[must_use]
+    pub const fn command_timeout(mut self, timeout: Duration) -> Self {
+        self.command_timeout = timeout;
+        self
+    }
+
+    /// Connects to the radio and starts routing frames.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the transport cannot be connected or does not
+    /// provide a frame stream.
+    pub async fn start(mut self) -> Result<ProxyHandle> {
+        self.transport.connect().await?;
+        let frames = self.transport.take_frame_stream().ok_or(Error::Protocol {
+            message: "transport does not provide a frame stream".into(),
+        })?;
+
+        let (command_tx, command_rx) = mpsc::channel(CLIENT_BUFFER);
+        let clients = Arc::new(Mutex::new(HashMap::new()));
+
+        let router = Router {
+            transport: self.transport,
+            command_timeout: self.command_timeout,
+            clients: Arc::clone(&clients),
+            owner: None,
+            pending_until: None,
+        };
+        tokio::spawn(router.run(frames, command_rx));
+
+        Ok(ProxyHandle {
+            inner: Arc::new(HandleInner {
+                command_tx,
+                clients,
+                next_id: AtomicU64::new(1),
+            }),
+        })
+    }
+}
+
+type Clients = Arc<Mutex<HashMap<ClientId, mpsc::Sender<Bytes>>>>;
+
+/// Routes frames between the radio and the clients.
+struct Router<T> {
+    transport: T,
+    command_timeout: Duration,
+    clients: Clients,
+    owner: Option<ClientId>,
+    pending_until: Option<Instant>,
+}
+
+impl<T: Transport> Router<T> {
+    async fn run(
+        mut self,
+        mut frames: crate::transport::FrameStream,
+        mut command_rx: mpsc::Receiver<(ClientId, Bytes)>,
+    ) {
+        loop {
+            let pending_until = self.pending_until;
+            tokio::select! {
+                frame = frames.next() => match frame {
+                    Some(Ok(frame)) => self.route(frame).await,
+                    Some(Err(e)) => {
+                        tracing::error!("proxy: radio read error: {}", e);
+                        break;
+                    }
+                    None => {
+                        tracing::info!("proxy: radio closed");
+                        break;
+                    }
+                },
+                command = command_rx.recv(), if pending_until.is_none() => {
+                    let Some((client, data)) = command else { break };
+                    if let Err(e) = self.transport.send(data).await {
+                        tracing::error!("proxy: radio write error: {}", e);
+                        break;
+                    }
+                    self.owner = Some(client);
+                    self.pending_until = Some(Instant::now() + self.command_timeout);
+                }
+                () = async {
+                    if let Some(deadline) = pending_until {
+                        tokio::time::sleep_until(deadline).await;
+                    }
+                }, if pending_until.is_some() => {
+                    tracing::warn!("proxy: no response from radio for client {:?}", self.owner);
+                    self.pending_until = None;
+                }
+            }
+        }
+
+        // Dropping all client senders closes the client connections
+        self.clients.lock().await.clear();
+        let _ = self.transport.disconnect().await;
+    }
+
+    async fn route(&mut self, frame: Bytes) {
+        if is_push_frame(&frame) {
+            let clients = self.clients.lock().await;
+            for (id, tx) in clients.iter() {
+                if tx.try_send(frame.clone()).is_err() {
+                    tracing::warn!("proxy: dropping push frame for stalled client {}", id);
+                }
+            }
+            return;
+        }
+
+        if is_final_response(&frame) {
+            self.pending_until = None;
+        }
+        let Some(owner) = self.owner else {
+            tracing::debug!("proxy: dropping unsolicited response");
+            return;
+        };
+        // A stalled client must not block the radio, so responses are not awaited
+        let clients = self.clients.lock().await;
+        if let Some(tx) = clients.get(&owner) {
+            if tx.try_send(frame).is_err() {
+                tracing::warn!("proxy: dropping response for stalled client {}", owner);
+            }
+        } else {
+            tracing::debug!("proxy: dropping response for closed client {}", owner);
+        }
+    }
+}
+
+struct HandleInner {
+    command_tx: mpsc::Sender<(ClientId, Bytes)>,
+    clients: Clients,
+    next_id: AtomicU64,
+}
+
+/// Handle to a running [`Proxy`].
+///
+/// Cloning the handle is cheap; all clones refer to the same proxy.
+#[derive(Clone)]
+pub struct ProxyHandle {
+    inner: Arc<HandleInner>,
+}
+
+impl ProxyHandle {
+    /// Adds a client connected over an async byte stream.
+    ///
+    /// The client speaks the companion protocol as if the proxy were the
+    /// device. The client is removed when the stream closes.
+    pub async fn add_client<S>(&self, stream: S) -> ClientId
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        let (frame_tx, mut frame_rx) = mpsc::channel::<Bytes>(CLIENT_BUFFER);
+        self.inner.clients.lock().await.insert(id, frame_tx);
+        tracing::info!("proxy: client {} connected", id);
+
+        let (mut sink, mut stream) = Framed::new(stream, FrameCodec::device()).split();
+        let command_tx = self.inner.command_tx.clone();
+        let clients = Arc::clone(&self.inner.clients);
+
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    command = stream.next() => match command {
+                        Some(Ok(data)) => {
+                            if command_tx.send((id, data)).await.is_err() {
+                                break;
+                            }
+                        }
+                        Some(Err(e)) => {
+                            tracing::debug!("proxy: client {} read error: {}", id, e);
+                            break;
+                        }
+                        None => break,
+                    },
+                    frame = frame_rx.recv() => match frame {
+                        Some(frame) => {
+                            if sink.send(frame).await.is_err() {
+                                break;
+                            }
+                        }
+                        None => break,
+                    },
+                }
+            }
+            clients.lock().await.remove(&id);
+            tracing::info!("proxy: client {} disconnected", id);
+        });
+
+        id
+    }
+
+    /// Returns the number of connected clients.
+    pub async fn client_count(&self) -> usize {
+        self.inner.clients.lock().await.len()
+    }
+
+    /// Returns true if the radio connection has closed.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.inner.command_tx.is_closed()
+    }
+
+    /// Accepts TCP clients until the radio connection closes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if accepting fails or the radio connection closes.
+    pub async fn serve(&self, listener: TcpListener) -> Result<()> {
+        tracing::info!("proxy: listening on {}", listener.local_addr()?);
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => {
+                    let (stream, addr) = accepted?;
+                    // Frames are small and latency-sensitive
+                    stream.set_nodelay(true)?;
+                    let id = self.add_client(stream).await;
+                    tracing::debug!("proxy: client {} is {}", id, addr);
+                }
+                () = self.inner.command_tx.closed() => {
+                    return Err(Error::Io(io::Error::new(
+                        io::ErrorKind::ConnectionReset,
+                        "radio connection closed",
+                    )));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::DuplexStream;
+
+    use super::*;
+    use crate::protocol::CommandOpcode;
+    use crate::transport::StreamTransport;
+
+    type Client = Framed<DuplexStream, FrameCodec>;
+
+    /// Starts a proxy in front of a fake radio that answers `GetBattery`
+    /// and `GetContacts`, and forwards frames from `push_rx` as pushes.
+    async fn start_proxy(mut push_rx: mpsc::Receiver<Bytes>) -> ProxyHandle {
+        let (host, radio) = tokio::io::duplex(1024);
+        let mut radio = Framed::new(radio, FrameCodec::device());
+
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    Some(push) = push_rx.recv() => radio.send(push).await.unwrap(),
+                    Some(Ok(request)) = radio.next() => {
+                        match CommandOpcode::from_byte(request[0]) {
+                            Some(CommandOpcode::GetBattery) => {
+                                radio.send(Bytes::from_static(&[0x0C, 0xD4, 0x0D])).await.unwrap();
+                            }
+                            Some(CommandOpcode::GetContacts) => {
+                                for frame in [&[0x02, 1, 0, 0, 0][..], &[0x03, 0xAA], &[0x04, 0, 0, 0, 0]] {
+                                    tokio::time::sleep(Duration::from_millis(10)).await;
+                                    radio.send(Bytes::copy_from_slice(frame)).await.unwrap();
+                                }
+                            }
+                            _ => radio.send(Bytes::from_static(&[0x00])).await.unwrap(),
+                        }
+                    }
+                    else => break,
+                }
+            }
+        });
+
+        Proxy::new(StreamTransport::new(host))
+            .start()
+            .await
+            .unwrap()
+    }
+
+    async fn connect(proxy: &ProxyHandle) -> Client {
+        let (client, server) = tokio::io::duplex(1024);
+        proxy.add_client(server).await;
+        Framed::new(client, FrameCodec::new())
+    }
+
+    async fn recv(client: &mut Client) -> Bytes {
+        tokio::time::timeout(Duration::from_secs(1), client.next())
+            .await
+            .expect("timed out")
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_proxy_routes_responses_to_sender() {
+        let (_push_tx, push_rx) = mpsc::channel(1);
+        let proxy = start_proxy(push_rx).await;
+        let mut a = connect(&proxy).await;
+        let mut b = connect(&proxy).await;
+        assert_eq!(proxy.client_count().await, 2);
+
+        a.send(Bytes::from_static(&[CommandOpcode::GetContacts as u8]))
+            .await
+            .unwrap();
+        b.send(Bytes::from_static(&[CommandOpcode::GetBattery as u8]))
+            .await
+            .unwrap();
+
+        // The contact list is not interleaved with b's command
+        assert_eq!(recv(&mut a).await[0], 0x02);
+        assert_eq!(recv(&mut a).await[0], 0x03);
+        assert_eq!(recv(&mut a).await[0], 0x04);
+        assert_eq!(&recv(&mut b).await[..], &[0x0C, 0xD4, 0x0D]);
+
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), a.next())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_proxy_fans_out_pushes() {
+        let (push_tx, push_rx) = mpsc::channel(1);
+        let proxy = start_proxy(push_rx).await;
+        let mut a = connect(&proxy).await;
+        let mut b = connect(&proxy).await;
+
+        push_tx.send(Bytes::from_static(&[0x83])).await.unwrap();
+        assert_eq!(&recv(&mut a).await[..], &[0x83]);
+        assert_eq!(&recv(&mut b).await[..], &[0x83]);
+
+        drop(a);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(proxy.client_count().await, 1);
+    }
+
+    #[test]
+    fn test_frame_classification() {
+        assert!(is_push_frame(&[0x80, 0x00]));
+        assert!(is_push_frame(&[0x9F]));
+        assert!(!is_push_frame(&[0x0C]));
+        assert!(!is_final_response(&[0x03]));
+        assert!(is_final_response(&[0x04]));
+    }
+}
@@ -0,0 +1,389 @@
+//! Repeater remote administration.
+//!
+//! Repeaters are configured through text CLI commands sent as command
+//! messages; the reply comes back later as a command message from the
+//! repeater. A [`RepeaterAdmin`] logs in with the admin password, sends
+//! commands through [`MeshCore::remote_command`], which matches each reply
+//! to its request, and parses the replies of common commands:
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use meshcore::MeshCore;
+//! use meshcore::repeater::RepeaterAdmin;
+//!
+//! # async fn example() -> meshcore::Result<()> {
+//! let mut client = MeshCore::serial("/dev/ttyUSB0");
+//! client.connect().await?;
+//! client.get_contacts().await?;
+//! let repeater = client.find_contact("hilltop").await.unwrap().public_key;
+//!
+//! let admin = RepeaterAdmin::login(Arc::new(client), repeater, "secret").await?;
+//! println!("{} runs {}", admin.name().await?, admin.version().await?);
+//! admin.set_tx_power(20).await?;
+//! admin.sync_clock().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::client::MeshCore;
+use crate::error::{Error, Result};
+use crate::transport::Transport;
+use crate::types::{PublicKey, RadioConfig};
+
+/// A neighbouring node heard directly by a repeater.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Neighbour {
+    /// Public key prefix of the neighbour (4 bytes).
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::types::serde_hex::serialize")
+    )]
+    pub prefix: Vec<u8>,
+    /// Seconds since the neighbour was last heard.
+    pub heard_secs_ago: u32,
+    /// SNR of the last packet from the neighbour, in dB.
+    pub snr: f32,
+}
+
+/// Returns an error for an unparseable reply.
+fn invalid_reply(command: &str, reply: &str) -> Error {
+    Error::Protocol {
+        message: format!("unexpected reply to {command:?}: {reply:?}"),
+    }
+}
+
+/// Returns the value of a `get` reply (`"> value"`).
+fn reply_value<'a>(command: &str, reply: &'a str) -> Result<&'a str> {
+    reply
+        .trim()
+        .strip_prefix('>')
+        .map(str::trim)
+        .ok_or_else(|| invalid_reply(command, reply))
+}
+
+/// Checks the reply of a command that answers `OK` on success.
+fn expect_ok(command: &str, reply: &str) -> Result<()> {
+    if reply.trim_start().starts_with("OK") {
+        Ok(())
+    } else {
+        Err(Error::RemoteCommand {
+            command: command.to_string(),
+            reply: reply.trim().to_string(),
+        })
+    }
+}
+
+/// Parses a `get radio` value (`freq,bw,sf,cr`).
+fn parse_radio(command: &str, reply: &str) -> Result<RadioConfig> {
+    let value = reply_value(command, reply)?;
+    let parts: Vec<&str> = value.split(',').map(str::trim).collect();
+    let [freq, bw, sf, cr] = parts.as_slice() else {
+        return Err(invalid_reply(command, reply));
+    };
+    Ok(RadioConfig {
+        frequency_mhz: freq.parse().map_err(|_| invalid_reply(command, reply))?,
+        bandwidth_khz: bw.parse().map_err(|_| invalid_reply(command, reply))?,
+        spreading_factor: sf.parse().map_err(|_| invalid_reply(command, reply))?,
+        coding_rate: cr.parse().map_err(|_| invalid_reply(command, reply))?,
+    })
+}
+
+/// Parses a `neighbors` reply: one `<prefix hex>:<secs ago>:<snr x4>` line
+/// per neighbour, or `-none-`.
+fn parse_neighbours(command: &str, reply: &str) -> Result<Vec<Neighbour>> {
+    let reply = reply.trim();
+    if reply == "-none-" {
+        return Ok(Vec::new());
+    }
+    reply
+        .lines()
+        .map(|line| {
+            let mut fields = line.trim().split(':');
+            let (Some(prefix), Some(secs), Some(snr), None) =
+                (fields.next(), fields.next(), fields.next(), fields.next())
+            else {
+                return Err(invalid_reply(command, line));
+            };
+            let snr: i16 = snr.parse().map_err(|_| invalid_reply(command, line))?;
+            Ok(Neighbour {
+                prefix: hex::decode(prefix).map_err(|_| invalid_reply(command, line))?,
+                heard_secs_ago: secs.parse().map_err(|_| invalid_reply(command, line))?,
+                snr: f32::from(snr) / 4.0,
+            })
+        })
+        .collect()
+}
+
+/// Administration handle for a repeater.
+pub struct RepeaterAdmin<T> {
+    client: Arc<MeshCore<T>>,
+    repeater: PublicKey,
+}
+
+impl<T: Transport + 'static> RepeaterAdmin<T> {
+    /// Logs in to a repeater with its admin password.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::LoginFailed`] if the password is rejected or only
+    /// grants guest access.
+    pub async fn login(
+        client: Arc<MeshCore<T>>,
+        repeater: PublicKey,
+        password: &str,
+    ) -> Result<Self> {
+        let login = client.login(&repeater, password).await?;
+        if !login.is_admin() {
+            return Err(Error::LoginFailed {
+                destination: repeater.to_hex(),
+            });
+        }
+        tracing::info!("logged in to repeater {} as admin", repeater);
+        Ok(Self { client, repeater })
+    }
+
+    /// Returns the repeater's public key.
+    #[must_use]
+    pub const fn repeater(&self) -> &PublicKey {
+        &self.repeater
+    }
+
+    /// Sends a CLI command and returns the repeater's reply.
+    ///
+    /// See [`MeshCore::remote_command`].
+    pub async fn command(&self, command: &str) -> Result<String> {
+        self.client.remote_command(&self.repeater, command).await
+    }
+
+    /// Sends a `get` command and returns the value.
+    async fn get(&self, key: &str) -> Result<String> {
+        let command = format!("get {key}");
+        let reply = self.command(&command).await?;
+        reply_value(&command, &reply).map(str::to_string)
+    }
+
+    /// Sends a `get` command and parses the value.
+    async fn get_parsed<V: std::str::FromStr>(&self, key: &str) -> Result<V> {
+        let value = self.get(key).await?;
+        value
+            .parse()
+            .map_err(|_| invalid_reply(&format!("get {key}"), &value))
+    }
+
+    /// Sends a `set` command.
+    async fn set(&self, key: &str, value: impl std::fmt::Display) -> Result<()> {
+        let command = format!("set {key} {value}");
+        let reply = self.command(&command).await?;
+        expect_ok(&command, &reply)
+    }
+
+    /// Gets the repeater's name.
+    pub async fn name(&self) -> Result<String> {
+        self.get("name").await
+    }
+
+    /// Sets the repeater's name.
+    pub async fn set_name(&self, name: &str) -> Result<()> {
+        self.set("name", name).await
+    }
+
+    /// Gets the radio parameters.
+    pub async fn radio(&self) -> Result<RadioConfig> {
+        let reply = self.command("get radio").await?;
+        parse_radio("get radio", &reply)
+    }
+
+    /// Sets the radio parameters.
+    ///
+    /// The repeater applies them after a reboot.
+    pub async fn set_radio(&self, radio: &RadioConfig) -> Result<()> {
+        let value = format!(
+            "{},{},{},{}",
+            radio.frequency_mhz, radio.bandwidth_khz, radio.spreading_factor, radio.coding_rate
+        );
+        self.set("radio", value).await
+    }
+
+    /// Gets the TX power in dBm.
+    pub async fn tx_power(&self) -> Result<i8> {
+        self.get_parsed("tx").await
+    }
+
+    /// Sets the TX power in dBm.
+    pub async fn set_tx_power(&self, dbm: i8) -> Result<()> {
+        self.set("tx", dbm).await
+    }
+
+    /// Gets the interval between local (zero-hop) adverts.
+    pub async fn advert_interval(&self) -> Result<Duration> {
+        let minutes: u64 = self.get_parsed("advert.interval").await?;
+        Ok(Duration::from_secs(minutes * 60))
+    }
+
+    /// Sets the interval between local adverts (whole minutes; zero
+    /// disables them).
+    pub async fn set_advert_interval(&self, interval: Duration) -> Result<()> {
+        self.set("advert.interval", interval.as_secs() / 60).await
+    }
+
+    /// Gets the maximum hop count of flood packets the repeater forwards.
+    pub async fn flood_max(&self) -> Result<u8> {
+        self.get_parsed("flood.max").await
+    }
+
+    /// Sets the maximum hop count of flood packets the repeater forwards.
+    pub async fn set_flood_max(&self, hops: u8) -> Result<()> {
+        self.set("flood.max", hops).await
+    }
+
+    /// Sets the repeater clock to the timestamp of the command.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::RemoteCommand`] if the repeater refuses, e.g.
+    /// because its clock is ahead.
+    pub async fn sync_clock(&self) -> Result<()> {
+        let reply = self.command("clock sync").await?;
+        expect_ok("clock sync", &reply)
+    }
+
+    /// Gets the firmware version.
+    pub async fn version(&self) -> Result<String> {
+        Ok(self.command("ver").await?.trim().to_string())
+    }
+
+    /// Lists the nodes the repeater hears directly.
+    pub async fn neighbours(&self) -> Result<Vec<Neighbour>> {
+        let reply = self.command("neighbors").await?;
+        parse_neighbours("neighbors", &reply)
+    }
+
+    /// Reboots the repeater.
+    ///
+    /// The repeater does not reply, so this returns once the command is
+    /// sent.
+    pub async fn reboot(&self) -> Result<()> {
+        self.client
+            .commands()
+            .send_command(
+                &self.repeater,
+                "reboot",
+                self.client.next_command_timestamp(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{CommandOpcode, PacketType};
+    use crate::testing;
+    use crate::types::TextType;
+
+    #[test]
+    fn test_parse_replies() {
+        assert_eq!(reply_value("get name", "> hilltop").unwrap(), "hilltop");
+        assert!(reply_value("get name", "??").is_err());
+
+        assert!(expect_ok("set tx 20", "OK").is_ok());
+        let err = expect_ok("set tx 99", "Error: max 22").unwrap_err();
+        assert!(matches!(err, Error::RemoteCommand { ref reply, .. } if reply == "Error: max 22"));
+
+        let radio = parse_radio("get radio", "> 869.525,250.0,11,5").unwrap();
+        assert!((radio.frequency_mhz - 869.525).abs() < 1e-9);
+        assert!((radio.bandwidth_khz - 250.0).abs() < 1e-9);
+        assert_eq!((radio.spreading_factor, radio.coding_rate), (11, 5));
+        assert!(parse_radio("get radio", "> 869.525,250.0").is_err());
+    }
+
+    #[test]
+    fn test_parse_neighbours() {
+        let neighbours = parse_neighbours("neighbors", "A1B2C3D4:30:24\nDEADBEEF:120:-8").unwrap();
+        assert_eq!(neighbours.len(), 2);
+        assert_eq!(neighbours[0].prefix, vec![0xA1, 0xB2, 0xC3, 0xD4]);
+        assert_eq!(neighbours[0].heard_secs_ago, 30);
+        assert!((neighbours[0].snr - 6.0).abs() < f32::EPSILON);
+        assert!((neighbours[1].snr + 2.0).abs() < f32::EPSILON);
+
+        assert!(parse_neighbours("neighbors", "-none-").unwrap().is_empty());
+        assert!(parse_neighbours("neighbors", "A1B2:x:1").is_err());
+    }
+
+    /// Reply of the fake repeater to a CLI command.
+    fn cli_reply(command: &str) -> &'static str {
+        match command {
+            "get name" => "> hilltop",
+            "get tx" => "> 22",
+            "set tx 20" => "OK",
+            "get advert.interval" => "> 120",
+            "clock sync" => "ERR: clock cannot go backwards",
+            _ => "??",
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repeater_admin() {
+        const REPEATER: u8 = 0x3E;
+        let mut replies = std::collections::VecDeque::new();
+        let (client, _push) = testing::connect(testing::self_info(0x01, ""), move |request| {
+            match CommandOpcode::from_byte(request[0]) {
+                Some(CommandOpcode::SendLogin) => {
+                    let mut login = vec![PacketType::LoginSuccess as u8, 1];
+                    login.extend_from_slice(&[REPEATER; 6]);
+                    vec![testing::msg_sent(1, 1000), login]
+                }
+                Some(CommandOpcode::SendMessage) => {
+                    let command = String::from_utf8_lossy(&request[13..]).into_owned();
+                    let mut reply = vec![PacketType::ContactMsgRecv as u8];
+                    reply.extend_from_slice(&[REPEATER; 6]);
+                    reply.extend_from_slice(&[0, TextType::Command as u8, 0, 0, 0, 0]);
+                    reply.extend_from_slice(cli_reply(&command).as_bytes());
+                    replies.push_back(reply);
+                    vec![
+                        testing::msg_sent(2, 1000),
+                        vec![PacketType::MessagesWaiting as u8],
+                    ]
+                }
+                Some(CommandOpcode::GetMessage) => vec![
+                    replies
+                        .pop_front()
+                        .unwrap_or_else(|| vec![PacketType::NoMoreMsgs as u8]),
+                ],
+                _ => vec![vec![PacketType::Ok as u8]],
+            }
+        })
+        .await;
+
+        let admin = Arc::new(
+            RepeaterAdmin::login(
+                Arc::new(client),
+                PublicKey::from_bytes(&[REPEATER; 32]),
+                "secret",
+            )
+            .await
+            .unwrap(),
+        );
+
+        // Concurrent commands each get their own reply
+        let (name, tx) = tokio::join!(admin.name(), admin.tx_power());
+        assert_eq!(name.unwrap(), "hilltop");
+        assert_eq!(tx.unwrap(), 22);
+
+        admin.set_tx_power(20).await.unwrap();
+        assert_eq!(
+            admin.advert_interval().await.unwrap(),
+            Duration::from_secs(120 * 60)
+        );
+        assert!(matches!(
+            admin.sync_clock().await,
+            Err(Error::RemoteCommand { .. })
+        ));
+    }
+}
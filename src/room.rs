@@ -0,0 +1,476 @@
+//! Room server sessions.
+//!
+//! A room server only forwards posts to clients that are logged in, and it
+//! forgets a client that stops sending keep-alives or when it restarts. A
+//! [`RoomSession`] logs in, keeps the session alive, logs in again when
+//! keep-alives go unacknowledged and delivers the room's posts with their
+//! original author resolved:
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use meshcore::MeshCore;
+//! use meshcore::room::{RoomConfig, RoomSession};
+//!
+//! # async fn example() -> meshcore::Result<()> {
+//! let mut client = MeshCore::serial("/dev/ttyUSB0");
+//! client.connect().await?;
+//! client.get_contacts().await?;
+//! let room = client.find_contact("bulletin").await.unwrap().public_key;
+//!
+//! let mut session =
+//!     RoomSession::login(Arc::new(client), room, "hello", RoomConfig::default()).await?;
+//! session.post("good morning").await?;
+//! while let Some(post) = session.recv().await {
+//!     let author = post.author.map_or_else(|| "?".to_string(), |c| c.name);
+//!     println!("{author}: {}", post.text);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+use crate::client::MeshCore;
+use crate::error::Result;
+use crate::event::{Event, Subscription};
+use crate::transport::Transport;
+use crate::types::{Contact, ContactMessage, PublicKey, TextType};
+
+/// Default interval between keep-alives.
+pub const DEFAULT_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(120);
+
+/// Default number of unacknowledged keep-alives before logging in again.
+pub const DEFAULT_MAX_MISSED: u32 = 2;
+
+/// Capacity of the post channel.
+const POST_CAPACITY: usize = 256;
+
+/// Room session configuration.
+#[derive(Debug, Clone)]
+pub struct RoomConfig {
+    /// Interval between keep-alives (and login retries while logged out).
+    pub keep_alive_interval: Duration,
+    /// Consecutive unacknowledged keep-alives before logging in again.
+    pub max_missed: u32,
+}
+
+impl Default for RoomConfig {
+    fn default() -> Self {
+        Self {
+            keep_alive_interval: DEFAULT_KEEP_ALIVE_INTERVAL,
+            max_missed: DEFAULT_MAX_MISSED,
+        }
+    }
+}
+
+impl RoomConfig {
+    /// Sets the interval between keep-alives.
+    #[must_use]
+    pub const fn keep_alive_interval(mut self, interval: Duration) -> Self {
+        self.keep_alive_interval = interval;
+        self
+    }
+
+    /// Sets the number of unacknowledged keep-alives before logging in again.
+    #[must_use]
+    pub const fn max_missed(mut self, max_missed: u32) -> Self {
+        self.max_missed = max_missed;
+        self
+    }
+}
+
+/// A post received from a room server.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RoomPost {
+    /// Public key of the room server.
+    pub room: PublicKey,
+    /// 4-byte public key prefix of the original author, if the post was
+    /// relayed from another member.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::types::serde_hex::option")
+    )]
+    pub author_prefix: Option<Vec<u8>>,
+    /// The author, if known to the device.
+    pub author: Option<Contact>,
+    /// Author's timestamp (Unix seconds).
+    pub timestamp: u32,
+    /// Post text.
+    pub text: String,
+}
+
+/// A logged-in session with a room server.
+///
+/// The session is kept alive in the background until it is dropped or
+/// [`Self::logout`] is called.
+pub struct RoomSession<T> {
+    client: Arc<MeshCore<T>>,
+    room: PublicKey,
+    logged_in: Arc<AtomicBool>,
+    posts: broadcast::Receiver<RoomPost>,
+    task: JoinHandle<()>,
+}
+
+impl<T: Transport + 'static> RoomSession<T> {
+    /// Logs in to a room server and starts the session.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::LoginFailed`](crate::Error::LoginFailed) if the
+    /// password is rejected, or a timeout if the server does not answer.
+    pub async fn login(
+        client: Arc<MeshCore<T>>,
+        room: PublicKey,
+        password: impl Into<String>,
+        config: RoomConfig,
+    ) -> Result<Self> {
+        let password = password.into();
+        // Subscribe before logging in so the first posts are not missed
+        let events = client.subscribe();
+        let (post_tx, posts) = broadcast::channel(POST_CAPACITY);
+
+        let login = client.login(&room, &password).await?;
+        tracing::info!(
+            "logged in to room {} ({})",
+            room,
+            if login.is_admin() { "admin" } else { "guest" }
+        );
+
+        let logged_in = Arc::new(AtomicBool::new(true));
+        let keeper = Keeper {
+            client: Arc::clone(&client),
+            room: room.clone(),
+            password,
+            config,
+            logged_in: Arc::clone(&logged_in),
+            posts: post_tx,
+            unresolved: HashSet::new(),
+        };
+        let task = tokio::spawn(keeper.run(events));
+
+        Ok(Self {
+            client,
+            room,
+            logged_in,
+            posts,
+            task,
+        })
+    }
+
+    /// Returns the room server's public key.
+    #[must_use]
+    pub const fn room(&self) -> &PublicKey {
+        &self.room
+    }
+
+    /// Returns true unless keep-alives went unacknowledged and logging in
+    /// again has not succeeded yet.
+    #[must_use]
+    pub fn is_logged_in(&self) -> bool {
+        self.logged_in.load(Ordering::SeqCst)
+    }
+
+    /// Posts a message to the room.
+    ///
+    /// Returns when the room server has acknowledged the post.
+    pub async fn post(&self, text: &str) -> Result<()> {
+        self.client.send_message(&self.room, text).await
+    }
+
+    /// Receives the next post.
+    ///
+    /// Returns `None` when the client disconnects.
+    pub async fn recv(&mut self) -> Option<RoomPost> {
+        loop {
+            match self.posts.recv().await {
+                Ok(post) => return Some(post),
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// Ends the session and logs out.
+    pub async fn logout(self) -> Result<()> {
+        self.task.abort();
+        self.client.commands().send_logout(&self.room).await
+    }
+}
+
+impl<T> Drop for RoomSession<T> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Background task keeping a session alive.
+struct Keeper<T> {
+    client: Arc<MeshCore<T>>,
+    room: PublicKey,
+    password: String,
+    config: RoomConfig,
+    logged_in: Arc<AtomicBool>,
+    posts: broadcast::Sender<RoomPost>,
+    /// Author prefixes not found after refreshing the contacts.
+    unresolved: HashSet<Vec<u8>>,
+}
+
+impl<T: Transport + 'static> Keeper<T> {
+    async fn run(mut self, mut events: Subscription) {
+        let mut interval = tokio::time::interval(self.config.keep_alive_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // The first tick completes immediately
+        interval.tick().await;
+
+        let mut missed = 0u32;
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Some(Event::MessagesWaiting) => {
+                        if let Err(e) = self.client.fetch_messages().await {
+                            tracing::warn!("room: failed to fetch messages: {}", e);
+                        }
+                    }
+                    Some(Event::ContactMessage(message))
+                        if message.sender_prefix == self.room.prefix() =>
+                    {
+                        let post = self.post(&message).await;
+                        // No receivers is fine
+                        let _ = self.posts.send(post);
+                    }
+                    Some(Event::Disconnected) | None => return,
+                    Some(_) => {}
+                },
+                _ = interval.tick() => {
+                    if self.logged_in.load(Ordering::SeqCst) {
+                        if self.keep_alive().await {
+                            missed = 0;
+                            continue;
+                        }
+                        missed += 1;
+                        tracing::debug!(
+                            "room {}: keep-alive missed ({} in a row)",
+                            self.room,
+                            missed
+                        );
+                        if missed < self.config.max_missed {
+                            continue;
+                        }
+                        tracing::warn!("room {}: session expired", self.room);
+                        self.logged_in.store(false, Ordering::SeqCst);
+                    }
+                    if self.relogin().await {
+                        missed = 0;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sends a keep-alive. Returns true if the room server acknowledged it.
+    ///
+    /// Room servers ACK a keep-alive but never respond to it, so a server
+    /// that expired the session, restarted or is out of range shows up as
+    /// a missing ACK within the timeout the device reports.
+    async fn keep_alive(&self) -> bool {
+        // Subscribe before sending so a fast ACK is not missed
+        let mut events = self.client.subscribe();
+        let (expected_ack, timeout_ms) =
+            match self.client.commands().binary_keep_alive(&self.room).await {
+                Ok(Event::MessageSent {
+                    expected_ack,
+                    timeout_ms,
+                }) => (expected_ack, timeout_ms),
+                Ok(event) => {
+                    tracing::debug!("room {}: keep-alive got {:?}", self.room, event);
+                    return false;
+                }
+                Err(e) => {
+                    tracing::debug!("room {}: keep-alive failed: {}", self.room, e);
+                    return false;
+                }
+            };
+
+        let ack = async {
+            while let Some(event) = events.recv().await {
+                if matches!(event, Event::Ack(ack) if ack.code == expected_ack) {
+                    return true;
+                }
+            }
+            false
+        };
+        let timeout = Duration::from_millis(u64::from(timeout_ms));
+        let acked = tokio::time::timeout(timeout, ack).await.unwrap_or(false);
+        if !acked {
+            tracing::debug!("room {}: keep-alive not acknowledged", self.room);
+        }
+        acked
+    }
+
+    /// Logs in again. Returns true on success.
+    async fn relogin(&self) -> bool {
+        match self.client.login(&self.room, &self.password).await {
+            Ok(login) => {
+                tracing::info!("room {}: logged in again", self.room);
+                if let Some(server_time) = login.server_time {
+                    tracing::debug!("room {}: server time {}", self.room, server_time);
+                }
+                self.logged_in.store(true, Ordering::SeqCst);
+                true
+            }
+            Err(e) => {
+                tracing::warn!("room {}: login failed: {}", self.room, e);
+                false
+            }
+        }
+    }
+
+    /// Builds a post, resolving the author.
+    ///
+    /// Room servers relay posts as signed messages whose signature field
+    /// holds the author's public key prefix.
+    async fn post(&mut self, message: &ContactMessage) -> RoomPost {
+        let author_prefix = message
+            .signature
+            .clone()
+            .filter(|_| message.text_type == TextType::Signed);
+
+        let mut author = None;
+        if let Some(prefix) = &author_prefix {
+            author = self.client.find_contact_by_prefix(prefix).await;
+            if author.is_none() && self.unresolved.insert(prefix.clone()) {
+                // The cache may be empty or stale
+                match self.client.get_contacts().await {
+                    Ok(_) => author = self.client.find_contact_by_prefix(prefix).await,
+                    Err(e) => tracing::warn!("room: failed to fetch contacts: {}", e),
+                }
+            }
+        }
+
+        RoomPost {
+            room: self.room.clone(),
+            author_prefix,
+            author,
+            timestamp: message.timestamp,
+            text: message.text.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicU32;
+
+    use super::*;
+    use crate::protocol::{CommandOpcode, PacketType};
+    use crate::testing::{self, Client};
+
+    const ROOM: u8 = 0x52;
+    const AUTHOR: u8 = 0xA7;
+
+    /// Fake room server behind a companion radio.
+    ///
+    /// Counts logins and delivers one relayed post from `AUTHOR`. The radio
+    /// sends every keep-alive; the server ACKs them while `alive` is set
+    /// and, as with real firmware, never responds to them.
+    async fn connect(alive: Arc<AtomicBool>, logins: Arc<AtomicU32>) -> (Client, PublicKey) {
+        let mut post_pending = true;
+        let (client, _push) = testing::connect(testing::self_info(0x01, ""), move |request| {
+            match CommandOpcode::from_byte(request[0]) {
+                Some(CommandOpcode::SendLogin) => {
+                    logins.fetch_add(1, Ordering::SeqCst);
+                    let mut login = vec![PacketType::LoginSuccess as u8, 0];
+                    login.extend_from_slice(&[ROOM; 6]);
+                    vec![
+                        testing::msg_sent(1, 100),
+                        login,
+                        vec![PacketType::MessagesWaiting as u8],
+                    ]
+                }
+                Some(CommandOpcode::BinaryReq) => {
+                    let mut responses = vec![testing::msg_sent(9, 100)];
+                    if alive.load(Ordering::SeqCst) {
+                        let mut ack = vec![PacketType::Ack as u8];
+                        ack.extend_from_slice(&9u32.to_le_bytes());
+                        responses.push(ack);
+                    }
+                    responses
+                }
+                Some(CommandOpcode::GetMessage) if post_pending => {
+                    post_pending = false;
+                    let mut post = vec![PacketType::ContactMsgRecv as u8];
+                    post.extend_from_slice(&[ROOM; 6]);
+                    post.extend_from_slice(&[0, TextType::Signed as u8]);
+                    post.extend_from_slice(&1_700_000_000u32.to_le_bytes());
+                    post.extend_from_slice(&[AUTHOR; 4]);
+                    post.extend_from_slice(b"meeting at 8");
+                    vec![post]
+                }
+                Some(CommandOpcode::GetMessage) => vec![vec![PacketType::NoMoreMsgs as u8]],
+                Some(CommandOpcode::GetContacts) => {
+                    testing::contacts(&[testing::contact(AUTHOR, "alice", 0)])
+                }
+                _ => vec![vec![PacketType::Ok as u8]],
+            }
+        })
+        .await;
+        (client, PublicKey::from_bytes(&[ROOM; 32]))
+    }
+
+    #[tokio::test]
+    async fn test_room_post_with_author() {
+        let logins = Arc::new(AtomicU32::new(0));
+        let (client, room) = connect(Arc::new(AtomicBool::new(true)), Arc::clone(&logins)).await;
+
+        let mut session = RoomSession::login(Arc::new(client), room, "pw", RoomConfig::default())
+            .await
+            .unwrap();
+        assert!(session.is_logged_in());
+
+        let post = tokio::time::timeout(Duration::from_secs(2), session.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(post.text, "meeting at 8");
+        assert_eq!(post.author_prefix, Some(vec![AUTHOR; 4]));
+        assert_eq!(post.author.unwrap().name, "alice");
+        assert_eq!(logins.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_room_relogin_without_ack() {
+        let alive = Arc::new(AtomicBool::new(true));
+        let logins = Arc::new(AtomicU32::new(0));
+        let (client, room) = connect(Arc::clone(&alive), Arc::clone(&logins)).await;
+
+        let config = RoomConfig::default()
+            .keep_alive_interval(Duration::from_millis(50))
+            .max_missed(2);
+        let session = RoomSession::login(Arc::new(client), room, "pw", config)
+            .await
+            .unwrap();
+
+        // Acknowledged keep-alives keep the session
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(logins.load(Ordering::SeqCst), 1);
+        assert!(session.is_logged_in());
+
+        // The radio still sends the keep-alives, but the server is gone
+        alive.store(false, Ordering::SeqCst);
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while logins.load(Ordering::SeqCst) < 2 || !session.is_logged_in() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+}
@@ -0,0 +1,116 @@
+//! Fake companion radio for tests.
+
+use bytes::Bytes;
+use futures::{SinkExt, StreamExt};
+use tokio::io::DuplexStream;
+use tokio::sync::mpsc;
+use tokio_util::codec::Framed;
+
+use crate::client::MeshCore;
+use crate::protocol::{CommandOpcode, FrameCodec, PacketType};
+use crate::transport::StreamTransport;
+
+/// Client connected to a fake device.
+pub(crate) type Client = MeshCore<StreamTransport<DuplexStream>>;
+
+/// Returns a `SelfInfo` frame for the public key `[key; 32]`.
+pub(crate) fn self_info(key: u8, name: &str) -> Vec<u8> {
+    let mut info = vec![PacketType::SelfInfo as u8, 1, 20, 22];
+    info.extend_from_slice(&[key; 32]);
+    info.extend_from_slice(&[0; 22]);
+    info.extend_from_slice(name.as_bytes());
+    info
+}
+
+/// Returns a `MsgSent` frame.
+pub(crate) fn msg_sent(expected_ack: u32, timeout_ms: u32) -> Vec<u8> {
+    let mut frame = vec![PacketType::MsgSent as u8, 0];
+    frame.extend_from_slice(&expected_ack.to_le_bytes());
+    frame.extend_from_slice(&timeout_ms.to_le_bytes());
+    frame
+}
+
+/// Returns a `Contact` frame of a chat node with the public key `[key; 32]`.
+pub(crate) fn contact(key: u8, name: &str, path_len: i8) -> Vec<u8> {
+    let mut frame = vec![PacketType::Contact as u8];
+    frame.extend_from_slice(&[key; 32]);
+    frame.extend_from_slice(&[1, 0, path_len.to_le_bytes()[0]]);
+    frame.extend_from_slice(&[0; 64]);
+    let mut name_bytes = [0u8; 32];
+    name_bytes[..name.len()].copy_from_slice(name.as_bytes());
+    frame.extend_from_slice(&name_bytes);
+    frame.extend_from_slice(&[0; 16]);
+    frame
+}
+
+/// Returns the frames of a contact list.
+pub(crate) fn contacts(contacts: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    let count = u32::try_from(contacts.len()).unwrap();
+    let mut start = vec![PacketType::ContactStart as u8];
+    start.extend_from_slice(&count.to_le_bytes());
+    let mut frames = vec![start];
+    frames.extend(contacts.iter().cloned());
+    frames.push(vec![PacketType::ContactEnd as u8, 0, 0, 0, 0]);
+    frames
+}
+
+/// Spawns a fake device and returns its host end and a sender for push
+/// frames.
+///
+/// `AppStart` is answered with `self_info`, every other request with the
+/// frames `respond` returns for it.
+pub(crate) fn spawn<F>(
+    self_info: Vec<u8>,
+    mut respond: F,
+) -> (DuplexStream, mpsc::UnboundedSender<Bytes>)
+where
+    F: FnMut(&[u8]) -> Vec<Vec<u8>> + Send + 'static,
+{
+    let (host, device) = tokio::io::duplex(4096);
+    let mut device = Framed::new(device, FrameCodec::device());
+    let (push_tx, mut push_rx) = mpsc::unbounded_channel::<Bytes>();
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                // Pushes queued before a request go out first
+                biased;
+                Some(push) = push_rx.recv() => {
+                    if device.send(push).await.is_err() {
+                        break;
+                    }
+                }
+                Some(Ok(request)) = device.next() => {
+                    let opcode = CommandOpcode::from_byte(request[0]);
+                    let responses = if opcode == Some(CommandOpcode::AppStart) {
+                        vec![self_info.clone()]
+                    } else {
+                        respond(&request)
+                    };
+                    for response in responses {
+                        if device.send(Bytes::from(response)).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+                else => break,
+            }
+        }
+    });
+
+    (host, push_tx)
+}
+
+/// Connects a client to a fake device, see [`spawn`].
+pub(crate) async fn connect<F>(
+    self_info: Vec<u8>,
+    respond: F,
+) -> (Client, mpsc::UnboundedSender<Bytes>)
+where
+    F: FnMut(&[u8]) -> Vec<Vec<u8>> + Send + 'static,
+{
+    let (host, push) = spawn(self_info, respond);
+    let mut client = MeshCore::stream(host);
+    client.connect().await.unwrap();
+    (client, push)
+}
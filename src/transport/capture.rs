@@ -0,0 +1,381 @@
+//! Frame capture recording.
+//!
+//! [`CaptureTransport`] wraps another transport and records every frame
+//! payload sent or received, with a timestamp, to a [`Capture`]. Captures
+//! can be fed back into a client with
+//! [`ReplayTransport`](super::replay::ReplayTransport).
+//!
+//! # File format
+//!
+//! Captures are UTF-8 text, one frame per line:
+//!
+//! ```text
+//! # meshcore capture v1
+//! # started 1718000000
+//! 0.201532 tx 01032020202020206d63636c69
+//! 0.254100 rx 0501141600...
+//! ```
+//!
+//! - Lines starting with `#` are comments; blank lines are ignored.
+//! - The first field is the time since the capture started, in seconds
+//!   with microsecond precision.
+//! - The second field is the direction: `tx` (host to device) or `rx`
+//!   (device to host).
+//! - The third field is the frame payload as lowercase hex, without the
+//!   frame header and length bytes.
+
+use std::fmt;
+use std::fs::File;
+use std::future::Future;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+use futures::StreamExt;
+
+use crate::error::{Error, Result};
+use crate::transport::{FrameStream, Transport};
+
+/// Header line written at the start of every capture.
+pub const CAPTURE_HEADER: &str = "# meshcore capture v1";
+
+/// Direction of a captured frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Host to device.
+    Tx,
+    /// Device to host.
+    Rx,
+}
+
+impl Direction {
+    /// Returns the direction as it appears in a capture file.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Tx => "tx",
+            Self::Rx => "rx",
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single captured frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptureRecord {
+    /// Time since the capture started.
+    pub elapsed: Duration,
+    /// Frame direction.
+    pub direction: Direction,
+    /// Frame payload.
+    pub payload: Bytes,
+}
+
+impl fmt::Display for CaptureRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{:06} {} {}",
+            self.elapsed.as_secs(),
+            self.elapsed.subsec_micros(),
+            self.direction,
+            hex::encode(&self.payload)
+        )
+    }
+}
+
+impl FromStr for CaptureRecord {
+    type Err = Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let invalid = |reason: String| Error::InvalidCapture { reason };
+
+        let mut fields = line.split_whitespace();
+        let (Some(elapsed), Some(direction), payload, None) = (
+            fields.next(),
+            fields.next(),
+            fields.next().unwrap_or_default(),
+            fields.next(),
+        ) else {
+            return Err(invalid(format!("expected 3 fields in {line:?}")));
+        };
+
+        let elapsed = elapsed
+            .parse::<f64>()
+            .ok()
+            .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+            .ok_or_else(|| invalid(format!("invalid timestamp {elapsed:?}")))?;
+        let direction = match direction {
+            "tx" => Direction::Tx,
+            "rx" => Direction::Rx,
+            other => return Err(invalid(format!("invalid direction {other:?}"))),
+        };
+        let payload = hex::decode(payload).map_err(|e| invalid(format!("invalid payload: {e}")))?;
+
+        Ok(Self {
+            elapsed,
+            direction,
+            payload: Bytes::from(payload),
+        })
+    }
+}
+
+/// Reads all records from a capture.
+///
+/// # Errors
+///
+/// Returns an error if reading fails or a line is malformed.
+pub fn read_capture<R: BufRead>(reader: R) -> Result<Vec<CaptureRecord>> {
+    let mut records = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let record = line.parse().map_err(|e| match e {
+            Error::InvalidCapture { reason } => Error::InvalidCapture {
+                reason: format!("line {}: {reason}", index + 1),
+            },
+            other => other,
+        })?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Reads all records from a capture file.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or is malformed.
+pub fn read_capture_file(path: impl AsRef<Path>) -> Result<Vec<CaptureRecord>> {
+    read_capture(BufReader::new(File::open(path)?))
+}
+
+struct CaptureSink {
+    writer: Box<dyn Write + Send>,
+    start: Instant,
+}
+
+/// Destination for captured frames.
+///
+/// Cloning a `Capture` shares the underlying writer and start time.
+#[derive(Clone)]
+pub struct Capture {
+    sink: Arc<Mutex<CaptureSink>>,
+}
+
+impl Capture {
+    /// Starts a capture written to `writer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header cannot be written.
+    pub fn new(writer: impl Write + Send + 'static) -> Result<Self> {
+        let mut writer: Box<dyn Write + Send> = Box::new(writer);
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        writeln!(writer, "{CAPTURE_HEADER}")?;
+        writeln!(writer, "# started {started}")?;
+        writer.flush()?;
+
+        Ok(Self {
+            sink: Arc::new(Mutex::new(CaptureSink {
+                writer,
+                start: Instant::now(),
+            })),
+        })
+    }
+
+    /// Starts a capture written to a new file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be created.
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+
+    /// Records a frame payload.
+    ///
+    /// Each record is flushed immediately so a capture survives a crash.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    pub fn record(&self, direction: Direction, payload: &[u8]) -> Result<()> {
+        let mut sink = self
+            .sink
+            .lock()
+            .map_err(|_| io::Error::other("capture writer poisoned"))?;
+        let record = CaptureRecord {
+            elapsed: sink.start.elapsed(),
+            direction,
+            payload: Bytes::copy_from_slice(payload),
+        };
+        writeln!(sink.writer, "{record}")?;
+        sink.writer.flush()?;
+        Ok(())
+    }
+
+    fn record_or_warn(&self, direction: Direction, payload: &[u8]) {
+        if let Err(e) = self.record(direction, payload) {
+            tracing::warn!("failed to record {} frame: {}", direction, e);
+        }
+    }
+}
+
+impl fmt::Debug for Capture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Capture").finish_non_exhaustive()
+    }
+}
+
+/// Transport tap that records all frames to a [`Capture`].
+///
+/// Failing to write the capture is logged but does not affect the
+/// wrapped transport.
+pub struct CaptureTransport<T> {
+    inner: T,
+    capture: Capture,
+}
+
+impl<T: Transport> CaptureTransport<T> {
+    /// Wraps `inner`, recording its frames to `capture`.
+    #[must_use]
+    pub const fn new(inner: T, capture: Capture) -> Self {
+        Self { inner, capture }
+    }
+
+    /// Returns the wrapped transport.
+    pub const fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns the capture.
+    pub const fn capture(&self) -> &Capture {
+        &self.capture
+    }
+}
+
+impl<T: Transport> Transport for CaptureTransport<T> {
+    fn connect(&mut self) -> Pin<Box<dyn Future<Output = Result<()>> + Send + '_>> {
+        self.inner.connect()
+    }
+
+    fn disconnect(&mut self) -> Pin<Box<dyn Future<Output = Result<()>> + Send + '_>> {
+        self.inner.disconnect()
+    }
+
+    fn send(&mut self, data: Bytes) -> Pin<Box<dyn Future<Output = Result<()>> + Send + '_>> {
+        Box::pin(async move {
+            self.inner.send(data.clone()).await?;
+            self.capture.record_or_warn(Direction::Tx, &data);
+            Ok(())
+        })
+    }
+
+    fn is_connected(&self) -> bool {
+        self.inner.is_connected()
+    }
+
+    fn take_frame_stream(&mut self) -> Option<FrameStream> {
+        let capture = self.capture.clone();
+        let frames = self.inner.take_frame_stream()?;
+        Some(Box::pin(frames.inspect(move |result| {
+            if let Ok(frame) = result {
+                capture.record_or_warn(Direction::Rx, frame);
+            }
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::SinkExt;
+    use tokio_util::codec::Framed;
+
+    use super::*;
+    use crate::protocol::FrameCodec;
+    use crate::transport::StreamTransport;
+
+    /// Writer that appends to a shared buffer.
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_record_roundtrip() {
+        let record = CaptureRecord {
+            elapsed: Duration::from_micros(1_250_042),
+            direction: Direction::Rx,
+            payload: Bytes::from_static(&[0x0C, 0xD4, 0x0D]),
+        };
+        let line = record.to_string();
+        assert_eq!(line, "1.250042 rx 0cd40d");
+        assert_eq!(line.parse::<CaptureRecord>().unwrap(), record);
+    }
+
+    #[test]
+    fn test_read_capture_reports_line() {
+        let text = "# meshcore capture v1\n\n0.1 tx 14\n0.2 up 00\n";
+        let err = read_capture(text.as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("line 4"));
+
+        let records = read_capture("0.1 tx 14\n0.2 rx 00\n".as_bytes()).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].direction, Direction::Rx);
+    }
+
+    #[tokio::test]
+    async fn test_capture_transport_records_both_directions() {
+        let buf = SharedBuf::default();
+        let (host, device) = tokio::io::duplex(256);
+        let mut device = Framed::new(device, FrameCodec::device());
+        let mut transport = CaptureTransport::new(
+            StreamTransport::new(host),
+            Capture::new(buf.clone()).unwrap(),
+        );
+
+        transport.connect().await.unwrap();
+        let mut frames = transport.take_frame_stream().unwrap();
+
+        transport.send(Bytes::from_static(&[0x14])).await.unwrap();
+        device.next().await.unwrap().unwrap();
+        device
+            .send(Bytes::from_static(&[0x0C, 0xD4, 0x0D]))
+            .await
+            .unwrap();
+        frames.next().await.unwrap().unwrap();
+
+        let text = String::from_utf8(buf.0.lock().unwrap().clone()).unwrap();
+        assert!(text.starts_with(CAPTURE_HEADER));
+        let records = read_capture(text.as_bytes()).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].direction, Direction::Tx);
+        assert_eq!(&records[0].payload[..], &[0x14]);
+        assert_eq!(records[1].direction, Direction::Rx);
+        assert_eq!(&records[1].payload[..], &[0x0C, 0xD4, 0x0D]);
+        assert!(records[0].elapsed <= records[1].elapsed);
+    }
+}
@@ -0,0 +1,233 @@
+//! Serial port auto-detection.
+//!
+//! [`discover_devices`] probes every USB serial port by sending `AppStart`
+//! and returns the ports where a `MeshCore` device answered, together with
+//! its [`SelfInfo`] and USB metadata.
+
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::StreamExt;
+use tokio_serial::SerialPortType;
+
+use crate::commands::APP_START;
+use crate::error::{Error, Result};
+use crate::protocol::{ErrorCode, PacketType, parse_self_info};
+use crate::transport::Transport;
+use crate::transport::serial::{SerialConfig, SerialTransport};
+use crate::types::SelfInfo;
+
+/// Default time to wait for `SelfInfo` after opening a port.
+pub const DEFAULT_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// USB metadata of a serial port.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsbInfo {
+    /// Vendor ID.
+    pub vid: u16,
+    /// Product ID.
+    pub pid: u16,
+    /// Serial number.
+    pub serial_number: Option<String>,
+    /// Manufacturer.
+    pub manufacturer: Option<String>,
+    /// Product name.
+    pub product: Option<String>,
+}
+
+impl std::fmt::Display for UsbInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04x}:{:04x}", self.vid, self.pid)?;
+        if let Some(serial) = &self.serial_number {
+            write!(f, " ({serial})")?;
+        }
+        Ok(())
+    }
+}
+
+/// A `MeshCore` device found on a serial port.
+#[derive(Debug, Clone)]
+pub struct DiscoveredDevice {
+    /// Serial port path.
+    pub port: String,
+    /// Serial settings the device answered with.
+    pub config: SerialConfig,
+    /// Device info returned by `AppStart`.
+    pub self_info: SelfInfo,
+    /// USB metadata, if the port is a USB port.
+    pub usb: Option<UsbInfo>,
+}
+
+/// Returns the serial ports that may have a `MeshCore` device attached.
+///
+/// Only USB ports are candidates; companion radios are always attached
+/// over USB.
+fn candidate_ports() -> Result<Vec<(String, UsbInfo)>> {
+    let mut ports: Vec<_> = tokio_serial::available_ports()
+        .map_err(Error::Serial)?
+        .into_iter()
+        .filter_map(|info| match info.port_type {
+            SerialPortType::UsbPort(usb) => Some((
+                info.port_name,
+                UsbInfo {
+                    vid: usb.vid,
+                    pid: usb.pid,
+                    serial_number: usb.serial_number,
+                    manufacturer: usb.manufacturer,
+                    product: usb.product,
+                },
+            )),
+            _ => None,
+        })
+        .collect();
+    ports.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(ports)
+}
+
+/// Sends `AppStart` over a transport and waits for `SelfInfo`.
+///
+/// The transport is connected before and disconnected after probing.
+/// Push frames received meanwhile are ignored.
+///
+/// # Errors
+///
+/// Returns an error if the transport fails, the device reports an error
+/// or no `SelfInfo` arrives within `timeout`.
+pub async fn probe<T: Transport>(mut transport: T, timeout: Duration) -> Result<SelfInfo> {
+    transport.connect().await?;
+    let result = probe_connected(&mut transport, timeout).await;
+    let _ = transport.disconnect().await;
+    result
+}
+
+async fn probe_connected<T: Transport>(transport: &mut T, timeout: Duration) -> Result<SelfInfo> {
+    let mut frames = transport.take_frame_stream().ok_or(Error::Protocol {
+        message: "transport does not provide a frame stream".into(),
+    })?;
+    transport.send(Bytes::from_static(&APP_START)).await?;
+
+    let wait = async {
+        while let Some(frame) = frames.next().await {
+            let frame = frame?;
+            match frame.first().and_then(|&b| PacketType::from_byte(b)) {
+                Some(PacketType::SelfInfo) => return parse_self_info(&frame[1..]),
+                Some(PacketType::Error) => {
+                    return Err(Error::Device {
+                        code: ErrorCode::from_payload(&frame[1..]),
+                        command: None,
+                    });
+                }
+                _ => {}
+            }
+        }
+        Err(Error::NotConnected)
+    };
+
+    tokio::time::timeout(timeout, wait)
+        .await
+        .map_err(|_| Error::Timeout {
+            timeout_ms: u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX),
+        })?
+}
+
+/// Probes a serial port for a `MeshCore` device.
+///
+/// # Errors
+///
+/// Returns an error if the port cannot be opened or no device answers.
+pub async fn probe_port(config: SerialConfig, timeout: Duration) -> Result<SelfInfo> {
+    probe(SerialTransport::new(config), timeout).await
+}
+
+/// Finds `MeshCore` devices on all USB serial ports.
+///
+/// Ports are probed concurrently with default settings and
+/// [`DEFAULT_PROBE_TIMEOUT`]. Ports that cannot be opened (e.g. because
+/// another process holds them) or do not answer are skipped. Devices are
+/// returned sorted by port name.
+///
+/// # Errors
+///
+/// Returns an error if the port list cannot be retrieved.
+pub async fn discover_devices() -> Result<Vec<DiscoveredDevice>> {
+    discover_devices_with(SerialConfig::new, DEFAULT_PROBE_TIMEOUT).await
+}
+
+/// Finds `MeshCore` devices with custom serial settings.
+///
+/// `config` builds the configuration for a port name.
+///
+/// # Errors
+///
+/// Returns an error if the port list cannot be retrieved.
+pub async fn discover_devices_with(
+    config: impl Fn(String) -> SerialConfig,
+    timeout: Duration,
+) -> Result<Vec<DiscoveredDevice>> {
+    let probes = candidate_ports()?.into_iter().map(|(port, usb)| {
+        let config = config(port.clone());
+        async move {
+            match probe_port(config.clone(), timeout).await {
+                Ok(self_info) => {
+                    tracing::info!("found {} on {} [{}]", self_info.name, port, usb);
+                    Some(DiscoveredDevice {
+                        port,
+                        config,
+                        self_info,
+                        usb: Some(usb),
+                    })
+                }
+                Err(e) => {
+                    tracing::debug!("no device on {}: {}", port, e);
+                    None
+                }
+            }
+        }
+    });
+
+    Ok(futures::future::join_all(probes)
+        .await
+        .into_iter()
+        .flatten()
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use crate::transport::StreamTransport;
+
+    #[tokio::test]
+    async fn test_probe_returns_self_info() {
+        let (host, push) = testing::spawn(testing::self_info(0xAB, "probe"), |_| Vec::new());
+        // Pushes before the response are ignored
+        push.send(Bytes::from_static(&[0x83])).unwrap();
+
+        let info = probe(StreamTransport::new(host), Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(info.name, "probe");
+    }
+
+    #[tokio::test]
+    async fn test_probe_times_out_on_silence() {
+        let (host, _device) = tokio::io::duplex(1024);
+        let err = probe(StreamTransport::new(host), Duration::from_millis(20))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Timeout { .. }));
+    }
+
+    #[test]
+    fn test_usb_info_display() {
+        let usb = UsbInfo {
+            vid: 0x303a,
+            pid: 0x1001,
+            serial_number: Some("F4:12".into()),
+            manufacturer: None,
+            product: None,
+        };
+        assert_eq!(usb.to_string(), "303a:1001 (F4:12)");
+    }
+}
@@ -1,17 +1,28 @@
 //! Transport layer for `MeshCore` communication.
 //!
-//! This module provides the abstraction for different transport methods.
-//! Currently only USB/Serial is implemented.
+//! This module provides the abstraction for different transport methods:
+//! USB/Serial, and a generic transport over any async byte stream. Frame
+//! capture and replay are implemented as transports as well.
 
+pub mod capture;
+pub mod discovery;
+pub mod replay;
 pub mod serial;
+pub mod stream;
 
 use std::future::Future;
 use std::pin::Pin;
 
 use bytes::Bytes;
+use futures::Stream;
 
 use crate::error::Result;
 
+/// Stream of received frame payloads.
+///
+/// The stream ends when the connection is closed.
+pub type FrameStream = Pin<Box<dyn Stream<Item = Result<Bytes>> + Send>>;
+
 /// Trait for transport implementations.
 pub trait Transport: Send + Sync {
     /// Connects to the device.
@@ -25,6 +36,40 @@ pub trait Transport: Send + Sync {
 
     /// Returns true if connected.
     fn is_connected(&self) -> bool;
+
+    /// Takes the stream of received frames for use in a background task.
+    ///
+    /// Called once after connecting. Transports that deliver frames by
+    /// other means may return `None` (the default).
+    fn take_frame_stream(&mut self) -> Option<FrameStream> {
+        None
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn connect(&mut self) -> Pin<Box<dyn Future<Output = Result<()>> + Send + '_>> {
+        (**self).connect()
+    }
+
+    fn disconnect(&mut self) -> Pin<Box<dyn Future<Output = Result<()>> + Send + '_>> {
+        (**self).disconnect()
+    }
+
+    fn send(&mut self, data: Bytes) -> Pin<Box<dyn Future<Output = Result<()>> + Send + '_>> {
+        (**self).send(data)
+    }
+
+    fn is_connected(&self) -> bool {
+        (**self).is_connected()
+    }
+
+    fn take_frame_stream(&mut self) -> Option<FrameStream> {
+        (**self).take_frame_stream()
+    }
 }
 
+pub use capture::{Capture, CaptureRecord, CaptureTransport, Direction};
+pub use discovery::{DiscoveredDevice, UsbInfo, discover_devices};
+pub use replay::ReplayTransport;
 pub use serial::SerialTransport;
+pub use stream::StreamTransport;
@@ -0,0 +1,254 @@
+//! Replay of recorded frame captures.
+//!
+//! [`ReplayTransport`] feeds the received frames of a capture (see
+//! [`capture`](super::capture)) back into a client, so that frame
+//! processing can be reproduced exactly without a device:
+//!
+//! ```no_run
+//! use meshcore::MeshCore;
+//! use meshcore::transport::ReplayTransport;
+//!
+//! # async fn example() -> meshcore::Result<()> {
+//! let transport = ReplayTransport::open("field-report.cap")?.speed(10.0);
+//! let mut client = MeshCore::new(transport);
+//! let mut events = client.subscribe();
+//! client.connect().await?;
+//!
+//! while let Some(event) = events.recv().await {
+//!     println!("{event:?}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use tokio::sync::watch;
+
+use crate::error::{Error, Result};
+use crate::transport::capture::{CaptureRecord, Direction, read_capture_file};
+use crate::transport::{FrameStream, Transport};
+
+/// Transport that replays a frame capture.
+///
+/// Received (`rx`) frames are delivered with the same spacing as in the
+/// capture, scaled by the replay speed. Each received frame that was
+/// recorded after a sent (`tx`) frame is held back until the client has
+/// sent that many frames, so responses never overtake their commands.
+/// Sent frames that differ from the capture are logged.
+///
+/// The frame stream ends after the last record.
+#[derive(Debug)]
+pub struct ReplayTransport {
+    records: Arc<[CaptureRecord]>,
+    speed: Option<f64>,
+    sent: watch::Sender<usize>,
+    connected: bool,
+}
+
+impl ReplayTransport {
+    /// Creates a transport replaying `records` in real time.
+    #[must_use]
+    pub fn new(records: Vec<CaptureRecord>) -> Self {
+        Self {
+            records: records.into(),
+            speed: Some(1.0),
+            sent: watch::Sender::new(0),
+            connected: false,
+        }
+    }
+
+    /// Creates a transport replaying a capture file in real time.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is malformed.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::new(read_capture_file(path)?))
+    }
+
+    /// Sets the replay speed factor.
+    ///
+    /// A factor of 2.0 replays twice as fast as recorded. Non-positive or
+    /// non-finite factors replay without any delay.
+    #[must_use]
+    pub fn speed(mut self, factor: f64) -> Self {
+        self.speed = (factor.is_finite() && factor > 0.0).then_some(factor);
+        self
+    }
+
+    /// Replays without any delay between frames.
+    #[must_use]
+    pub const fn instant(mut self) -> Self {
+        self.speed = None;
+        self
+    }
+
+    /// Returns the replayed records.
+    #[must_use]
+    pub fn records(&self) -> &[CaptureRecord] {
+        &self.records
+    }
+
+    /// Returns the number of frames sent by the client.
+    #[must_use]
+    pub fn frames_sent(&self) -> usize {
+        *self.sent.borrow()
+    }
+}
+
+/// State of the replayed frame stream.
+struct ReplayState {
+    records: Arc<[CaptureRecord]>,
+    speed: Option<f64>,
+    sent: watch::Receiver<usize>,
+    index: usize,
+    tx_seen: usize,
+    last_elapsed: Duration,
+}
+
+impl ReplayState {
+    async fn next_frame(&mut self) -> Option<Bytes> {
+        while let Some(record) = self.records.get(self.index) {
+            self.index += 1;
+            let gap = record.elapsed.saturating_sub(self.last_elapsed);
+            self.last_elapsed = record.elapsed;
+
+            match record.direction {
+                Direction::Tx => {
+                    // Wait for the client to send the recorded command
+                    self.tx_seen += 1;
+                    let expected = self.tx_seen;
+                    self.sent.wait_for(|&n| n >= expected).await.ok()?;
+                }
+                Direction::Rx => {
+                    if let Some(speed) = self.speed {
+                        tokio::time::sleep(gap.div_f64(speed)).await;
+                    }
+                    return Some(record.payload.clone());
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Transport for ReplayTransport {
+    fn connect(&mut self) -> Pin<Box<dyn Future<Output = Result<()>> + Send + '_>> {
+        Box::pin(async move {
+            self.connected = true;
+            Ok(())
+        })
+    }
+
+    fn disconnect(&mut self) -> Pin<Box<dyn Future<Output = Result<()>> + Send + '_>> {
+        Box::pin(async move {
+            self.connected = false;
+            Ok(())
+        })
+    }
+
+    fn send(&mut self, data: Bytes) -> Pin<Box<dyn Future<Output = Result<()>> + Send + '_>> {
+        Box::pin(async move {
+            if !self.connected {
+                return Err(Error::NotConnected);
+            }
+
+            let index = *self.sent.borrow();
+            let recorded = self
+                .records
+                .iter()
+                .filter(|r| r.direction == Direction::Tx)
+                .nth(index);
+            match recorded {
+                Some(record) if record.payload == data => {}
+                Some(record) => tracing::warn!(
+                    "replay: sent frame {} differs from capture ({} vs {})",
+                    index,
+                    hex::encode(&data),
+                    hex::encode(&record.payload)
+                ),
+                None => tracing::warn!("replay: sent frame {} is not in the capture", index),
+            }
+
+            self.sent.send_modify(|n| *n += 1);
+            Ok(())
+        })
+    }
+
+    fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    fn take_frame_stream(&mut self) -> Option<FrameStream> {
+        let state = ReplayState {
+            records: Arc::clone(&self.records),
+            speed: self.speed,
+            sent: self.sent.subscribe(),
+            index: 0,
+            tx_seen: 0,
+            last_elapsed: Duration::ZERO,
+        };
+        Some(Box::pin(futures::stream::unfold(
+            state,
+            |mut state| async move { state.next_frame().await.map(|frame| (Ok(frame), state)) },
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::MeshCore;
+    use crate::testing;
+
+    fn record(millis: u64, direction: Direction, payload: &[u8]) -> CaptureRecord {
+        CaptureRecord {
+            elapsed: Duration::from_millis(millis),
+            direction,
+            payload: Bytes::copy_from_slice(payload),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_replay_accelerated_timing() {
+        let records = vec![
+            record(1000, Direction::Rx, &[0x80]),
+            record(3000, Direction::Rx, &[0x83]),
+        ];
+        let mut transport = ReplayTransport::new(records).speed(2.0);
+        transport.connect().await.unwrap();
+        let mut frames = transport.take_frame_stream().unwrap();
+
+        let start = tokio::time::Instant::now();
+        assert_eq!(&frames.next().await.unwrap().unwrap()[..], &[0x80]);
+        assert_eq!(start.elapsed(), Duration::from_millis(500));
+        assert_eq!(&frames.next().await.unwrap().unwrap()[..], &[0x83]);
+        assert_eq!(start.elapsed(), Duration::from_millis(1500));
+        assert!(frames.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_replay_into_meshcore() {
+        let self_info = testing::self_info(0xAB, "replayed");
+
+        let records = vec![
+            record(200, Direction::Tx, b"\x01\x03      mccli"),
+            record(250, Direction::Rx, &self_info),
+            record(300, Direction::Tx, &[0x14]),
+            record(320, Direction::Rx, &[0x0C, 0xD4, 0x0D]),
+        ];
+
+        let mut client = MeshCore::new(ReplayTransport::new(records).instant());
+        let info = client.connect().await.unwrap();
+        assert_eq!(info.name, "replayed");
+        assert_eq!(client.get_battery().await.unwrap().millivolts, 3540);
+    }
+}
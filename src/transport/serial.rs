@@ -4,7 +4,6 @@
 //! connected via USB.
 
 use std::future::Future;
-use std::io;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
@@ -12,11 +11,14 @@ use std::time::Duration;
 use bytes::Bytes;
 use tokio::io::{AsyncReadExt, AsyncWriteExt, ReadHalf, WriteHalf};
 use tokio::sync::{Mutex, mpsc};
-use tokio_serial::{SerialPortBuilderExt, SerialStream};
+use tokio_serial::{SerialPort, SerialPortBuilderExt, SerialStream};
+use tokio_util::codec::FramedRead;
 
 use crate::error::{Error, Result};
-use crate::protocol::{FrameDecoder, encode_frame};
-use crate::transport::Transport;
+use crate::protocol::{FrameCodec, FrameDecoder, encode_frame};
+use crate::transport::{FrameStream, Transport};
+
+pub use tokio_serial::{FlowControl, Parity};
 
 /// Default baud rate for `MeshCore` devices.
 pub const DEFAULT_BAUD_RATE: u32 = 115_200;
@@ -24,6 +26,45 @@ pub const DEFAULT_BAUD_RATE: u32 = 115_200;
 /// Default connection delay.
 pub const DEFAULT_CONNECTION_DELAY: Duration = Duration::from_millis(300);
 
+/// Default time to drain stale data after connecting.
+pub const DEFAULT_DRAIN_TIME: Duration = Duration::from_millis(500);
+
+/// Modem control line of a serial port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlLine {
+    /// Data Terminal Ready.
+    Dtr,
+    /// Request To Send.
+    Rts,
+}
+
+/// Reset pulse sent on a control line after opening the port.
+///
+/// The line is asserted for `duration` and then released. Boards with an
+/// auto-reset circuit (e.g. ESP32 dev boards wire RTS to EN) reboot on
+/// the pulse; the connection delay should cover the boot time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResetPulse {
+    /// Line to pulse.
+    pub line: ControlLine,
+    /// How long the line is asserted.
+    pub duration: Duration,
+}
+
+impl ResetPulse {
+    /// Creates a reset pulse.
+    #[must_use]
+    pub const fn new(line: ControlLine, duration: Duration) -> Self {
+        Self { line, duration }
+    }
+
+    /// Reset pulse for ESP32 boards with the usual RTS-to-EN circuit.
+    #[must_use]
+    pub const fn esp32() -> Self {
+        Self::new(ControlLine::Rts, Duration::from_millis(100))
+    }
+}
+
 /// Configuration for serial transport.
 #[derive(Debug, Clone)]
 pub struct SerialConfig {
@@ -31,18 +72,39 @@ pub struct SerialConfig {
     pub port: String,
     /// Baud rate.
     pub baud_rate: u32,
+    /// Flow control.
+    pub flow_control: FlowControl,
+    /// Parity.
+    pub parity: Parity,
+    /// RTS state to set after opening, or `None` to leave it unchanged.
+    pub rts: Option<bool>,
+    /// DTR state to set after opening, or `None` to leave it unchanged.
+    pub dtr: Option<bool>,
+    /// Optional reset pulse sent after setting RTS and DTR.
+    pub reset_pulse: Option<ResetPulse>,
     /// Delay after connection before sending commands.
     pub connection_delay: Duration,
+    /// Time spent discarding stale data after the connection delay.
+    pub drain_time: Duration,
 }
 
 impl SerialConfig {
     /// Creates a new serial configuration with default settings.
+    ///
+    /// By default, RTS is set low (matching the Python library), DTR is
+    /// left unchanged and no reset pulse is sent.
     #[must_use]
     pub fn new(port: impl Into<String>) -> Self {
         Self {
             port: port.into(),
             baud_rate: DEFAULT_BAUD_RATE,
+            flow_control: FlowControl::None,
+            parity: Parity::None,
+            rts: Some(false),
+            dtr: None,
+            reset_pulse: None,
             connection_delay: DEFAULT_CONNECTION_DELAY,
+            drain_time: DEFAULT_DRAIN_TIME,
         }
     }
 
@@ -53,12 +115,67 @@ impl SerialConfig {
         self
     }
 
+    /// Sets the flow control.
+    #[must_use]
+    pub const fn flow_control(mut self, flow_control: FlowControl) -> Self {
+        self.flow_control = flow_control;
+        self
+    }
+
+    /// Sets the parity.
+    #[must_use]
+    pub const fn parity(mut self, parity: Parity) -> Self {
+        self.parity = parity;
+        self
+    }
+
+    /// Sets the RTS state after opening (`None` leaves it unchanged).
+    #[must_use]
+    pub const fn rts(mut self, rts: Option<bool>) -> Self {
+        self.rts = rts;
+        self
+    }
+
+    /// Sets the DTR state after opening (`None` leaves it unchanged).
+    #[must_use]
+    pub const fn dtr(mut self, dtr: Option<bool>) -> Self {
+        self.dtr = dtr;
+        self
+    }
+
+    /// Sets a reset pulse to send after opening.
+    #[must_use]
+    pub const fn reset_pulse(mut self, pulse: ResetPulse) -> Self {
+        self.reset_pulse = Some(pulse);
+        self
+    }
+
     /// Sets the connection delay.
     #[must_use]
     pub const fn connection_delay(mut self, delay: Duration) -> Self {
         self.connection_delay = delay;
         self
     }
+
+    /// Sets the drain time (zero disables draining).
+    #[must_use]
+    pub const fn drain_time(mut self, drain_time: Duration) -> Self {
+        self.drain_time = drain_time;
+        self
+    }
+
+    /// Avoids rebooting the device on connect.
+    ///
+    /// Leaves RTS and DTR unchanged and disables the reset pulse, for
+    /// boards that reboot on a control line change. Note that the
+    /// operating system may still change the lines when opening the port.
+    #[must_use]
+    pub const fn no_reset(mut self) -> Self {
+        self.rts = None;
+        self.dtr = None;
+        self.reset_pulse = None;
+        self
+    }
 }
 
 /// Serial transport for `MeshCore` communication.
@@ -115,59 +232,6 @@ impl SerialTransport {
     pub fn frame_tx(&self) -> Option<mpsc::Sender<Bytes>> {
         self.frame_tx.clone()
     }
-
-    /// Runs the read loop with a given reader, processing incoming data.
-    ///
-    /// This should be spawned as a separate task.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if reading fails or the connection is lost.
-    pub async fn run_read_loop_with_reader(
-        mut reader: ReadHalf<SerialStream>,
-        mut decoder: FrameDecoder,
-        frame_tx: mpsc::Sender<Bytes>,
-    ) -> Result<()> {
-        let mut buf = [0u8; 1024];
-
-        loop {
-            let n = match reader.read(&mut buf).await {
-                Ok(0) => {
-                    tracing::debug!("serial port closed");
-                    return Err(Error::Io(io::Error::new(
-                        io::ErrorKind::ConnectionReset,
-                        "serial port closed",
-                    )));
-                }
-                Ok(n) => n,
-                Err(e) => {
-                    tracing::error!("serial read error: {}", e);
-                    return Err(Error::Io(e));
-                }
-            };
-
-            tracing::trace!("received {} bytes", n);
-            decoder.feed(&buf[..n]);
-
-            // Process all complete frames
-            loop {
-                match decoder.decode() {
-                    Ok(Some(frame)) => {
-                        tracing::trace!("decoded frame: {} bytes", frame.len());
-                        if frame_tx.send(frame).await.is_err() {
-                            tracing::debug!("frame receiver dropped");
-                            return Ok(());
-                        }
-                    }
-                    Ok(None) => break, // Need more data
-                    Err(e) => {
-                        tracing::warn!("frame decode error: {}", e);
-                        // Continue processing - the decoder skips invalid bytes
-                    }
-                }
-            }
-        }
-    }
 }
 
 impl Transport for SerialTransport {
@@ -180,37 +244,19 @@ impl Transport for SerialTransport {
             tracing::info!("connecting to serial port: {}", self.config.port);
 
             let mut stream = tokio_serial::new(&self.config.port, self.config.baud_rate)
+                .flow_control(self.config.flow_control)
+                .parity(self.config.parity)
                 .open_native_async()
                 .map_err(Error::Serial)?;
 
-            // Set RTS to false, matching Python behavior
-            // This is important for proper device initialization
-            if let Err(e) = tokio_serial::SerialPort::write_request_to_send(&mut stream, false) {
-                tracing::warn!("failed to set RTS: {}", e);
-            }
+            apply_control_lines(&mut stream, &self.config).await;
 
             // Wait for device to be ready
             tokio::time::sleep(self.config.connection_delay).await;
 
             // Drain any stale data from the device buffer
             // Some devices send data shortly after connection opens
-            let mut buf = [0u8; 1024];
-            let mut total_drained = 0usize;
-
-            // Try draining for up to 500ms with multiple read attempts
-            let drain_deadline = tokio::time::Instant::now() + Duration::from_millis(500);
-            while tokio::time::Instant::now() < drain_deadline {
-                match tokio::time::timeout(Duration::from_millis(20), stream.read(&mut buf)).await {
-                    Ok(Ok(n)) if n > 0 => {
-                        total_drained += n;
-                    }
-                    _ => {
-                        // Brief pause then try again
-                        tokio::time::sleep(Duration::from_millis(10)).await;
-                    }
-                }
-            }
-
+            let total_drained = drain(&mut stream, self.config.drain_time).await;
             if total_drained > 0 {
                 tracing::debug!("drained {} stale bytes from buffer", total_drained);
             }
@@ -256,6 +302,63 @@ impl Transport for SerialTransport {
     fn is_connected(&self) -> bool {
         self.writer.is_some()
     }
+
+    fn take_frame_stream(&mut self) -> Option<FrameStream> {
+        let reader = self.reader.take()?;
+        let codec = FrameCodec::with_decoder(std::mem::take(&mut self.decoder));
+        Some(Box::pin(FramedRead::new(reader, codec)))
+    }
+}
+
+/// Sets a control line, logging failures.
+///
+/// Not all ports support control lines (e.g. some USB-CDC adapters on
+/// Windows), so failures are not fatal.
+fn set_line(stream: &mut SerialStream, line: ControlLine, level: bool) {
+    let result = match line {
+        ControlLine::Rts => stream.write_request_to_send(level),
+        ControlLine::Dtr => stream.write_data_terminal_ready(level),
+    };
+    if let Err(e) = result {
+        tracing::warn!("failed to set {:?}: {}", line, e);
+    }
+}
+
+/// Applies the configured RTS/DTR states and reset pulse.
+async fn apply_control_lines(stream: &mut SerialStream, config: &SerialConfig) {
+    if let Some(rts) = config.rts {
+        set_line(stream, ControlLine::Rts, rts);
+    }
+    if let Some(dtr) = config.dtr {
+        set_line(stream, ControlLine::Dtr, dtr);
+    }
+    if let Some(pulse) = config.reset_pulse {
+        tracing::debug!("sending {:?} reset pulse", pulse.line);
+        set_line(stream, pulse.line, true);
+        tokio::time::sleep(pulse.duration).await;
+        set_line(stream, pulse.line, false);
+    }
+}
+
+/// Discards incoming data for `duration`, returning the number of bytes.
+async fn drain(stream: &mut SerialStream, duration: Duration) -> usize {
+    let mut buf = [0u8; 1024];
+    let mut total_drained = 0usize;
+
+    // Try draining with multiple read attempts until the deadline
+    let drain_deadline = tokio::time::Instant::now() + duration;
+    while tokio::time::Instant::now() < drain_deadline {
+        match tokio::time::timeout(Duration::from_millis(20), stream.read(&mut buf)).await {
+            Ok(Ok(n)) if n > 0 => {
+                total_drained += n;
+            }
+            _ => {
+                // Brief pause then try again
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }
+    }
+    total_drained
 }
 
 /// Lists available serial ports.
@@ -288,6 +391,27 @@ mod tests {
         assert_eq!(config.connection_delay, Duration::from_secs(1));
     }
 
+    #[test]
+    fn test_serial_config_line_settings() {
+        let config = SerialConfig::new("/dev/ttyACM0")
+            .flow_control(FlowControl::Hardware)
+            .parity(Parity::Even)
+            .dtr(Some(true))
+            .reset_pulse(ResetPulse::esp32())
+            .drain_time(Duration::ZERO);
+        assert_eq!(config.flow_control, FlowControl::Hardware);
+        assert_eq!(config.parity, Parity::Even);
+        assert_eq!(config.rts, Some(false));
+        assert_eq!(config.dtr, Some(true));
+        assert_eq!(config.reset_pulse.unwrap().line, ControlLine::Rts);
+        assert_eq!(config.drain_time, Duration::ZERO);
+
+        let config = config.no_reset();
+        assert_eq!(config.rts, None);
+        assert_eq!(config.dtr, None);
+        assert!(config.reset_pulse.is_none());
+    }
+
     #[test]
     #[ignore = "Requires /sys/class/tty - not available in sandboxed builds"]
     fn test_list_ports() {
@@ -0,0 +1,150 @@
+//! Generic transport over any async byte stream.
+//!
+//! [`StreamTransport`] speaks the frame protocol over anything that
+//! implements `AsyncRead + AsyncWrite`: a TCP or Unix domain socket, a
+//! pipe provided by `socat`, a child process's stdio (combined with
+//! [`tokio::io::join`]), or [`tokio::io::duplex`] in tests.
+
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+
+use bytes::Bytes;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
+use tokio_util::codec::FramedRead;
+
+use crate::error::{Error, Result};
+use crate::protocol::{FrameCodec, encode_frame};
+use crate::transport::{FrameStream, Transport};
+
+/// Transport over an arbitrary `AsyncRead + AsyncWrite` stream.
+///
+/// The stream is already open when the transport is created, so
+/// connecting only marks it as in use. Once disconnected, the stream is
+/// closed and the transport cannot be connected again.
+pub struct StreamTransport<S> {
+    reader: Option<ReadHalf<S>>,
+    writer: Option<WriteHalf<S>>,
+    connected: bool,
+}
+
+impl<S: AsyncRead + AsyncWrite> StreamTransport<S> {
+    /// Creates a new transport over the given stream.
+    #[must_use]
+    pub fn new(stream: S) -> Self {
+        let (reader, writer) = tokio::io::split(stream);
+        Self {
+            reader: Some(reader),
+            writer: Some(writer),
+            connected: false,
+        }
+    }
+}
+
+impl<S> Transport for StreamTransport<S>
+where
+    S: AsyncRead + AsyncWrite + Send + Sync + 'static,
+{
+    fn connect(&mut self) -> Pin<Box<dyn Future<Output = Result<()>> + Send + '_>> {
+        Box::pin(async move {
+            if self.writer.is_none() {
+                return Err(Error::Io(io::Error::new(
+                    io::ErrorKind::NotConnected,
+                    "stream has been closed",
+                )));
+            }
+            self.connected = true;
+            Ok(())
+        })
+    }
+
+    fn disconnect(&mut self) -> Pin<Box<dyn Future<Output = Result<()>> + Send + '_>> {
+        Box::pin(async move {
+            self.connected = false;
+            self.reader = None;
+            if let Some(mut writer) = self.writer.take() {
+                // The peer may already be gone
+                let _ = writer.shutdown().await;
+            }
+            Ok(())
+        })
+    }
+
+    fn send(&mut self, data: Bytes) -> Pin<Box<dyn Future<Output = Result<()>> + Send + '_>> {
+        Box::pin(async move {
+            if !self.connected {
+                return Err(Error::NotConnected);
+            }
+            let writer = self.writer.as_mut().ok_or(Error::NotConnected)?;
+
+            let frame = encode_frame(&data);
+            tracing::trace!("sending frame: {} bytes", frame.len());
+
+            writer.write_all(&frame).await?;
+            writer.flush().await?;
+            Ok(())
+        })
+    }
+
+    fn is_connected(&self) -> bool {
+        self.connected && self.writer.is_some()
+    }
+
+    fn take_frame_stream(&mut self) -> Option<FrameStream> {
+        let reader = self.reader.take()?;
+        Some(Box::pin(FramedRead::new(reader, FrameCodec::new())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{SinkExt, StreamExt};
+    use tokio_util::codec::Framed;
+
+    use super::*;
+    use crate::MeshCore;
+    use crate::protocol::{CommandOpcode, PacketType};
+    use crate::testing;
+
+    #[tokio::test]
+    async fn test_stream_transport_send_and_receive() {
+        let (host, device) = tokio::io::duplex(256);
+        let mut transport = StreamTransport::new(host);
+        let mut device = Framed::new(device, FrameCodec::device());
+
+        assert!(transport.send(Bytes::from_static(&[0x14])).await.is_err());
+        transport.connect().await.unwrap();
+        let mut frames = transport.take_frame_stream().unwrap();
+
+        transport.send(Bytes::from_static(&[0x14])).await.unwrap();
+        assert_eq!(&device.next().await.unwrap().unwrap()[..], &[0x14]);
+
+        device.send(Bytes::from_static(&[0x00])).await.unwrap();
+        assert_eq!(&frames.next().await.unwrap().unwrap()[..], &[0x00]);
+
+        transport.disconnect().await.unwrap();
+        assert!(!transport.is_connected());
+        assert!(transport.connect().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_meshcore_over_duplex() {
+        let (host, _push) = testing::spawn(testing::self_info(0xAB, "duplex"), |request| {
+            match CommandOpcode::from_byte(request[0]) {
+                Some(CommandOpcode::GetBattery) => {
+                    vec![vec![PacketType::Battery as u8, 0xD4, 0x0D]]
+                }
+                _ => vec![vec![PacketType::Ok as u8]],
+            }
+        });
+
+        let mut client = MeshCore::stream(host);
+        let info = client.connect().await.unwrap();
+        assert_eq!(info.name, "duplex");
+
+        let battery = client.get_battery().await.unwrap();
+        assert_eq!(battery.millivolts, 3540);
+
+        client.disconnect().await.unwrap();
+    }
+}
@@ -84,6 +84,13 @@ impl std::fmt::Debug for PublicKey {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for PublicKey {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
 impl std::fmt::Display for PublicKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.to_hex())
@@ -92,6 +99,7 @@ impl std::fmt::Display for PublicKey {
 
 /// Contact flags.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ContactFlags(u8);
 
 impl ContactFlags {
@@ -126,6 +134,8 @@ impl ContactFlags {
 /// Device/contact type.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 #[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum ContactType {
     /// Unknown device type.
     #[default]
@@ -153,6 +163,7 @@ impl ContactType {
 
 /// Information about a contact.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Contact {
     /// The contact's public key.
     pub public_key: PublicKey,
@@ -163,6 +174,10 @@ pub struct Contact {
     /// Outbound path length (-1 means flood).
     pub out_path_len: i8,
     /// Outbound path data.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::types::serde_hex::serialize")
+    )]
     pub out_path: Bytes,
     /// Advertised name.
     pub name: String,
@@ -0,0 +1,406 @@
+//! Contact cards and `meshcore://` URIs.
+//!
+//! A contact card is a signed advertisement packet as exported by the device.
+//! The wire format is:
+//! ```text
+//! [header:1] [transport_codes:4 (transport routes only)] [path_len:1] [path:path_len]
+//! [pubkey:32] [timestamp:4LE] [signature:64] [flags:1]
+//! (if flags & 0x10: [lat:4LE] [lon:4LE]) (if flags & 0x20: [feature1:2LE])
+//! (if flags & 0x40: [feature2:2LE]) (if flags & 0x80: [name...])
+//! ```
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+use crate::error::{Error, Result};
+use crate::types::contact::{ContactType, PUBLIC_KEY_LEN, PublicKey};
+use crate::types::signature::{SIGNATURE_LEN, Signature};
+
+/// URI scheme prefix for contact cards.
+pub const CONTACT_URI_PREFIX: &str = "meshcore://";
+
+/// Payload type of an advertisement packet.
+const PAYLOAD_TYPE_ADVERT: u8 = 0x04;
+
+/// Route type bits in the packet header.
+const ROUTE_TYPE_MASK: u8 = 0x03;
+
+/// Route types that carry transport codes.
+const ROUTE_TYPE_TRANSPORT_FLOOD: u8 = 0x00;
+const ROUTE_TYPE_TRANSPORT_DIRECT: u8 = 0x03;
+
+/// Advertisement flag bits.
+const ADV_TYPE_MASK: u8 = 0x0F;
+const ADV_LATLON_MASK: u8 = 0x10;
+const ADV_FEAT1_MASK: u8 = 0x20;
+const ADV_FEAT2_MASK: u8 = 0x40;
+const ADV_NAME_MASK: u8 = 0x80;
+
+/// Coordinate scaling factor (multiply by 1e6 for storage).
+const COORD_SCALE: f64 = 1_000_000.0;
+
+/// A contact card (signed advertisement) that can be shared and imported.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ContactCard {
+    /// Packet header byte (route type, payload type and version).
+    pub header: u8,
+    /// Transport codes (only present for transport route types).
+    pub transport_codes: Option<[u16; 2]>,
+    /// Packet path.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::types::serde_hex::serialize")
+    )]
+    pub path: Bytes,
+    /// Advertiser's public key.
+    pub public_key: PublicKey,
+    /// Advertisement timestamp (Unix seconds).
+    pub timestamp: u32,
+    /// Ed25519 signature over public key, timestamp and app data.
+    pub signature: Signature,
+    /// Advertised device type bits (low nibble of the flags).
+    ///
+    /// Kept as sent, so types [`ContactType`] does not know survive
+    /// re-encoding; see [`Self::device_type`].
+    pub advert_type: u8,
+    /// Advertised latitude.
+    pub latitude: Option<f64>,
+    /// Advertised longitude.
+    pub longitude: Option<f64>,
+    /// Feature field 1 (reserved).
+    pub feature1: Option<u16>,
+    /// Feature field 2 (reserved).
+    pub feature2: Option<u16>,
+    /// Advertised name.
+    pub name: Option<String>,
+}
+
+impl ContactCard {
+    /// Parses a `meshcore://<hex>` contact URI.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URI scheme is wrong, the hex is invalid,
+    /// or the card cannot be decoded.
+    pub fn parse_uri(uri: &str) -> Result<Self> {
+        let hex = uri.trim().strip_prefix(CONTACT_URI_PREFIX).ok_or_else(|| {
+            Error::InvalidContactCard {
+                reason: format!("URI must start with {CONTACT_URI_PREFIX}"),
+            }
+        })?;
+        let data = hex::decode(hex).map_err(|e| Error::InvalidContactCard {
+            reason: format!("invalid hex: {e}"),
+        })?;
+        Self::from_bytes(&data)
+    }
+
+    /// Decodes a contact card from raw bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is truncated or is not an advertisement.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let too_short = || Error::InvalidContactCard {
+            reason: format!("card too short: {} bytes", data.len()),
+        };
+
+        let mut buf = data;
+        if buf.remaining() < 2 {
+            return Err(too_short());
+        }
+
+        let header = buf.get_u8();
+        let payload_type = (header >> 2) & 0x0F;
+        if payload_type != PAYLOAD_TYPE_ADVERT {
+            return Err(Error::InvalidContactCard {
+                reason: format!("not an advertisement: payload type {payload_type}"),
+            });
+        }
+
+        let route_type = header & ROUTE_TYPE_MASK;
+        let transport_codes = if route_type == ROUTE_TYPE_TRANSPORT_FLOOD
+            || route_type == ROUTE_TYPE_TRANSPORT_DIRECT
+        {
+            if buf.remaining() < 4 {
+                return Err(too_short());
+            }
+            Some([buf.get_u16_le(), buf.get_u16_le()])
+        } else {
+            None
+        };
+
+        if buf.remaining() < 1 {
+            return Err(too_short());
+        }
+        let path_len = usize::from(buf.get_u8());
+
+        // Path + pubkey + timestamp + signature + flags
+        if buf.remaining() < path_len + PUBLIC_KEY_LEN + 4 + SIGNATURE_LEN + 1 {
+            return Err(too_short());
+        }
+        let path = Bytes::copy_from_slice(&buf[..path_len]);
+        buf.advance(path_len);
+
+        let public_key = PublicKey::from_bytes(&buf[..PUBLIC_KEY_LEN]);
+        buf.advance(PUBLIC_KEY_LEN);
+        let timestamp = buf.get_u32_le();
+        let mut signature = [0u8; SIGNATURE_LEN];
+        buf.copy_to_slice(&mut signature);
+        let signature = Signature::new(signature);
+
+        let flags = buf.get_u8();
+        let advert_type = flags & ADV_TYPE_MASK;
+
+        let (latitude, longitude) = if flags & ADV_LATLON_MASK != 0 {
+            if buf.remaining() < 8 {
+                return Err(too_short());
+            }
+            let lat = f64::from(buf.get_i32_le()) / COORD_SCALE;
+            let lon = f64::from(buf.get_i32_le()) / COORD_SCALE;
+            (Some(lat), Some(lon))
+        } else {
+            (None, None)
+        };
+
+        let mut read_feature = |mask: u8| -> Result<Option<u16>> {
+            if flags & mask == 0 {
+                return Ok(None);
+            }
+            if buf.remaining() < 2 {
+                return Err(too_short());
+            }
+            Ok(Some(buf.get_u16_le()))
+        };
+        let feature1 = read_feature(ADV_FEAT1_MASK)?;
+        let feature2 = read_feature(ADV_FEAT2_MASK)?;
+
+        let name = if flags & ADV_NAME_MASK != 0 {
+            let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+            Some(String::from_utf8_lossy(&buf[..len]).into_owned())
+        } else {
+            None
+        };
+
+        Ok(Self {
+            header,
+            transport_codes,
+            path,
+            public_key,
+            timestamp,
+            signature,
+            advert_type,
+            latitude,
+            longitude,
+            feature1,
+            feature2,
+            name,
+        })
+    }
+
+    /// Returns the advertised device type.
+    #[must_use]
+    pub const fn device_type(&self) -> ContactType {
+        ContactType::from_byte(self.advert_type)
+    }
+
+    /// Returns the advertisement flags byte.
+    #[must_use]
+    pub fn flags(&self) -> u8 {
+        let mut flags = self.advert_type & ADV_TYPE_MASK;
+        if self.latitude.is_some() || self.longitude.is_some() {
+            flags |= ADV_LATLON_MASK;
+        }
+        if self.feature1.is_some() {
+            flags |= ADV_FEAT1_MASK;
+        }
+        if self.feature2.is_some() {
+            flags |= ADV_FEAT2_MASK;
+        }
+        if self.name.is_some() {
+            flags |= ADV_NAME_MASK;
+        }
+        flags
+    }
+
+    /// Returns the advertisement app data (flags and optional fields).
+    #[must_use]
+    pub fn app_data(&self) -> Bytes {
+        let mut buf = BytesMut::with_capacity(13 + self.name.as_ref().map_or(0, String::len));
+        buf.put_u8(self.flags());
+        if self.latitude.is_some() || self.longitude.is_some() {
+            buf.put_i32_le((self.latitude.unwrap_or(0.0) * COORD_SCALE).round() as i32);
+            buf.put_i32_le((self.longitude.unwrap_or(0.0) * COORD_SCALE).round() as i32);
+        }
+        if let Some(feature) = self.feature1 {
+            buf.put_u16_le(feature);
+        }
+        if let Some(feature) = self.feature2 {
+            buf.put_u16_le(feature);
+        }
+        if let Some(name) = &self.name {
+            buf.put_slice(name.as_bytes());
+        }
+        buf.freeze()
+    }
+
+    /// Returns the message covered by the signature (public key, timestamp, app data).
+    #[must_use]
+    pub fn signed_message(&self) -> Bytes {
+        let app_data = self.app_data();
+        let mut buf = BytesMut::with_capacity(PUBLIC_KEY_LEN + 4 + app_data.len());
+        buf.put_slice(self.public_key.as_bytes());
+        buf.put_u32_le(self.timestamp);
+        buf.put_slice(&app_data);
+        buf.freeze()
+    }
+
+    /// Verifies the advertisement signature against the card's public key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signature does not match.
+    #[cfg(feature = "ed25519")]
+    pub fn verify(&self) -> Result<()> {
+        self.signature
+            .verify(&self.public_key, &self.signed_message())
+    }
+
+    /// Encodes the card into raw bytes, suitable for `import_contact`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is longer than 255 bytes.
+    pub fn to_bytes(&self) -> Result<Bytes> {
+        let path_len = u8::try_from(self.path.len()).map_err(|_| Error::InvalidContactCard {
+            reason: format!("path too long: {} bytes", self.path.len()),
+        })?;
+        let app_data = self.app_data();
+        let mut buf = BytesMut::with_capacity(
+            6 + self.path.len() + PUBLIC_KEY_LEN + 4 + SIGNATURE_LEN + app_data.len(),
+        );
+        buf.put_u8(self.header);
+        if let Some([code1, code2]) = self.transport_codes {
+            buf.put_u16_le(code1);
+            buf.put_u16_le(code2);
+        }
+        buf.put_u8(path_len);
+        buf.put_slice(&self.path);
+        buf.put_slice(self.public_key.as_bytes());
+        buf.put_u32_le(self.timestamp);
+        buf.put_slice(self.signature.as_bytes());
+        buf.put_slice(&app_data);
+        Ok(buf.freeze())
+    }
+
+    /// Encodes the card as a `meshcore://<hex>` URI.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the card cannot be encoded, see [`Self::to_bytes`].
+    pub fn to_uri(&self) -> Result<String> {
+        Ok(format!(
+            "{CONTACT_URI_PREFIX}{}",
+            hex::encode(self.to_bytes()?)
+        ))
+    }
+}
+
+impl std::str::FromStr for ContactCard {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse_uri(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_card() -> ContactCard {
+        ContactCard {
+            header: 0x11, // flood route, advert payload
+            transport_codes: None,
+            path: Bytes::new(),
+            public_key: PublicKey::from_bytes(&[0xAB; 32]),
+            timestamp: 1_700_000_000,
+            signature: Signature::new([0x55; SIGNATURE_LEN]),
+            advert_type: ContactType::Repeater as u8,
+            latitude: Some(51.5),
+            longitude: Some(-0.125),
+            feature1: None,
+            feature2: None,
+            name: Some("Hilltop".into()),
+        }
+    }
+
+    #[test]
+    fn test_card_roundtrip() {
+        let card = sample_card();
+        let uri = card.to_uri().unwrap();
+        assert!(uri.starts_with(CONTACT_URI_PREFIX));
+
+        let parsed = ContactCard::parse_uri(&uri).unwrap();
+        assert_eq!(parsed, card);
+        assert_eq!(parsed.flags(), 0x92);
+    }
+
+    #[test]
+    fn test_card_field_layout() {
+        let bytes = sample_card().to_bytes().unwrap();
+        assert_eq!(bytes[0], 0x11);
+        assert_eq!(bytes[1], 0); // path_len
+        assert_eq!(&bytes[2..34], &[0xAB; 32]);
+        assert_eq!(&bytes[34..38], &1_700_000_000_u32.to_le_bytes());
+        assert_eq!(bytes[102], 0x92); // flags
+        assert_eq!(&bytes[111..], b"Hilltop");
+    }
+
+    #[test]
+    fn test_card_transport_codes() {
+        let mut card = sample_card();
+        card.header = 0x10; // transport flood
+        card.transport_codes = Some([1, 2]);
+        card.path = Bytes::from_static(&[0x01, 0x02]);
+
+        let parsed = ContactCard::from_bytes(&card.to_bytes().unwrap()).unwrap();
+        assert_eq!(parsed, card);
+    }
+
+    #[test]
+    fn test_card_keeps_unknown_type() {
+        // Sensor adverts (type 4) are not a `ContactType`
+        let mut bytes = sample_card().to_bytes().unwrap().to_vec();
+        bytes[102] = 0x94;
+
+        let card = ContactCard::from_bytes(&bytes).unwrap();
+        assert_eq!(card.advert_type, 4);
+        assert_eq!(card.device_type(), ContactType::Unknown);
+        assert_eq!(card.flags(), 0x94);
+        assert_eq!(&card.to_bytes().unwrap()[..], &bytes[..]);
+        assert_eq!(card.signed_message()[36], 0x94);
+    }
+
+    #[test]
+    fn test_card_path_too_long() {
+        let mut card = sample_card();
+        card.path = Bytes::from(vec![0; 256]);
+        assert!(matches!(
+            card.to_bytes(),
+            Err(Error::InvalidContactCard { .. })
+        ));
+        assert!(card.to_uri().is_err());
+    }
+
+    #[test]
+    fn test_card_invalid() {
+        assert!(ContactCard::parse_uri("https://example.com").is_err());
+        assert!(ContactCard::parse_uri("meshcore://zz").is_err());
+        assert!(ContactCard::parse_uri("meshcore://1100").is_err());
+
+        // Not an advertisement (payload type 2)
+        let mut bytes = sample_card().to_bytes().unwrap().to_vec();
+        bytes[0] = 0x09;
+        assert!(ContactCard::from_bytes(&bytes).is_err());
+    }
+}
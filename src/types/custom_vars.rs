@@ -0,0 +1,215 @@
+//! Custom variables exposed by device firmware (sensor settings etc.).
+//!
+//! The device reports custom variables as a `key:value,key:value` string.
+
+use std::time::Duration;
+
+use crate::error::{Error, Result};
+
+/// GPS enable variable (`"1"` = on, `"0"` = off).
+pub const VAR_GPS: &str = "gps";
+
+/// GPS update interval variable (seconds).
+pub const VAR_GPS_INTERVAL: &str = "gps_interval";
+
+/// Ordered map of custom variables.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CustomVars {
+    entries: Vec<(String, String)>,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for CustomVars {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_map(self.entries.iter().map(|(k, v)| (k, v)))
+    }
+}
+
+impl CustomVars {
+    /// Creates an empty set of custom variables.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Parses a `key:value,key:value` string.
+    ///
+    /// Entries without a `:` separator are ignored.
+    #[must_use]
+    pub fn parse(data: &str) -> Self {
+        let entries = data
+            .split(',')
+            .filter_map(|kv| kv.split_once(':'))
+            .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+            .filter(|(k, _)| !k.is_empty())
+            .collect();
+        Self { entries }
+    }
+
+    /// Checks that a key/value pair can be encoded without corrupting the list.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key is empty or either part contains `:` or `,`.
+    pub fn validate(key: &str, value: &str) -> Result<()> {
+        if key.is_empty() {
+            return Err(Error::InvalidCustomVar {
+                reason: "key is empty".into(),
+            });
+        }
+        for (what, s) in [("key", key), ("value", value)] {
+            if s.contains([':', ',']) {
+                return Err(Error::InvalidCustomVar {
+                    reason: format!("{what} {s:?} contains ':' or ','"),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the raw value of a variable.
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns a variable parsed into the requested type.
+    ///
+    /// Returns `None` if the variable is missing or does not parse.
+    #[must_use]
+    pub fn get_parsed<V: std::str::FromStr>(&self, key: &str) -> Option<V> {
+        self.get(key).and_then(|v| v.parse().ok())
+    }
+
+    /// Sets a variable, keeping its position if it already exists.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key or value is invalid (see [`Self::validate`]).
+    pub fn set(&mut self, key: &str, value: impl Into<String>) -> Result<()> {
+        let value = value.into();
+        Self::validate(key, &value)?;
+        self.insert(key, value);
+        Ok(())
+    }
+
+    /// Sets a variable without validation.
+    fn insert(&mut self, key: &str, value: String) {
+        if let Some(entry) = self.entries.iter_mut().find(|(k, _)| k == key) {
+            entry.1 = value;
+        } else {
+            self.entries.push((key.to_string(), value));
+        }
+    }
+
+    /// Removes a variable, returning its value.
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let pos = self.entries.iter().position(|(k, _)| k == key)?;
+        Some(self.entries.remove(pos).1)
+    }
+
+    /// Iterates over all variables in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Returns the number of variables.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if there are no variables.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the entries of `other` whose values differ from (or are missing in) `self`.
+    #[must_use]
+    pub fn diff<'a>(&self, other: &'a Self) -> Vec<(&'a str, &'a str)> {
+        other
+            .iter()
+            .filter(|(k, v)| self.get(k) != Some(v))
+            .collect()
+    }
+
+    /// Returns whether GPS is enabled.
+    #[must_use]
+    pub fn gps_enabled(&self) -> Option<bool> {
+        self.get(VAR_GPS).map(|v| v != "0")
+    }
+
+    /// Enables or disables GPS.
+    pub fn set_gps_enabled(&mut self, enabled: bool) {
+        self.insert(VAR_GPS, if enabled { "1" } else { "0" }.into());
+    }
+
+    /// Returns the GPS update interval.
+    #[must_use]
+    pub fn gps_interval(&self) -> Option<Duration> {
+        self.get_parsed(VAR_GPS_INTERVAL).map(Duration::from_secs)
+    }
+
+    /// Sets the GPS update interval (whole seconds).
+    pub fn set_gps_interval(&mut self, interval: Duration) {
+        self.insert(VAR_GPS_INTERVAL, interval.as_secs().to_string());
+    }
+}
+
+impl std::fmt::Display for CustomVars {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (k, v)) in self.entries.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{k}:{v}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_custom_vars() {
+        let vars = CustomVars::parse("gps:1,gps_interval:300,name:foo");
+        assert_eq!(vars.len(), 3);
+        assert_eq!(vars.gps_enabled(), Some(true));
+        assert_eq!(vars.gps_interval(), Some(Duration::from_secs(300)));
+        assert_eq!(vars.get("name"), Some("foo"));
+        assert_eq!(vars.to_string(), "gps:1,gps_interval:300,name:foo");
+
+        assert!(CustomVars::parse("").is_empty());
+    }
+
+    #[test]
+    fn test_set_and_diff() {
+        let current = CustomVars::parse("gps:0,gps_interval:300");
+        let mut desired = current.clone();
+        desired.set_gps_enabled(true);
+        desired.set("mode", "fast").unwrap();
+
+        assert_eq!(current.diff(&desired), vec![("gps", "1"), ("mode", "fast")]);
+        assert!(desired.diff(&desired).is_empty());
+    }
+
+    #[test]
+    fn test_reject_separators() {
+        let mut vars = CustomVars::new();
+        assert!(vars.set("a:b", "1").is_err());
+        assert!(vars.set("a", "1,2").is_err());
+        assert!(vars.set("", "1").is_err());
+        assert!(vars.is_empty());
+    }
+}
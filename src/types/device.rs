@@ -4,6 +4,7 @@ use crate::types::contact::PublicKey;
 
 /// Telemetry mode configuration.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TelemetryMode {
     /// Environment telemetry mode (upper 2 bits).
     pub env: u8,
@@ -33,6 +34,7 @@ impl TelemetryMode {
 
 /// Radio configuration parameters.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct RadioConfig {
     /// Frequency in MHz.
     pub frequency_mhz: f64,
@@ -57,6 +59,7 @@ impl Default for RadioConfig {
 
 /// Self device information returned after `AppStart`.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct SelfInfo {
     /// Advertisement type.
     pub advert_type: u8,
@@ -86,6 +89,7 @@ pub struct SelfInfo {
 
 /// Device information returned by `DeviceQuery`.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct DeviceInfo {
     /// Firmware version.
     pub firmware_version: u8,
@@ -105,6 +109,7 @@ pub struct DeviceInfo {
 
 /// Battery status.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct BatteryStatus {
     /// Battery voltage in millivolts.
     pub millivolts: u16,
@@ -116,11 +121,16 @@ pub struct BatteryStatus {
 
 /// Channel configuration.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Channel {
     /// Channel index (0-based).
     pub index: u8,
     /// Channel name (up to 32 bytes).
     pub name: String,
     /// Channel secret (16 bytes).
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::types::serde_hex::serialize")
+    )]
     pub secret: [u8; 16],
 }
@@ -0,0 +1,71 @@
+//! Node discovery response types.
+
+use bytes::Bytes;
+
+use crate::types::contact::{ContactType, PublicKey};
+
+/// Identity of a node as reported in a discovery response.
+///
+/// Depending on the `prefix_only` flag of the request, nodes answer either
+/// with a public key prefix or with their full public key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum DiscoveredKey {
+    /// Public key prefix (typically 8 bytes).
+    Prefix(
+        #[cfg_attr(
+            feature = "serde",
+            serde(serialize_with = "crate::types::serde_hex::serialize")
+        )]
+        Bytes,
+    ),
+    /// Full 32-byte public key.
+    Full(PublicKey),
+}
+
+impl DiscoveredKey {
+    /// Returns the key bytes (prefix or full key).
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Prefix(prefix) => prefix,
+            Self::Full(key) => key.as_bytes(),
+        }
+    }
+
+    /// Returns true if this identity matches the given public key.
+    #[must_use]
+    pub fn matches(&self, key: &PublicKey) -> bool {
+        match self {
+            Self::Prefix(prefix) => key.as_bytes().starts_with(prefix),
+            Self::Full(full) => full == key,
+        }
+    }
+
+    /// Returns the key as a hex string.
+    #[must_use]
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.as_bytes())
+    }
+}
+
+/// A response to a node discovery request.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DiscoverResponse {
+    /// Type of the responding node.
+    pub node_type: ContactType,
+    /// SNR of the response as received by this device, in dB.
+    pub snr: f32,
+    /// RSSI of the response as received by this device, in dBm.
+    pub rssi: i8,
+    /// SNR of the request as received by the responding node, in dB.
+    pub snr_in: f32,
+    /// Path length of the response.
+    pub path_len: u8,
+    /// Tag of the request this response belongs to.
+    pub tag: u32,
+    /// Identity of the responding node.
+    pub key: DiscoveredKey,
+}
@@ -0,0 +1,32 @@
+//! Login responses from room servers and repeaters.
+
+/// Permission bit granting admin access.
+pub const PERM_ADMIN: u8 = 0x01;
+
+/// A successful login to a room server or repeater.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LoginResponse {
+    /// 6-byte public key prefix of the server.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::types::serde_hex::serialize")
+    )]
+    pub pubkey_prefix: [u8; 6],
+    /// Permission bits (bit 0 = admin).
+    pub permissions: u8,
+    /// Server clock at login (Unix seconds), if reported.
+    pub server_time: Option<u32>,
+    /// Access control list permissions, if reported.
+    pub acl_permissions: Option<u8>,
+    /// Server firmware version level, if reported.
+    pub firmware_level: Option<u8>,
+}
+
+impl LoginResponse {
+    /// Returns true if the login grants admin access.
+    #[must_use]
+    pub const fn is_admin(&self) -> bool {
+        self.permissions & PERM_ADMIN != 0
+    }
+}
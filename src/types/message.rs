@@ -3,6 +3,8 @@
 /// Text type indicating message format.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 #[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum TextType {
     /// Plain text message.
     #[default]
@@ -30,6 +32,7 @@ impl TextType {
 /// Note: v3 message format only includes SNR, not RSSI.
 /// The 2 bytes after SNR are reserved (always 0x00).
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct SignalQuality {
     /// Signal-to-noise ratio in dB (raw value divided by 4).
     pub snr: f32,
@@ -37,8 +40,13 @@ pub struct SignalQuality {
 
 /// A received message from a contact (private message).
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ContactMessage {
     /// 6-byte public key prefix of the sender.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::types::serde_hex::serialize")
+    )]
     pub sender_prefix: [u8; 6],
     /// Path length.
     pub path_len: i8,
@@ -47,6 +55,10 @@ pub struct ContactMessage {
     /// Sender's timestamp (Unix seconds).
     pub timestamp: u32,
     /// Message signature (if `text_type` is `Signed`).
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::types::serde_hex::option")
+    )]
     pub signature: Option<Vec<u8>>,
     /// Message text.
     pub text: String,
@@ -56,6 +68,7 @@ pub struct ContactMessage {
 
 /// A received message from a channel.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ChannelMessage {
     /// Channel index.
     pub channel_index: u8,
@@ -72,6 +85,7 @@ pub struct ChannelMessage {
 }
 /// Acknowledgment received for a sent message.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Acknowledgment {
     /// ACK code matching the expected ACK from the message send response.
     pub code: u32,
@@ -2,19 +2,42 @@
 //!
 //! This module contains the core data structures used throughout the library:
 //! - Contacts and public keys
+//! - Contact cards (shareable signed adverts)
 //! - Device information
+//! - Custom variables
+//! - Node discovery responses
+//! - Login responses
 //! - Messages
+//! - Signatures
+//! - Received packet log entries
 //! - Statistics
 //! - Telemetry
+//! - Trace results
 
 pub mod contact;
+pub mod contact_card;
+pub mod custom_vars;
 pub mod device;
+pub mod discovery;
+pub mod login;
 pub mod message;
+pub mod rx_log;
+#[cfg(feature = "serde")]
+pub(crate) mod serde_hex;
+pub mod signature;
 pub mod stats;
 pub mod telemetry;
+pub mod trace;
 
 pub use contact::{Contact, ContactFlags, ContactType, PublicKey};
+pub use contact_card::ContactCard;
+pub use custom_vars::CustomVars;
 pub use device::{BatteryStatus, Channel, DeviceInfo, RadioConfig, SelfInfo, TelemetryMode};
+pub use discovery::{DiscoverResponse, DiscoveredKey};
+pub use login::LoginResponse;
 pub use message::{Acknowledgment, ChannelMessage, ContactMessage, SignalQuality, TextType};
+pub use rx_log::{PayloadType, RouteType, RxLogEntry};
+pub use signature::Signature;
 pub use stats::{CoreStats, DeviceStatus, PacketStats, RadioStats, StatsType};
 pub use telemetry::{Telemetry, TelemetryReading, TelemetryValue};
+pub use trace::TraceResult;
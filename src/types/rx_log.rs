@@ -0,0 +1,128 @@
+//! Log entries for packets received over the air.
+
+/// Route type of a mesh packet (lower two header bits).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[repr(u8)]
+pub enum RouteType {
+    /// Flood routing with transport codes.
+    TransportFlood = 0,
+    /// Flood routing.
+    Flood = 1,
+    /// Direct routing along a known path.
+    Direct = 2,
+    /// Direct routing with transport codes.
+    TransportDirect = 3,
+}
+
+impl RouteType {
+    /// Extracts the route type from a packet header.
+    #[must_use]
+    pub const fn from_header(header: u8) -> Self {
+        match header & 0x03 {
+            0 => Self::TransportFlood,
+            1 => Self::Flood,
+            2 => Self::Direct,
+            _ => Self::TransportDirect,
+        }
+    }
+
+    /// Returns true if the packet carries transport codes.
+    #[must_use]
+    pub const fn has_transport_codes(self) -> bool {
+        matches!(self, Self::TransportFlood | Self::TransportDirect)
+    }
+
+    /// Returns true for flood routing.
+    #[must_use]
+    pub const fn is_flood(self) -> bool {
+        matches!(self, Self::TransportFlood | Self::Flood)
+    }
+}
+
+/// Payload type of a mesh packet (header bits 2-5).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum PayloadType {
+    /// Request to a node.
+    Request,
+    /// Response to a request.
+    Response,
+    /// Private text message.
+    TextMessage,
+    /// Acknowledgment.
+    Ack,
+    /// Node advertisement.
+    Advert,
+    /// Channel text message.
+    GroupText,
+    /// Channel binary data.
+    GroupData,
+    /// Anonymous request (e.g. login).
+    AnonRequest,
+    /// Returned path.
+    Path,
+    /// Trace packet.
+    Trace,
+    /// Part of a multipart packet.
+    Multipart,
+    /// Control packet, e.g. node discovery.
+    Control,
+    /// Application-defined raw packet.
+    RawCustom,
+    /// Unknown payload type.
+    Unknown(u8),
+}
+
+impl PayloadType {
+    /// Extracts the payload type from a packet header.
+    #[must_use]
+    pub const fn from_header(header: u8) -> Self {
+        match (header >> 2) & 0x0F {
+            0x00 => Self::Request,
+            0x01 => Self::Response,
+            0x02 => Self::TextMessage,
+            0x03 => Self::Ack,
+            0x04 => Self::Advert,
+            0x05 => Self::GroupText,
+            0x06 => Self::GroupData,
+            0x07 => Self::AnonRequest,
+            0x08 => Self::Path,
+            0x09 => Self::Trace,
+            0x0A => Self::Multipart,
+            0x0B => Self::Control,
+            0x0F => Self::RawCustom,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+/// A packet received over the air, as logged by the device.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RxLogEntry {
+    /// SNR of the packet in dB.
+    pub snr: f32,
+    /// RSSI of the packet in dBm.
+    pub rssi: i8,
+    /// Route type.
+    pub route_type: RouteType,
+    /// Payload type.
+    pub payload_type: PayloadType,
+    /// Payload format version (header bits 6-7).
+    pub payload_version: u8,
+    /// Repeater hashes the packet travelled through.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::types::serde_hex::serialize")
+    )]
+    pub path: Vec<u8>,
+    /// Packet payload (encrypted for most payload types).
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::types::serde_hex::serialize")
+    )]
+    pub payload: Vec<u8>,
+}
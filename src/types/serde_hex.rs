@@ -0,0 +1,21 @@
+//! Serialisation of binary fields as hex strings.
+
+use serde::Serializer;
+
+/// Serialises bytes as a lowercase hex string.
+pub fn serialize<S: Serializer>(bytes: impl AsRef<[u8]>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&hex::encode(bytes))
+}
+
+/// Serialises optional bytes as a hex string or `null`.
+// The signature is dictated by `serialize_with`
+#[allow(clippy::ref_option)]
+pub fn option<S: Serializer, T: AsRef<[u8]>>(
+    bytes: &Option<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match bytes {
+        Some(bytes) => serialize(bytes, serializer),
+        None => serializer.serialize_none(),
+    }
+}
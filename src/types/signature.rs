@@ -0,0 +1,118 @@
+//! Ed25519 signatures produced by the device.
+
+use crate::error::{Error, Result};
+#[cfg(feature = "ed25519")]
+use crate::types::contact::PublicKey;
+
+/// Length of an Ed25519 signature in bytes.
+pub const SIGNATURE_LEN: usize = 64;
+
+/// An Ed25519 signature.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Signature([u8; SIGNATURE_LEN]);
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Signature {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl Signature {
+    /// Creates a signature from its 64 raw bytes.
+    #[must_use]
+    pub const fn new(bytes: [u8; SIGNATURE_LEN]) -> Self {
+        Self(bytes)
+    }
+
+    /// Tries to create a signature from bytes.
+    ///
+    /// Returns `None` if the slice is not exactly 64 bytes.
+    #[must_use]
+    pub fn try_from_bytes(bytes: &[u8]) -> Option<Self> {
+        bytes.try_into().ok().map(Self)
+    }
+
+    /// Returns the signature as a byte slice.
+    #[must_use]
+    pub const fn as_bytes(&self) -> &[u8; SIGNATURE_LEN] {
+        &self.0
+    }
+
+    /// Returns the signature as a hex string.
+    #[must_use]
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    /// Verifies this signature over `message` against a public key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the public key is not a valid Ed25519 point
+    /// or the signature does not match.
+    #[cfg(feature = "ed25519")]
+    pub fn verify(&self, public_key: &PublicKey, message: &[u8]) -> Result<()> {
+        use ed25519_dalek::{Signature as DalekSignature, Verifier, VerifyingKey};
+
+        let mut key_bytes = [0u8; 32];
+        key_bytes.copy_from_slice(public_key.as_bytes());
+        let key = VerifyingKey::from_bytes(&key_bytes).map_err(|e| Error::InvalidPublicKey {
+            reason: e.to_string(),
+        })?;
+        key.verify(message, &DalekSignature::from_bytes(&self.0))
+            .map_err(|_| Error::InvalidSignature {
+                reason: format!("signature does not match key {public_key}"),
+            })
+    }
+}
+
+impl TryFrom<&[u8]> for Signature {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self> {
+        Self::try_from_bytes(bytes).ok_or_else(|| Error::InvalidSignature {
+            reason: format!("expected {SIGNATURE_LEN} bytes, got {}", bytes.len()),
+        })
+    }
+}
+
+impl std::fmt::Debug for Signature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Signature({}...)", &self.to_hex()[..12])
+    }
+}
+
+impl std::fmt::Display for Signature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_hex())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_from_bytes() {
+        let sig = Signature::try_from(&[0xAA; 64][..]).unwrap();
+        assert_eq!(sig.as_bytes(), &[0xAA; 64]);
+        assert!(Signature::try_from(&[0xAA; 63][..]).is_err());
+    }
+
+    #[cfg(feature = "ed25519")]
+    #[test]
+    fn test_signature_verify() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+        let public_key = PublicKey::from_bytes(signing_key.verifying_key().as_bytes());
+        let sig = Signature::new(signing_key.sign(b"manifest").to_bytes());
+
+        assert!(sig.verify(&public_key, b"manifest").is_ok());
+        assert!(sig.verify(&public_key, b"tampered").is_err());
+    }
+}
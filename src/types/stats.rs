@@ -3,6 +3,8 @@
 /// Statistics type identifier.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum StatsType {
     /// Core device statistics.
     Core = 0,
@@ -27,6 +29,7 @@ impl StatsType {
 
 /// Core device statistics.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct CoreStats {
     /// Battery voltage in millivolts.
     pub battery_mv: u16,
@@ -40,6 +43,7 @@ pub struct CoreStats {
 
 /// Radio statistics.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct RadioStats {
     /// Noise floor in dBm.
     pub noise_floor: i16,
@@ -55,6 +59,7 @@ pub struct RadioStats {
 
 /// Packet statistics.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PacketStats {
     /// Total packets received.
     pub received: u32,
@@ -72,8 +77,13 @@ pub struct PacketStats {
 
 /// Full device status (from binary status request or status response).
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct DeviceStatus {
     /// 6-byte public key prefix.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::types::serde_hex::serialize")
+    )]
     pub pubkey_prefix: [u8; 6],
     /// Battery voltage in millivolts.
     pub battery_mv: u16,
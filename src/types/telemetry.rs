@@ -6,6 +6,11 @@ use std::collections::HashMap;
 
 /// A single telemetry value.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "type", content = "value", rename_all = "snake_case")
+)]
 pub enum TelemetryValue {
     /// Digital input (0 or 1).
     DigitalInput(u8),
@@ -58,11 +63,18 @@ pub enum TelemetryValue {
     /// Unix timestamp.
     UnixTime(u32),
     /// Generic value.
-    Generic(Vec<u8>),
+    Generic(
+        #[cfg_attr(
+            feature = "serde",
+            serde(serialize_with = "crate::types::serde_hex::serialize")
+        )]
+        Vec<u8>,
+    ),
 }
 
 /// A telemetry reading with channel and type info.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TelemetryReading {
     /// Channel number.
     pub channel: u8,
@@ -74,6 +86,7 @@ pub struct TelemetryReading {
 
 /// Collection of telemetry readings.
 #[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Telemetry {
     /// All readings keyed by channel.
     pub readings: Vec<TelemetryReading>,
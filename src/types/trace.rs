@@ -0,0 +1,30 @@
+//! Trace path results.
+
+/// Result of a trace through a list of repeaters.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TraceResult {
+    /// Tag of the trace request.
+    pub tag: u32,
+    /// Authentication code of the trace request.
+    pub auth_code: u32,
+    /// Trace flags.
+    pub flags: u8,
+    /// Hashes of the repeaters the trace passed, in order.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::types::serde_hex::serialize")
+    )]
+    pub path: Vec<u8>,
+    /// SNR in dB measured at each hop, ending with the SNR of the
+    /// returning trace at this device.
+    pub snrs: Vec<f32>,
+}
+
+impl TraceResult {
+    /// Returns the SNR measured by this device on the way back.
+    #[must_use]
+    pub fn final_snr(&self) -> Option<f32> {
+        self.snrs.last().copied()
+    }
+}